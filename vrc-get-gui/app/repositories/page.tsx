@@ -19,19 +19,24 @@ import {useQuery} from "@tanstack/react-query";
 import {
 	environmentAddRepository,
 	environmentDownloadRepository,
+	environmentExportRepositories,
 	environmentHideRepository,
+	environmentImportRepositories,
 	environmentRemoveRepository,
 	environmentRepositoriesInfo,
+	environmentRepositoryNews,
 	environmentShowRepository,
+	TauriPackageNewsKind,
 	TauriRemoteRepositoryInfo,
 	TauriUserRepository
 } from "@/lib/bindings";
 import {HNavBar, VStack} from "@/components/layout";
 import React, {Suspense, useMemo, useState} from "react";
-import {XCircleIcon} from "@heroicons/react/24/outline";
+import {ExclamationTriangleIcon, XCircleIcon} from "@heroicons/react/24/outline";
 import {nop} from "@/lib/nop";
 import {useTranslation} from "react-i18next";
-import {toastError, toastSuccess, toastThrownError} from "@/lib/toast";
+import {toastError, toastNormal, toastSuccess, toastThrownError} from "@/lib/toast";
+import {toVersionString} from "@/lib/version";
 
 export default function Page(props: {}) {
 	return <Suspense><PageBody {...props}/></Suspense>
@@ -55,6 +60,7 @@ type State = {
 function PageBody() {
 	const {t} = useTranslation();
 	const [state, setState] = useState<State>({type: 'normal'});
+	const [showNews, setShowNews] = useState(false);
 
 	const result = useQuery({
 		queryKey: ["environmentRepositoriesInfo"],
@@ -104,6 +110,52 @@ function PageBody() {
 		}
 	}
 
+	async function exportRepositories() {
+		try {
+			// headers often have secrets so they are redacted for sharing
+			switch (await environmentExportRepositories(true)) {
+				case "NoFileSelected":
+					break;
+				case "Successful":
+					toastSuccess(t("exported repositories successfully!"));
+					break;
+			}
+		} catch (e) {
+			toastThrownError(e);
+		}
+	}
+
+	async function importRepositories() {
+		try {
+			const imported = await environmentImportRepositories();
+			switch (imported.type) {
+				case "NoFileSelected":
+					break;
+				case "ParseError":
+					toastError(t("failed to read the repository list: {{message}}", {message: imported.message}));
+					break;
+				case "Successful":
+					for (const failed of imported.failed) {
+						toastError(t("failed to add {{name}}: {{message}}", failed));
+					}
+					for (const redacted of imported.redacted) {
+						toastNormal(t("headers of {{name}} are redacted and not added: {{headers}}", {
+							name: redacted.name,
+							headers: redacted.headers.join(", "),
+						}));
+					}
+					toastSuccess(t("imported repositories: {{added}} added, {{skipped}} already added", {
+						added: imported.added.length,
+						skipped: imported.skipped.length,
+					}));
+					await result.refetch();
+					break;
+			}
+		} catch (e) {
+			toastThrownError(e);
+		}
+	}
+
 	let dialogBody;
 	switch (state.type) {
 		case "normal":
@@ -148,6 +200,9 @@ function PageBody() {
 				<Typography className="cursor-pointer py-1.5 font-bold flex-grow-0">
 					{t("community repositories")}
 				</Typography>
+				<Button onClick={() => setShowNews(true)} variant={"outlined"}>{t("what's new")}</Button>
+				<Button onClick={importRepositories} variant={"outlined"}>{t("import repositories")}</Button>
+				<Button onClick={exportRepositories} variant={"outlined"}>{t("export repositories")}</Button>
 				<Button onClick={() => setState({type: 'enteringRepositoryInfo'})}>{t("add repository")}</Button>
 			</HNavBar>
 			<main className="flex-shrink flex-grow overflow-hidden flex">
@@ -159,6 +214,7 @@ function PageBody() {
 						refetch={() => result.refetch()}
 					/>
 					{dialog}
+					{showNews && <RepositoryNews close={() => setShowNews(false)}/>}
 				</Card>
 			</main>
 		</VStack>
@@ -227,6 +283,7 @@ function RepositoryRow(
 		refetch: () => void,
 	}
 ) {
+	const {t} = useTranslation();
 	const cellClass = "p-2.5";
 	const id = `repository-${repo.id}`;
 
@@ -246,10 +303,16 @@ function RepositoryRow(
 									checked={selected} onChange={onChange}/>
 			</td>
 			<td className={cellClass}>
-				<label htmlFor={id}>
+				<label htmlFor={id} className={"flex items-center gap-1"}>
 					<Typography className="font-normal">
 						{repo.display_name}
 					</Typography>
+					{repo.fetch_status?.type === "Failed" &&
+						<Tooltip content={repo.fetch_status.cache_used
+							? t("failed to fetch the repository, using the cached one: {{error}}", {error: repo.fetch_status.error})
+							: t("failed to fetch the repository: {{error}}", {error: repo.fetch_status.error})}>
+							<ExclamationTriangleIcon className={"size-5 text-orange-700"}/>
+						</Tooltip>}
 				</label>
 			</td>
 			<td className={cellClass}>
@@ -379,3 +442,62 @@ function Confirming(
 		</>
 	);
 }
+
+function RepositoryNews(
+	{
+		close,
+	}: {
+		close: () => void,
+	}
+) {
+	const {t} = useTranslation();
+
+	const result = useQuery({
+		queryKey: ["environmentRepositoryNews"],
+		queryFn: environmentRepositoryNews,
+	})
+
+	const kindLabel = (kind: TauriPackageNewsKind) => {
+		switch (kind) {
+			case "NewPackage":
+				return t("new package");
+			case "NewVersion":
+				return t("new version");
+			case "Yanked":
+				return t("yanked");
+			case "Removed":
+				return t("removed");
+			default:
+				const _exhaustiveCheck: never = kind;
+		}
+	}
+
+	const news = result.data ?? [];
+
+	return (
+		<Dialog handler={nop} open>
+			<DialogHeader>{t("what's new")}</DialogHeader>
+			<DialogBody className={"max-h-[60vh] overflow-y-auto"}>
+				{result.isLoading ? <Typography>{t("loading...")}</Typography>
+					: news.length === 0 ? <Typography>{t("no changes of repositories found yet")}</Typography>
+						: news.map((news, idx) => (
+							<div key={idx} className={"mb-3"}>
+								<Typography className={"font-bold"}>
+									{news.repository_name ?? news.repository_id} ({new Date(news.fetched_at).toLocaleString()})
+								</Typography>
+								<List className={"p-0"}>
+									{news.changes.map((change, idx) => (
+										<ListItem key={idx} className={"py-1"}>
+											{kindLabel(change.kind)}: {change.name} {toVersionString(change.version)}
+										</ListItem>
+									))}
+								</List>
+							</div>
+						))}
+			</DialogBody>
+			<DialogFooter>
+				<Button onClick={close}>{t("ok")}</Button>
+			</DialogFooter>
+		</Dialog>
+	);
+}
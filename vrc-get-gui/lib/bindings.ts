@@ -50,6 +50,10 @@ export function environmentShowRepository(repository: string) {
     return invoke()<null>("environment_show_repository", { repository })
 }
 
+export function environmentRepositoryNews() {
+    return invoke()<TauriRepositoryNews[]>("environment_repository_news")
+}
+
 export function environmentSetHideLocalUserPackages(value: boolean) {
     return invoke()<null>("environment_set_hide_local_user_packages", { value })
 }
@@ -90,6 +94,14 @@ export function environmentRemoveRepository(id: string) {
     return invoke()<null>("environment_remove_repository", { id })
 }
 
+export function environmentExportRepositories(redactHeaders: boolean) {
+    return invoke()<TauriExportRepositoriesResult>("environment_export_repositories", { redactHeaders })
+}
+
+export function environmentImportRepositories() {
+    return invoke()<TauriImportRepositoriesResult>("environment_import_repositories")
+}
+
 export function environmentProjectCreationInformation() {
     return invoke()<TauriProjectCreationInformation>("environment_project_creation_information")
 }
@@ -166,6 +178,10 @@ export type TauriFinalizeMigrationWithUnity2022 = { type: "NoUnity2022Found" } |
 export type TauriPickUnityHubResult = "NoFolderSelected" | "InvalidSelection" | "Successful"
 export type TauriBeforeMigrateProjectTo2022Result = { type: "NoUnity2022Found" } | { type: "ConfirmNotExactlyRecommendedUnity2022"; found: string; recommended: string } | { type: "ReadyToMigrate" }
 export type TauriAddRepositoryResult = "BadUrl" | "Success"
+export type TauriExportRepositoriesResult = "NoFileSelected" | "Successful"
+export type TauriImportRepositoriesResult = { type: "NoFileSelected" } | { type: "ParseError"; message: string } | { type: "Successful"; added: string[]; skipped: string[]; failed: TauriImportRepositoryError[]; redacted: TauriRedactedHeaders[] }
+export type TauriImportRepositoryError = { name: string; message: string }
+export type TauriRedactedHeaders = { name: string; headers: string[] }
 export type TauriProjectType = "Unknown" | "LegacySdk2" | "LegacyWorlds" | "LegacyAvatars" | "UpmWorlds" | "UpmAvatars" | "UpmStarter" | "Worlds" | "Avatars" | "VpmStarter"
 export type TauriOpenUnityResult = "NoUnityVersionForTheProject" | "NoMatchingUnityFound" | "Success"
 export type TauriPickProjectDefaultPathResult = { type: "NoFolderSelected" } | { type: "InvalidSelection" } | { type: "Successful"; new_path: string }
@@ -187,5 +203,9 @@ export type TauriDownloadRepository = { type: "BadUrl" } | { type: "Duplicated"
 export type TauriConflictInfo = { packages: string[]; unity_conflict: boolean }
 export type TauriVersion = { major: number; minor: number; patch: number; pre: string; build: string }
 export type TauriPackageSource = "LocalUser" | { Remote: { id: string; display_name: string } }
-export type TauriUserRepository = { id: string; url: string | null; display_name: string }
+export type TauriUserRepository = { id: string; url: string | null; display_name: string; fetch_status: TauriRepositoryFetchStatus | null }
+export type TauriRepositoryFetchStatus = { type: "Updated" } | { type: "NotModified" } | { type: "NotFetched" } | { type: "Failed"; error: string; cache_used: boolean }
+export type TauriRepositoryNews = { repository_id: string; repository_name: string | null; fetched_at: string; changes: TauriPackageNews[] }
+export type TauriPackageNews = { kind: TauriPackageNewsKind; name: string; version: TauriVersion }
+export type TauriPackageNewsKind = "NewPackage" | "NewVersion" | "Yanked" | "Removed"
 export type TauriPackageChange = { InstallNew: TauriBasePackageInfo } | { Remove: TauriRemoveReason }
@@ -25,7 +25,10 @@ use tokio::process::{Child, Command};
 use futures::prelude::*;
 
 use crate::config::GuiConfigHolder;
-use vrc_get_vpm::environment::UserProject;
+use vrc_get_vpm::environment::{
+    AddRepositoryErr, PackageNewsKind, RepositoryFetchStatus, RepositoryList, RepositoryNews,
+    UserProject,
+};
 use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo, DirEntry, EnvironmentIo, IoTrait};
 use vrc_get_vpm::repository::RemoteRepository;
 use vrc_get_vpm::unity_project::pending_project_changes::{
@@ -52,6 +55,7 @@ pub(crate) fn handlers<R: Runtime>() -> impl Fn(Invoke<R>) + Send + Sync + 'stat
         environment_repositories_info,
         environment_hide_repository,
         environment_show_repository,
+        environment_repository_news,
         environment_set_hide_local_user_packages,
         environment_get_settings,
         environment_pick_unity_hub,
@@ -62,6 +66,8 @@ pub(crate) fn handlers<R: Runtime>() -> impl Fn(Invoke<R>) + Send + Sync + 'stat
         environment_download_repository,
         environment_add_repository,
         environment_remove_repository,
+        environment_export_repositories,
+        environment_import_repositories,
         environment_project_creation_information,
         environment_check_project_name,
         environment_create_project,
@@ -96,6 +102,7 @@ pub(crate) fn export_ts() {
             environment_repositories_info,
             environment_hide_repository,
             environment_show_repository,
+            environment_repository_news,
             environment_set_hide_local_user_packages,
             environment_get_settings,
             environment_pick_unity_hub,
@@ -106,6 +113,8 @@ pub(crate) fn export_ts() {
             environment_download_repository,
             environment_add_repository,
             environment_remove_repository,
+            environment_export_repositories,
+            environment_import_repositories,
             environment_project_creation_information,
             environment_check_project_name,
             environment_create_project,
@@ -841,6 +850,30 @@ struct TauriUserRepository {
     id: String,
     url: Option<String>,
     display_name: String,
+    fetch_status: Option<TauriRepositoryFetchStatus>,
+}
+
+#[derive(Serialize, specta::Type)]
+#[serde(tag = "type")]
+enum TauriRepositoryFetchStatus {
+    Updated,
+    NotModified,
+    NotFetched,
+    Failed { error: String, cache_used: bool },
+}
+
+impl From<&RepositoryFetchStatus> for TauriRepositoryFetchStatus {
+    fn from(value: &RepositoryFetchStatus) -> Self {
+        match value {
+            RepositoryFetchStatus::Updated => Self::Updated,
+            RepositoryFetchStatus::NotModified => Self::NotModified,
+            RepositoryFetchStatus::Failed { error, cache_used } => Self::Failed {
+                error: error.to_string(),
+                cache_used: *cache_used,
+            },
+            _ => Self::NotFetched,
+        }
+    }
 }
 
 #[derive(Serialize, specta::Type)]
@@ -867,6 +900,9 @@ async fn environment_repositories_info(
                         id: id.to_string(),
                         url: x.url().map(|x| x.to_string()),
                         display_name: x.name().unwrap_or(id).to_string(),
+                        fetch_status: environment
+                            .get_repository_fetch_status(x.local_path())
+                            .map(Into::into),
                     }
                 })
                 .collect(),
@@ -903,6 +939,70 @@ async fn environment_show_repository(
     })
 }
 
+#[derive(Serialize, specta::Type)]
+struct TauriRepositoryNews {
+    repository_id: String,
+    repository_name: Option<String>,
+    fetched_at: String,
+    changes: Vec<TauriPackageNews>,
+}
+
+#[derive(Serialize, specta::Type)]
+struct TauriPackageNews {
+    kind: TauriPackageNewsKind,
+    name: String,
+    version: TauriVersion,
+}
+
+#[derive(Serialize, specta::Type)]
+enum TauriPackageNewsKind {
+    NewPackage,
+    NewVersion,
+    Yanked,
+    Removed,
+}
+
+impl From<&RepositoryNews> for TauriRepositoryNews {
+    fn from(value: &RepositoryNews) -> Self {
+        Self {
+            repository_id: value.repository_id().to_string(),
+            repository_name: value.repository_name().map(|x| x.to_string()),
+            fetched_at: value.fetched_at().to_rfc3339(),
+            changes: value
+                .changes()
+                .iter()
+                .map(|x| TauriPackageNews {
+                    kind: match x.kind() {
+                        PackageNewsKind::NewPackage => TauriPackageNewsKind::NewPackage,
+                        PackageNewsKind::NewVersion => TauriPackageNewsKind::NewVersion,
+                        PackageNewsKind::Yanked => TauriPackageNewsKind::Yanked,
+                        _ => TauriPackageNewsKind::Removed,
+                    },
+                    name: x.name().to_string(),
+                    version: x.version().into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns changes of repositories found by refreshing repositories, the newest first.
+#[tauri::command]
+#[specta::specta]
+async fn environment_repository_news(
+    state: State<'_, Mutex<EnvironmentState>>,
+) -> Result<Vec<TauriRepositoryNews>, RustError> {
+    with_environment!(&state, |environment| {
+        Ok(environment
+            .get_repository_news()
+            .await?
+            .iter()
+            .rev()
+            .map(Into::into)
+            .collect())
+    })
+}
+
 #[tauri::command]
 #[specta::specta]
 async fn environment_set_hide_local_user_packages(
@@ -1279,8 +1379,9 @@ async fn environment_download_repository(
             }
         }
 
-        let client = environment.http().unwrap();
-        let repo = match RemoteRepository::download(client, &url, &headers.0).await {
+        let client = environment.http();
+        let io = environment.io();
+        let repo = match RemoteRepository::download(client, io, &url, &headers.0, None).await {
             Ok((repo, _)) => repo,
             Err(e) => {
                 return Ok(TauriDownloadRepository::DownloadError {
@@ -1335,7 +1436,9 @@ async fn environment_add_repository(
     };
 
     with_environment!(&state, |environment| {
-        environment.add_remote_repo(url, None, headers.0).await?;
+        environment
+            .add_remote_repo(url, None, headers.0, Vec::new(), None, false)
+            .await?;
         environment.save().await?;
     });
 
@@ -1359,6 +1462,121 @@ async fn environment_remove_repository(
     Ok(())
 }
 
+#[derive(Serialize, specta::Type)]
+enum TauriExportRepositoriesResult {
+    NoFileSelected,
+    Successful,
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn environment_export_repositories(
+    state: State<'_, Mutex<EnvironmentState>>,
+    redact_headers: bool,
+) -> Result<TauriExportRepositoriesResult, RustError> {
+    let json = with_environment!(&state, |environment| {
+        environment.export_repositories(redact_headers).to_json()?
+    });
+
+    let Some(path) = FileDialogBuilder::new()
+        .add_filter("JSON", &["json"])
+        .set_file_name("repositories.json")
+        .save_file()
+    else {
+        return Ok(TauriExportRepositoriesResult::NoFileSelected);
+    };
+
+    tokio::fs::write(path, json).await?;
+
+    Ok(TauriExportRepositoriesResult::Successful)
+}
+
+#[derive(Serialize, specta::Type)]
+#[serde(tag = "type")]
+enum TauriImportRepositoriesResult {
+    NoFileSelected,
+    ParseError {
+        message: String,
+    },
+    Successful {
+        added: Vec<String>,
+        skipped: Vec<String>,
+        failed: Vec<TauriImportRepositoryError>,
+        redacted: Vec<TauriRedactedHeaders>,
+    },
+}
+
+#[derive(Serialize, specta::Type)]
+struct TauriImportRepositoryError {
+    name: String,
+    message: String,
+}
+
+#[derive(Serialize, specta::Type)]
+struct TauriRedactedHeaders {
+    name: String,
+    headers: Vec<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn environment_import_repositories(
+    state: State<'_, Mutex<EnvironmentState>>,
+) -> Result<TauriImportRepositoriesResult, RustError> {
+    let Some(path) = FileDialogBuilder::new()
+        .add_filter("JSON", &["json"])
+        .pick_file()
+    else {
+        return Ok(TauriImportRepositoriesResult::NoFileSelected);
+    };
+
+    let list = match RepositoryList::parse(&tokio::fs::read(path).await?) {
+        Ok(list) => list,
+        Err(e) => {
+            return Ok(TauriImportRepositoriesResult::ParseError {
+                message: e.to_string(),
+            })
+        }
+    };
+
+    let mut added = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    let mut redacted = Vec::new();
+
+    with_environment!(&state, |environment| {
+        for repo in list.repositories() {
+            let name = repo.name().unwrap_or(repo.url().as_str()).to_string();
+            match environment.import_repository(repo).await {
+                Ok(()) => {
+                    let headers = repo.redacted_headers().map(Into::into).collect::<Vec<_>>();
+                    if !headers.is_empty() {
+                        redacted.push(TauriRedactedHeaders {
+                            name: name.clone(),
+                            headers,
+                        });
+                    }
+                    added.push(name);
+                }
+                Err(AddRepositoryErr::AlreadyAdded) => skipped.push(name),
+                Err(e) => failed.push(TauriImportRepositoryError {
+                    name,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        environment.save().await?;
+    });
+
+    Ok(TauriImportRepositoriesResult::Successful {
+        added,
+        skipped,
+        failed,
+        redacted,
+    })
+}
+
 #[derive(Serialize, Deserialize, specta::Type)]
 #[serde(tag = "type")]
 enum TauriProjectTemplate {
@@ -1860,13 +2078,15 @@ async fn project_upgrade_multiple_package(
     package_indices: Vec<usize>,
 ) -> Result<TauriPendingProjectChanges, RustError> {
     changes!(state, env_version, |environment, packages| {
+        let unity_project = load_project(project_path).await?;
+
+        // held packages are not upgraded with upgrading multiple packages
         let installing_packages = package_indices
             .iter()
             .map(|index| packages[*index])
+            .filter(|package| !unity_project.is_held(package.name()))
             .collect::<Vec<_>>();
 
-        let unity_project = load_project(project_path).await?;
-
         let operation = AddPackageOperation::UpgradeLocked;
 
         let allow_prerelease = environment.show_prerelease_packages();
@@ -1,5 +1,8 @@
+mod package_filter;
 mod repo_holder;
 mod repo_source;
+mod repository_list;
+mod repository_news;
 mod settings;
 mod uesr_package_collection;
 mod vrc_get_settings;
@@ -14,10 +17,12 @@ mod unity_management;
 use crate::io;
 use crate::io::SeekFrom;
 use crate::repository::local::LocalCachedRepository;
-use crate::repository::RemoteRepository;
 use crate::structs::setting::UserRepoSetting;
 use crate::traits::{EnvironmentIoHolder, HttpClient, PackageCollection, RemotePackageDownloader};
-use crate::utils::{to_vec_pretty_os_eol, Sha256AsyncWrite};
+use crate::utils::{
+    file_url_path, is_allowed_in_repo, is_file_url, offline_error, resolve_headers,
+    to_vec_pretty_os_eol, Sha256AsyncWrite,
+};
 use crate::{PackageInfo, PackageManifest, VersionSelector};
 use futures::future::{join_all, try_join};
 use futures::prelude::*;
@@ -26,6 +31,7 @@ use indexmap::IndexMap;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use log::{error, warn};
+use sha2::{Digest, Sha256};
 use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::ffi::{OsStr, OsString};
@@ -33,16 +39,23 @@ use std::fmt;
 use std::fs::remove_file;
 use std::path::{Path, PathBuf};
 use std::pin::pin;
+use std::time::Duration;
 use url::Url;
 
 use crate::environment::vrc_get_settings::VrcGetSettings;
 use crate::io::{DirEntry, EnvironmentIo};
+pub(crate) use package_filter::PackageFilter;
 #[cfg(feature = "experimental-project-management")]
 pub use project_management::*;
 pub(crate) use repo_holder::RepoHolder;
+pub use repo_holder::RepositoryFetchStatus;
 pub(crate) use repo_source::RepoSource;
+use repository_list::is_env_reference;
+pub use repository_list::{ExportedRepository, RepositoryList, REDACTED_HEADER_VALUE};
+pub use repository_news::{PackageNews, PackageNewsKind, RepositoryNews};
 pub(crate) use settings::Settings;
 pub(crate) use uesr_package_collection::UserPackageCollection;
+pub(crate) use vrc_get_settings::RepositorySettings;
 
 const OFFICIAL_URL_STR: &str = "https://packages.vrchat.com/official?download";
 const LOCAL_OFFICIAL_PATH: &str = "Repos/vrc-official.json";
@@ -112,6 +125,8 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
                 LOCAL_OFFICIAL_PATH.as_ref(),
                 &EMPTY_HEADERS,
                 Some(&OFFICIAL_URL),
+                &[],
+                None,
             ));
         } else {
             warn!("ignoring official repository is experimental feature!");
@@ -122,6 +137,8 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
                 LOCAL_CURATED_PATH.as_ref(),
                 &EMPTY_HEADERS,
                 Some(&CURATED_URL),
+                &[],
+                None,
             ));
         } else {
             warn!("ignoring curated repository is experimental feature!");
@@ -133,15 +150,24 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
     pub async fn load_package_infos(&mut self, update: bool) -> io::Result<()> {
         let http = if update { self.http.as_ref() } else { None };
         let predefined_repos = self.get_predefined_repos().into_iter();
-        let user_repos = self
-            .settings
-            .user_repos()
-            .iter()
-            .map(UserRepoSetting::to_source);
+        let vrc_get_settings = &self.vrc_get_settings;
+        let user_repos = (self.settings.user_repos().iter())
+            .map(|repo| repo.to_source(vrc_get_settings.repository(repo)));
         self.io.create_dir_all("Repos".as_ref()).await?;
         self.repo_cache
-            .load_repos(http, &self.io, predefined_repos.chain(user_repos))
+            .load_repos(
+                http,
+                &self.io,
+                predefined_repos.chain(user_repos),
+                self.vrc_get_settings.repository_timeout(),
+            )
             .await?;
+        let news = self.repo_cache.take_news();
+        if !news.is_empty() {
+            repository_news::save_news(&self.io, news)
+                .await
+                .unwrap_or_else(|e| error!("saving repository news: {}", e));
+        }
         self.update_user_repo_id();
         self.load_user_package_infos().await?;
         self.remove_id_duplication();
@@ -228,9 +254,10 @@ impl<T: HttpClient, IO: EnvironmentIo> PackageCollection for Environment<T, IO>
     }
 
     fn find_packages(&self, package: &str) -> impl Iterator<Item = PackageInfo> {
-        self.repo_cache
+        // user packages are preferred over remote packages with the same version
+        self.user_packages
             .find_packages(package)
-            .chain(self.user_packages.find_packages(package))
+            .chain(self.repo_cache.find_packages(package))
     }
 
     fn find_package_by_name(
@@ -245,7 +272,8 @@ impl<T: HttpClient, IO: EnvironmentIo> PackageCollection for Environment<T, IO>
             .user_packages
             .find_package_by_name(package, package_selector);
 
-        return local.into_iter().chain(user).max_by_key(|x| x.version());
+        // user packages are preferred over remote packages with the same version
+        return package_selector.select_preferred(user.into_iter().chain(local), |x| x.version());
     }
 }
 
@@ -270,9 +298,10 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
         let mut list = Vec::new();
 
         self.get_repos()
-            .flat_map(|(_, repo)| {
+            .flat_map(|(path, repo)| {
                 repo.get_packages()
                     .filter_map(|packages| packages.get_latest(version_selector))
+                    .filter(|json| self.repo_cache.allows_package(path, json.name()))
                     .map(|json| PackageInfo::remote(json, repo))
             })
             .filter(|x| filter(x.package_json()))
@@ -296,19 +325,192 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
         self.settings.user_repos()
     }
 
+    /// Returns the priority of the user repository. See [`set_repo_priority`](Self::set_repo_priority)
+    pub fn get_repo_priority(&self, repo: &UserRepoSetting) -> i32 {
+        self.vrc_get_settings.repository(repo).priority()
+    }
+
+    /// Sets the priority of the user repositories matching `condition`.
+    ///
+    /// If multiple repositories have the same version of the package, the one with higher priority is used.
+    /// Returns the number of matched repositories.
+    pub fn set_repo_priority(
+        &mut self,
+        condition: impl Fn(&UserRepoSetting) -> bool,
+        priority: i32,
+    ) -> usize {
+        let matched = (self.settings.user_repos().iter())
+            .filter(|x| condition(x))
+            .collect::<Vec<_>>();
+
+        for repo in &matched {
+            (self.vrc_get_settings).set_repository_priority(repo, priority);
+            self.repo_cache.set_priority(repo.local_path(), priority);
+        }
+
+        matched.len()
+    }
+
+    /// Sets the package name patterns the user repositories matching `condition` may provide.
+    ///
+    /// Patterns may contain `*` which matches any sequence of characters.
+    /// If `allowed` is empty, all packages not matching `denied` are allowed.
+    /// Returns the number of matched repositories.
+    pub fn set_repo_package_filter(
+        &mut self,
+        condition: impl Fn(&UserRepoSetting) -> bool,
+        allowed: Vec<Box<str>>,
+        denied: Vec<Box<str>>,
+    ) -> usize {
+        let filter = PackageFilter::new(&allowed, &denied);
+        let matched = (self.settings.user_repos().iter())
+            .filter(|x| condition(x))
+            .collect::<Vec<_>>();
+
+        for repo in &matched {
+            (self.vrc_get_settings).set_repository_package_filter(
+                repo,
+                allowed.clone(),
+                denied.clone(),
+            );
+            (self.repo_cache).set_package_filter(repo.local_path(), filter.clone());
+        }
+
+        matched.len()
+    }
+
+    /// Exports the user repositories to share with others.
+    ///
+    /// Local repositories without url are not included since they are not portable.
+    /// If `redact_headers` is true, header values are replaced with [`REDACTED_HEADER_VALUE`]
+    /// unless the value is exactly one `${env:NAME}` reference. `${cmd:COMMAND}` values are redacted too.
+    pub fn export_repositories(&self, redact_headers: bool) -> RepositoryList {
+        let repos = self.get_user_repos().iter().map(|repo| {
+            // headers are saved to the repository cache when added by vrc-get
+            let headers = match self.repo_cache.get_repo(repo.local_path()) {
+                Some(cache) if repo.headers().is_empty() => cache.headers(),
+                _ => repo.headers(),
+            };
+            (repo, self.vrc_get_settings.repository(repo), headers)
+        });
+        RepositoryList::from_user_repos(repos, redact_headers)
+    }
+
+    /// Adds the repository in the [`RepositoryList`] with its options.
+    ///
+    /// This returns [`AddRepositoryErr::AlreadyAdded`] without fetching the repository
+    /// if the repository with the same url or id is already added.
+    ///
+    /// Header values exactly one `${env:NAME}` reference, which are kept on export with redaction,
+    /// are resolved with the environment variables of this machine.
+    /// Since the list may be made by others, this returns [`AddRepositoryErr::HeaderReference`]
+    /// if other header values look like references, which may run commands.
+    pub async fn import_repository(
+        &mut self,
+        repo: &ExportedRepository,
+    ) -> Result<(), AddRepositoryErr> {
+        if repo.is_added_to(self.get_user_repos()) {
+            return Err(AddRepositoryErr::AlreadyAdded);
+        }
+
+        let headers = repo.headers();
+        if let Some((name, _)) =
+            (headers.iter()).find(|(_, value)| value.contains("${") && !is_env_reference(value))
+        {
+            return Err(AddRepositoryErr::HeaderReference { name: name.clone() });
+        }
+        let header_references = headers.values().any(|value| is_env_reference(value));
+
+        self.add_remote_repo(
+            repo.url().clone(),
+            repo.name(),
+            headers,
+            repo.mirrors().to_vec(),
+            repo.public_key(),
+            header_references,
+        )
+        .await?;
+
+        let is_imported = |x: &UserRepoSetting| x.url() == Some(repo.url());
+        self.set_repo_priority(is_imported, repo.priority());
+        self.set_repo_package_filter(
+            is_imported,
+            repo.allowed_packages().to_vec(),
+            repo.denied_packages().to_vec(),
+        );
+
+        Ok(())
+    }
+
+    fn find_user_repo(&self, repository: &LocalCachedRepository) -> Option<&UserRepoSetting> {
+        let user_repos = self.get_user_repos();
+        // the id and url in the repository come from the downloaded index,
+        // so the cached repository of each user repository is compared first
+        (user_repos.iter())
+            .find(|x| self.is_cached_repo(x.local_path(), repository))
+            .or_else(|| {
+                let id = repository.id().or(repository.url().map(Url::as_str))?;
+                user_repos.iter().find(|x| x.id() == Some(id))
+            })
+    }
+
+    fn is_cached_repo(&self, path: &Path, repository: &LocalCachedRepository) -> bool {
+        (self.repo_cache.get_repo(path)).is_some_and(|x| std::ptr::eq(x, repository))
+    }
+
+    /// Returns the url the repository is fetched from.
+    ///
+    /// The url in the repository may be declared by the downloaded index, so the url configured
+    /// for the user or predefined repository is used instead if the repository is known.
+    fn repository_source_url<'a>(
+        &'a self,
+        repository: &'a LocalCachedRepository,
+        user_repo: Option<&'a UserRepoSetting>,
+    ) -> Option<&'a Url> {
+        if let Some(user_repo) = user_repo {
+            return user_repo.url();
+        }
+        (self.get_predefined_repos().into_iter())
+            .find(|x| self.is_cached_repo(x.cache_path(), repository))
+            .map_or(repository.url(), |x| x.url())
+    }
+
+    /// Adds the remote repository.
+    ///
+    /// If `header_references` is true, `${env:NAME}` and `${cmd:COMMAND}` in header values are resolved
+    /// when requests are made. This must only be true if the user specified the headers on this machine.
     pub async fn add_remote_repo(
         &mut self,
         url: Url,
         name: Option<&str>,
         headers: IndexMap<Box<str>, Box<str>>,
+        mirrors: Vec<Url>,
+        public_key: Option<&str>,
+        header_references: bool,
     ) -> Result<(), AddRepositoryErr> {
         let user_repos = self.get_user_repos();
         if user_repos.iter().any(|x| x.url() == Some(&url)) {
             return Err(AddRepositoryErr::AlreadyAdded);
         }
-        let http = self.http.as_ref().ok_or(AddRepositoryErr::OfflineMode)?;
+        // file:// repositories can be added even in offline mode
+        if self.http.is_none() && !is_file_url(&url) {
+            return Err(AddRepositoryErr::OfflineMode);
+        }
 
-        let (remote_repo, etag) = RemoteRepository::download(http, &url, &headers).await?;
+        let fetched = repo_holder::fetch_from_mirrors(
+            self.http.as_ref(),
+            &self.io,
+            &url,
+            &mirrors,
+            &*resolve_headers(&headers, header_references).await?,
+            None,
+            public_key,
+            self.repository_timeout(),
+        )
+        .await?;
+        let Some((remote_repo, etag)) = fetched else {
+            unreachable!("downloading without etag must return Some");
+        };
         let repo_name = name.or(remote_repo.name()).map(Into::into);
 
         let repo_id = remote_repo.id().map(Into::into);
@@ -334,14 +536,21 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
 
         let file_name = self.write_new_repo(&local_cache).await?;
 
-        self.settings.add_user_repo(UserRepoSetting::new(
+        let repo = UserRepoSetting::new(
             self.io
                 .resolve(format!("{}/{}", REPO_CACHE_FOLDER, file_name).as_ref())
                 .into_boxed_path(),
             repo_name,
             Some(url),
             repo_id,
-        ));
+        );
+        (self.vrc_get_settings).set_repository_mirrors(&repo, mirrors);
+        if let Some(public_key) = public_key {
+            (self.vrc_get_settings).set_repository_public_key(&repo, Some(public_key.into()));
+        }
+        // saved in vrc-get settings since settings.json may be copied from other machines
+        (self.vrc_get_settings).set_repository_header_references(&repo, header_references);
+        self.settings.add_user_repo(repo);
         Ok(())
     }
 
@@ -410,6 +619,7 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
 
         for x in &removed {
             self.repo_cache.remove_repo(x.local_path());
+            self.vrc_get_settings.remove_repository(x);
         }
 
         join_all(removed.iter().map(|x| async move {
@@ -502,6 +712,51 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
         self.settings.set_unity_hub(value);
     }
 
+    /// Returns true if remote packages without `zipSHA256` in the repository are refused.
+    pub fn require_zip_sha256(&self) -> bool {
+        self.vrc_get_settings.require_zip_sha256()
+    }
+
+    pub fn set_require_zip_sha256(&mut self, value: bool) {
+        self.vrc_get_settings.set_require_zip_sha256(value);
+    }
+
+    /// Returns the timeout for fetching each remote repository.
+    pub fn repository_timeout(&self) -> Duration {
+        self.vrc_get_settings.repository_timeout()
+    }
+
+    /// Returns the prefix-based rewrites of package zip urls. The first matching prefix is replaced.
+    pub fn url_rewrites(&self) -> &IndexMap<Box<str>, Box<str>> {
+        self.vrc_get_settings.url_rewrites()
+    }
+
+    /// Sets the replacement of the package zip urls starting with `prefix`. `None` removes the rewrite.
+    pub fn set_url_rewrite(&mut self, prefix: &str, replacement: Option<&str>) {
+        self.vrc_get_settings.set_url_rewrite(prefix, replacement);
+    }
+
+    /// Sets the timeout for fetching each remote repository. `None` resets to the default.
+    ///
+    /// The timeout is saved in seconds, and at least one second.
+    pub fn set_repository_timeout(&mut self, value: Option<Duration>) {
+        self.vrc_get_settings.set_repository_timeout(value);
+    }
+
+    /// Returns the result of fetching the repository in the last [`load_package_infos`].
+    ///
+    /// [`load_package_infos`]: Environment::load_package_infos
+    pub fn get_repository_fetch_status(&self, local_path: &Path) -> Option<&RepositoryFetchStatus> {
+        self.repo_cache.get_fetch_status(local_path)
+    }
+
+    /// Returns the changes of repositories found by refreshing repositories, the oldest first.
+    ///
+    /// Only the recent changes are kept.
+    pub async fn get_repository_news(&self) -> io::Result<Vec<RepositoryNews>> {
+        repository_news::load_news(&self.io).await
+    }
+
     pub fn http(&self) -> Option<&T> {
         self.http.as_ref()
     }
@@ -520,6 +775,44 @@ impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
     }
 }
 
+impl<T: HttpClient, IO: EnvironmentIo> Environment<T, IO> {
+    /// Adds the package zip to the cache so the package can be installed without downloading.
+    ///
+    /// This is used to install packages from [`ProjectBundle`](crate::unity_project::ProjectBundle).
+    /// Returns error if the zip doesn't match `zipSHA256` of the package.
+    pub async fn add_package_to_cache(
+        &self,
+        package: &PackageManifest,
+        zip: &[u8],
+    ) -> io::Result<()> {
+        let hash = Sha256::digest(zip);
+        if let Some(repo_hash) = parse_zip_sha256(package)? {
+            if repo_hash[..] != hash[..] {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "sha256 of {} version {} mismatched",
+                        package.name(),
+                        package.version()
+                    ),
+                ));
+            }
+        }
+
+        let (zip_path, sha_path) = package_cache_paths(package)?;
+        let zip_file_name = zip_path.file_name().unwrap().to_string_lossy();
+
+        self.io.create_dir_all(zip_path.parent().unwrap()).await?;
+        self.io.write(&zip_path, zip).await?;
+        self.io
+            .write(
+                &sha_path,
+                format!("{} {}\n", hex::encode(hash), zip_file_name).as_bytes(),
+            )
+            .await
+    }
+}
+
 impl<T: HttpClient, IO: EnvironmentIo> RemotePackageDownloader for Environment<T, IO> {
     type FileStream = IO::FileStream;
 
@@ -528,55 +821,181 @@ impl<T: HttpClient, IO: EnvironmentIo> RemotePackageDownloader for Environment<T
         repository: &LocalCachedRepository,
         package: &PackageManifest,
     ) -> io::Result<Self::FileStream> {
-        let zip_file_name = format!("vrc-get-{}-{}.zip", &package.name(), package.version());
-        let zip_path = PathBuf::from(format!(
-            "{}/{}/{}",
-            REPO_CACHE_FOLDER,
-            package.name(),
-            &zip_file_name
-        ));
-        let sha_path = zip_path.with_extension("zip.sha256");
+        let (zip_path, sha_path) = package_cache_paths(package)?;
+
+        if package.zip_sha_256().is_none() && self.require_zip_sha256() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} version {} has no zipSHA256 in the repository",
+                    package.name(),
+                    package.version()
+                ),
+            ));
+        }
+
+        let repo_hash = parse_zip_sha256(package)?;
 
         if let Some(cache_file) =
-            try_load_package_cache(&self.io, &zip_path, &sha_path, package.zip_sha_256()).await
+            try_load_package_cache(&self.io, &zip_path, &sha_path, repo_hash.as_ref()).await
         {
             Ok(cache_file)
         } else {
             self.io.create_dir_all(zip_path.parent().unwrap()).await?;
 
+            let url = package.url().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "URL field of the package.json in the repository empty",
+                )
+            })?;
+
+            let user_repo = self.find_user_repo(repository);
+            let settings = user_repo.map(|x| self.vrc_get_settings.repository(x));
+            let mirrors = settings
+                .map(RepositorySettings::mirrors)
+                .unwrap_or_default();
+            let header_references = settings.is_some_and(RepositorySettings::header_references);
+            let headers = resolve_headers(repository.headers(), header_references).await?;
+
+            let urls = package_zip_urls(
+                url,
+                self.repository_source_url(repository, user_repo),
+                mirrors,
+                self.vrc_get_settings.url_rewrites(),
+            );
+
+            if urls.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "{} version {} is at local file url '{}' in remote repository",
+                        package.name(),
+                        package.version(),
+                        url
+                    ),
+                ));
+            }
+
             Ok(download_package_zip(
                 self.http.as_ref(),
                 &self.io,
-                repository.headers(),
+                &headers,
                 &zip_path,
                 &sha_path,
-                &zip_file_name,
-                package.url().ok_or_else(|| {
-                    io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "URL field of the package.json in the repository empty",
-                    )
-                })?,
+                &urls,
+                repo_hash.as_ref(),
             )
             .await?)
         }
     }
 }
 
+/// Returns `zipSHA256` of the package. Malformed `zipSHA256` is an error not to skip verification.
+fn parse_zip_sha256(package: &PackageManifest) -> io::Result<Option<[u8; 256 / 8]>> {
+    let Some(sha256) = package.zip_sha_256() else {
+        return Ok(None);
+    };
+    FromHex::from_hex(sha256).map(Some).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "zipSHA256 of {} version {} is not valid: {sha256}",
+                package.name(),
+                package.version()
+            ),
+        )
+    })
+}
+
+/// Returns the paths of the cached zip and its sha256 file of the package.
+///
+/// The name comes from repositories or bundles, so names which can escape the cache folder are rejected.
+fn package_cache_paths(package: &PackageManifest) -> io::Result<(PathBuf, PathBuf)> {
+    let name = package.name();
+    if name.is_empty() || name.contains(['/', '\\', ':']) || name.contains("..") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid package name: {name}"),
+        ));
+    }
+
+    let zip_file_name = format!("vrc-get-{}-{}.zip", &package.name(), package.version());
+    let zip_path = PathBuf::from(format!(
+        "{}/{}/{}",
+        REPO_CACHE_FOLDER,
+        package.name(),
+        &zip_file_name
+    ));
+    let sha_path = zip_path.with_extension("zip.sha256");
+    Ok((zip_path, sha_path))
+}
+
+/// Returns the urls to download the package zip from, in the order to try,
+/// with whether to send the headers of the repository to the url.
+///
+/// Urls rewritten with `url_rewrites` come first, then the original url.
+/// The original url is skipped if it's a `file://` url listed in a remote repository.
+/// If the zip is placed next to the repository, the same path on each mirror follows.
+///
+/// The headers may have credentials for the repository, so they are sent to rewritten urls and
+/// mirrors only if they have the same origin as the original url or the repository.
+fn package_zip_urls(
+    zip_url: &Url,
+    repo_url: Option<&Url>,
+    mirrors: &[Url],
+    url_rewrites: &IndexMap<Box<str>, Box<str>>,
+) -> Vec<(Url, bool)> {
+    let mut urls = Vec::new();
+
+    let send_headers = |url: &Url| {
+        url.origin() == zip_url.origin() || repo_url.is_some_and(|x| url.origin() == x.origin())
+    };
+
+    if let Some((prefix, replacement)) = url_rewrites
+        .iter()
+        .find(|(prefix, _)| zip_url.as_str().starts_with(prefix.as_ref()))
+    {
+        let rewritten = format!("{}{}", replacement, &zip_url.as_str()[prefix.len()..]);
+        match Url::parse(&rewritten) {
+            Ok(url) => urls.push((url.clone(), send_headers(&url))),
+            Err(e) => warn!("rewriting url '{}': {}", zip_url, e),
+        }
+    }
+
+    if is_allowed_in_repo(zip_url, repo_url) {
+        urls.push((zip_url.clone(), true));
+    } else {
+        warn!("ignoring local file url '{}' in remote repository", zip_url);
+    }
+
+    if let Some(repo_base) = repo_url.and_then(|x| x.join("./").ok()) {
+        if let Some(relative) = zip_url.as_str().strip_prefix(repo_base.as_str()) {
+            for mirror in mirrors {
+                if let Ok(url) = mirror.join("./").and_then(|x| x.join(relative)) {
+                    urls.push((url.clone(), send_headers(&url)));
+                }
+            }
+        }
+    }
+
+    urls.into_iter().unique_by(|(url, _)| url.clone()).collect()
+}
+
 /// Try to load from the zip file
 ///
 /// # Arguments
 ///
 /// * `zip_path`: the path to zip file
 /// * `sha_path`: the path to sha256 file
-/// * `sha256`: sha256 hash if specified
+/// * `repo_hash`: sha256 hash if specified
 ///
 /// returns: Option<File> readable zip file or None
 async fn try_load_package_cache<IO: EnvironmentIo>(
     io: &IO,
     zip_path: &Path,
     sha_path: &Path,
-    sha256: Option<&str>,
+    repo_hash: Option<&[u8; 256 / 8]>,
 ) -> Option<IO::FileStream> {
     let mut cache_file = io.open(zip_path).await.ok()?;
 
@@ -591,8 +1010,8 @@ async fn try_load_package_cache<IO: EnvironmentIo>(
     let hex: [u8; 256 / 8] = FromHex::from_hex(buf).ok()?;
 
     // if stored sha doesn't match sha in repo: current cache is invalid
-    if let Some(repo_hash) = sha256.and_then(|x| <[u8; 256 / 8] as FromHex>::from_hex(x).ok()) {
-        if repo_hash != hex {
+    if let Some(repo_hash) = repo_hash {
+        if repo_hash != &hex {
             return None;
         }
     }
@@ -618,8 +1037,8 @@ async fn try_load_package_cache<IO: EnvironmentIo>(
 /// * `http`: http client. returns error if none
 /// * `zip_path`: the path to zip file
 /// * `sha_path`: the path to sha256 file
-/// * `zip_file_name`: the name of zip file. will be used in the sha file
-/// * `url`: url to zip file
+/// * `urls`: the urls to download the zip from with whether to send `headers`. tried in order until one succeeds
+/// * `repo_hash`: `zipSHA256` of the package if specified. the downloaded file is removed if it doesn't match
 ///
 /// returns: Result<File, Error> the readable zip file.
 async fn download_package_zip<IO: EnvironmentIo>(
@@ -628,24 +1047,71 @@ async fn download_package_zip<IO: EnvironmentIo>(
     headers: &IndexMap<Box<str>, Box<str>>,
     zip_path: &Path,
     sha_path: &Path,
-    zip_file_name: &str,
-    url: &Url,
+    urls: &[(Url, bool)],
+    repo_hash: Option<&[u8; 256 / 8]>,
 ) -> io::Result<IO::FileStream> {
-    let Some(http) = http else {
+    // packages at file:// urls can be copied even in offline mode
+    if http.is_none() && !urls.iter().any(|(url, _)| is_file_url(url)) {
         return Err(io::Error::new(io::ErrorKind::NotFound, "Offline mode"));
-    };
+    }
 
-    // file not found: err
-    let cache_file = io.create(zip_path).await?;
+    let no_headers = IndexMap::new();
+    let mut urls = urls.iter().peekable();
+    loop {
+        let (url, send_headers) = urls.next().expect("no url to download");
+        let headers = if *send_headers { headers } else { &no_headers };
+        match download_package_zip_from(http, io, headers, zip_path, sha_path, url, repo_hash).await
+        {
+            Err(e) if urls.peek().is_some() => {
+                warn!("downloading '{}' failed, trying next url: {}", url, e);
+            }
+            result => return result,
+        }
+    }
+}
 
-    let mut response = pin!(http.get(url, headers).await?);
+async fn download_package_zip_from<IO: EnvironmentIo>(
+    http: Option<&impl HttpClient>,
+    io: &IO,
+    headers: &IndexMap<Box<str>, Box<str>>,
+    zip_path: &Path,
+    sha_path: &Path,
+    url: &Url,
+    repo_hash: Option<&[u8; 256 / 8]>,
+) -> io::Result<IO::FileStream> {
+    let zip_file_name = zip_path.file_name().unwrap().to_string_lossy();
 
+    // file not found: err
+    let cache_file = io.create(zip_path).await?;
     let mut writer = Sha256AsyncWrite::new(cache_file);
-    io::copy(&mut response, &mut writer).await?;
+
+    if let Some(path) = file_url_path(url)? {
+        io::copy(&mut io.open(&path).await?, &mut writer).await?;
+    } else {
+        let http = http.ok_or_else(offline_error)?;
+        let mut response = pin!(http.get(url, headers).await?);
+        io::copy(&mut response, &mut writer).await?;
+    }
 
     let (mut cache_file, hash) = writer.finalize();
 
     cache_file.flush().await?;
+
+    if let Some(repo_hash) = repo_hash {
+        if repo_hash[..] != hash[..] {
+            drop(cache_file);
+            io.remove_file(zip_path).await.ok();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "sha256 of downloaded {zip_file_name} mismatched: expected {}, got {}",
+                    hex::encode(repo_hash),
+                    hex::encode(&hash[..])
+                ),
+            ));
+        }
+    }
+
     cache_file.seek(SeekFrom::Start(0)).await?;
 
     // write sha file
@@ -663,6 +1129,10 @@ pub enum AddRepositoryErr {
     Io(io::Error),
     AlreadyAdded,
     OfflineMode,
+    /// The header value contains `${` other than exactly one `${env:NAME}`, which is not allowed for imported repositories
+    HeaderReference {
+        name: Box<str>,
+    },
 }
 
 impl fmt::Display for AddRepositoryErr {
@@ -673,6 +1143,10 @@ impl fmt::Display for AddRepositoryErr {
             AddRepositoryErr::OfflineMode => {
                 f.write_str("you can't add remote repo in offline mode")
             }
+            AddRepositoryErr::HeaderReference { name } => write!(
+                f,
+                "value of header '{name}' contains '${{' other than exactly one '${{env:NAME}}', which is not allowed for imported repositories"
+            ),
         }
     }
 }
@@ -690,3 +1164,65 @@ impl From<serde_json::Error> for AddRepositoryErr {
         Self::Io(value.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_package_zip_urls() {
+        let repo_url = url("https://vpm.anatawa12.com/vpm.json");
+        let mirrors = [
+            url("https://mirror.example.com/vpm/vpm.json"),
+            url("https://vpm.anatawa12.com/mirror/vpm.json"),
+        ];
+        let mut url_rewrites = IndexMap::new();
+        url_rewrites.insert(
+            "https://vpm.anatawa12.com/".into(),
+            "https://cache.example.com/".into(),
+        );
+
+        // zip next to the repository
+        let zip_url = url("https://vpm.anatawa12.com/package-1.0.0.zip");
+        assert_eq!(
+            package_zip_urls(&zip_url, Some(&repo_url), &mirrors, &url_rewrites),
+            vec![
+                (url("https://cache.example.com/package-1.0.0.zip"), false),
+                (zip_url.clone(), true),
+                (
+                    url("https://mirror.example.com/vpm/package-1.0.0.zip"),
+                    false
+                ),
+                (
+                    url("https://vpm.anatawa12.com/mirror/package-1.0.0.zip"),
+                    true
+                ),
+            ]
+        );
+
+        // zip on another host is not mirrored
+        let zip_url = url("https://github.com/anatawa12/package/releases/package-1.0.0.zip");
+        assert_eq!(
+            package_zip_urls(&zip_url, Some(&repo_url), &mirrors, &url_rewrites),
+            vec![(zip_url.clone(), true)]
+        );
+
+        // file:// zip in remote repository is ignored
+        let zip_url = url("file:///package-1.0.0.zip");
+        assert_eq!(
+            package_zip_urls(&zip_url, Some(&repo_url), &mirrors, &IndexMap::new()),
+            vec![]
+        );
+
+        // file:// zip in file:// repository
+        let repo_url = url("file:///vpm.json");
+        assert_eq!(
+            package_zip_urls(&zip_url, Some(&repo_url), &[], &IndexMap::new()),
+            vec![(zip_url.clone(), true)]
+        );
+    }
+}
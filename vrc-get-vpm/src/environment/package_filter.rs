@@ -0,0 +1,71 @@
+use crate::utils::wildcard_matches;
+
+/// The filter of package names a repository may provide.
+///
+/// Patterns may contain `*`, which matches any sequence of characters.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PackageFilter {
+    allowed: Vec<Box<str>>,
+    denied: Vec<Box<str>>,
+}
+
+impl PackageFilter {
+    pub fn new(allowed: &[Box<str>], denied: &[Box<str>]) -> Self {
+        Self {
+            allowed: allowed.to_vec(),
+            denied: denied.to_vec(),
+        }
+    }
+
+    /// Returns true if the package is allowed.
+    ///
+    /// If there are no allowed patterns, all packages not denied are allowed.
+    pub fn allows(&self, package: &str) -> bool {
+        (self.allowed.is_empty() || self.allowed.iter().any(|x| wildcard_matches(x, package)))
+            && !self.denied.iter().any(|x| wildcard_matches(x, package))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_filter(allowed: &[&str], denied: &[&str]) -> PackageFilter {
+        let to_vec = |x: &[&str]| x.iter().map(|&x| x.into()).collect::<Vec<_>>();
+        PackageFilter::new(&to_vec(allowed), &to_vec(denied))
+    }
+
+    #[test]
+    fn allows_all_by_default() {
+        let filter = new_filter(&[], &[]);
+        assert!(filter.allows("com.anatawa12.package"));
+        assert!(filter.allows("com.vrchat.base"));
+    }
+
+    #[test]
+    fn allow_only() {
+        let filter = new_filter(&["com.anatawa12.*", "com.vrchat.base"], &[]);
+        assert!(filter.allows("com.anatawa12.package"));
+        assert!(filter.allows("com.vrchat.base"));
+        assert!(!filter.allows("com.vrchat.avatars"));
+        assert!(!filter.allows("jp.anatawa12.package"));
+    }
+
+    #[test]
+    fn deny_only() {
+        let filter = new_filter(&[], &["com.vrchat.*"]);
+        assert!(filter.allows("com.anatawa12.package"));
+        assert!(!filter.allows("com.vrchat.base"));
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let filter = new_filter(&["com.anatawa12.*"], &["com.anatawa12.experimental*"]);
+        assert!(filter.allows("com.anatawa12.package"));
+        assert!(!filter.allows("com.anatawa12.experimental"));
+        assert!(!filter.allows("com.anatawa12.experimental-tools"));
+
+        let same = new_filter(&["com.anatawa12.package"], &["com.anatawa12.package"]);
+        assert!(!same.allows("com.anatawa12.package"));
+    }
+}
@@ -1,27 +1,71 @@
 use crate::environment::repo_source::RepoSource;
+use crate::environment::repository_news::{diff_repository, RepositoryNews};
+use crate::environment::PackageFilter;
 use crate::io;
 use crate::io::EnvironmentIo;
 use crate::repository::local::LocalCachedRepository;
 use crate::repository::RemoteRepository;
 use crate::traits::HttpClient;
-use crate::utils::{read_json_file, to_vec_pretty_os_eol, try_load_json};
+use crate::utils::{
+    is_allowed_in_repo, is_file_url, offline_error, read_json_file, resolve_headers, sleep,
+    to_vec_pretty_os_eol, try_load_json, with_timeout,
+};
+use crate::version::Version;
 use crate::{PackageCollection, PackageInfo, VersionSelector};
-use futures::future::join_all;
+use futures::prelude::*;
 use indexmap::IndexMap;
-use log::error;
+use itertools::Itertools;
+use log::{error, warn};
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 use url::Url;
 
+/// The number of repositories fetched at the same time
+const MAX_CONCURRENT_FETCHES: usize = 8;
+/// The number of retries for transient errors
+const MAX_RETRIES: u32 = 2;
+/// The delay before the first retry. This is doubled for each retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The result of fetching a repository in the last load of repositories.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum RepositoryFetchStatus {
+    /// The repository is downloaded and the cache is updated
+    Updated,
+    /// The repository is not modified since the last fetch
+    NotModified,
+    /// The repository is not fetched since it's a local repository or we're in offline mode
+    NotFetched,
+    /// Fetching the repository failed.
+    ///
+    /// If `cache_used` is true, the cached repository is used instead.
+    Failed { error: Box<str>, cache_used: bool },
+}
+
 #[derive(Debug)]
 pub(crate) struct RepoHolder {
     cached_repos_new: HashMap<Box<Path>, LocalCachedRepository>,
+    /// paths of `cached_repos_new` in the order of priority, the highest first
+    sorted_paths: Vec<Box<Path>>,
+    fetch_statuses: HashMap<Box<Path>, RepositoryFetchStatus>,
+    priorities: HashMap<Box<Path>, i32>,
+    package_filters: HashMap<Box<Path>, PackageFilter>,
+    /// changes found in the last load of repositories
+    news: Vec<RepositoryNews>,
 }
 
 impl RepoHolder {
     pub(crate) fn new() -> Self {
         RepoHolder {
             cached_repos_new: HashMap::new(),
+            sorted_paths: Vec::new(),
+            fetch_statuses: HashMap::new(),
+            priorities: HashMap::new(),
+            package_filters: HashMap::new(),
+            news: Vec::new(),
         }
     }
 }
@@ -33,62 +77,158 @@ impl RepoHolder {
         http: Option<&impl HttpClient>,
         io: &IO,
         sources: impl Iterator<Item = RepoSource<'a>>,
+        timeout: Duration,
     ) -> io::Result<()> {
-        let repos = join_all(sources.map(|src| async move {
-            match Self::load_repo_from_source(http, io, &src).await {
-                Ok(Some(v)) => Some((v, src.cache_path().into())),
-                Ok(None) => None,
+        let repos = stream::iter(sources.map(|src| async move {
+            let path: Box<Path> = src.cache_path().into();
+            let settings = (src.priority(), src.package_filter());
+            match Self::load_repo_from_source(http, io, &src, timeout).await {
+                Ok((repo, status, news)) => (path, settings, Some(repo), status, news),
                 Err(e) => {
                     error!("loading repo '{}': {}", src.cache_path().display(), e);
-                    None
+                    let status = RepositoryFetchStatus::Failed {
+                        error: e.to_string().into(),
+                        cache_used: false,
+                    };
+                    (path, settings, None, status, None)
                 }
             }
         }))
+        .buffer_unordered(MAX_CONCURRENT_FETCHES)
+        .collect::<Vec<_>>()
         .await;
 
-        for (repo, path) in repos.into_iter().flatten() {
-            self.cached_repos_new.insert(path, repo);
+        self.news.clear();
+        for (path, (priority, package_filter), repo, status, news) in repos {
+            self.news.extend(news);
+            if let Some(repo) = repo {
+                self.cached_repos_new.insert(path.clone(), repo);
+            }
+            self.priorities.insert(path.clone(), priority);
+            self.package_filters.insert(path.clone(), package_filter);
+            self.fetch_statuses.insert(path, status);
         }
 
+        self.sort_repos();
+        self.warn_conflicting_packages();
+
         Ok(())
     }
 
+    /// Warns if the same version of the package is provided by multiple repositories with different zip.
+    fn warn_conflicting_packages(&self) {
+        for (name, version, repos) in self.conflicting_packages() {
+            warn!(
+                "{}@{} is provided by multiple repositories with different zipSHA256: {}. using one from {}",
+                name,
+                version,
+                repos.iter().join(", "),
+                repos[0],
+            );
+        }
+    }
+
+    /// Returns the versions of packages provided by multiple repositories with different zip,
+    /// with the names of the repositories in the order of priority.
+    fn conflicting_packages(&self) -> Vec<(&str, &Version, Vec<&str>)> {
+        let mut sources = IndexMap::<(&str, &Version), Vec<(&str, _)>>::new();
+
+        for (path, repo) in self.get_repo_with_path() {
+            for package in repo
+                .get_packages()
+                .flat_map(|x| x.all_versions())
+                .filter(|x| self.allows_package(path, x.name()))
+            {
+                sources
+                    .entry((package.name(), package.version()))
+                    .or_default()
+                    .push((repo_name(repo), package.zip_sha_256()));
+            }
+        }
+
+        sources
+            .into_iter()
+            .filter(|(_, sources)| !sources.iter().map(|(_, sha256)| sha256).all_equal())
+            .map(|((name, version), sources)| {
+                let repos = sources.iter().map(|(repo, _)| *repo).collect();
+                (name, version, repos)
+            })
+            .collect()
+    }
+
     async fn load_repo_from_source<IO: EnvironmentIo>(
         client: Option<&impl HttpClient>,
         io: &IO,
         source: &RepoSource<'_>,
-    ) -> io::Result<Option<LocalCachedRepository>> {
+        timeout: Duration,
+    ) -> io::Result<(
+        LocalCachedRepository,
+        RepositoryFetchStatus,
+        Option<RepositoryNews>,
+    )> {
         if let Some(url) = &source.url() {
-            RepoHolder::load_remote_repo(client, io, source.headers(), source.cache_path(), url)
-                .await
-                .map(Some)
+            RepoHolder::load_remote_repo(client, io, source, url, timeout).await
         } else {
             RepoHolder::load_local_repo(io, source.cache_path())
                 .await
-                .map(Some)
+                .map(|repo| (repo, RepositoryFetchStatus::NotFetched, None))
         }
     }
 
     async fn load_remote_repo(
         client: Option<&impl HttpClient>,
         io: &impl EnvironmentIo,
-        headers: &IndexMap<Box<str>, Box<str>>,
-        path: &Path,
+        source: &RepoSource<'_>,
         remote_url: &Url,
-    ) -> io::Result<LocalCachedRepository> {
+        timeout: Duration,
+    ) -> io::Result<(
+        LocalCachedRepository,
+        RepositoryFetchStatus,
+        Option<RepositoryNews>,
+    )> {
+        let path = source.cache_path();
+        let headers = source.headers();
+        let public_key = source.public_key();
         if let Some(mut loaded) = try_load_json::<LocalCachedRepository>(io, path).await? {
-            if let (Some(client), Some(remote_url)) = (client, loaded.url().map(|x| x.to_owned())) {
+            let mut status = RepositoryFetchStatus::NotFetched;
+            let mut news = None;
+            // the url in the cache comes from the repository itself, so a remote repository
+            // must not switch to a file:// url
+            let remote_url = Some(
+                loaded
+                    .url()
+                    .filter(|x| is_allowed_in_repo(x, Some(remote_url)))
+                    .unwrap_or(remote_url)
+                    .to_owned(),
+            );
+            // file:// repositories can be updated even in offline mode
+            if let Some(remote_url) = remote_url.filter(|x| client.is_some() || is_file_url(x)) {
                 // if it's possible to download remote repo, try to update with that
-                match RemoteRepository::download_with_etag(
-                    client,
-                    &remote_url,
-                    loaded.headers(),
-                    loaded.vrc_get.as_ref().map(|x| x.etag.as_ref()),
-                )
-                .await
-                {
-                    Ok(None) => log::debug!("cache matched downloading {}", remote_url),
+                let etag = loaded.vrc_get.as_ref().map(|x| x.etag.as_ref());
+                let headers = resolve_headers(loaded.headers(), source.header_references()).await;
+                let fetched = match headers {
+                    Ok(headers) => {
+                        fetch_from_mirrors(
+                            client,
+                            io,
+                            &remote_url,
+                            source.mirrors(),
+                            &headers,
+                            etag,
+                            public_key,
+                            timeout,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                match fetched {
+                    Ok(None) => {
+                        log::debug!("cache matched downloading {}", remote_url);
+                        status = RepositoryFetchStatus::NotModified;
+                    }
                     Ok(Some((remote_repo, etag))) => {
+                        news = diff_repository(loaded.repo(), &remote_repo);
                         loaded.set_repo(remote_repo);
                         loaded.set_etag(etag);
 
@@ -97,24 +237,41 @@ impl RepoHolder {
                             .unwrap_or_else(|e| {
                                 error!("writing local repo cache '{}': {}", path.display(), e)
                             });
+                        status = RepositoryFetchStatus::Updated;
                     }
                     Err(e) => {
-                        error!("fetching remote repo '{}': {}", remote_url, e);
+                        warn!(
+                            "fetching remote repo '{}' failed, using cached one: {}",
+                            remote_url, e
+                        );
+                        status = RepositoryFetchStatus::Failed {
+                            error: e.to_string().into(),
+                            cache_used: true,
+                        };
                     }
                 }
             }
 
-            Ok(loaded)
+            Ok((loaded, status, news))
         } else {
             // if local repository not found: try downloading remote one
-            let Some(client) = client else {
-                return Err(io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    "offline mode",
-                ));
+            if client.is_none() && !is_file_url(remote_url) {
+                return Err(offline_error());
+            }
+            let fetched = fetch_from_mirrors(
+                client,
+                io,
+                remote_url,
+                source.mirrors(),
+                &*resolve_headers(headers, source.header_references()).await?,
+                None,
+                public_key,
+                timeout,
+            )
+            .await?;
+            let Some((remote_repo, etag)) = fetched else {
+                unreachable!("downloading without etag must return Some");
             };
-            let (remote_repo, etag) =
-                RemoteRepository::download(client, remote_url, headers).await?;
 
             let mut local_cache = LocalCachedRepository::new(remote_repo, headers.clone());
 
@@ -126,7 +283,8 @@ impl RepoHolder {
                     error!("writing local repo cache '{}': {}", path.display(), e);
                 });
 
-            Ok(local_cache)
+            // everything is new for newly added repositories so we don't make news
+            Ok((local_cache, RepositoryFetchStatus::Updated, None))
         }
     }
 
@@ -137,36 +295,196 @@ impl RepoHolder {
         read_json_file::<LocalCachedRepository>(io.open(path).await?, path).await
     }
 
-    pub(crate) fn get_repos(&self) -> Vec<&LocalCachedRepository> {
-        self.cached_repos_new.values().collect()
-    }
-
+    /// Returns the repositories in the order of priority, the highest first.
     pub(crate) fn get_repo_with_path(
         &self,
     ) -> impl Iterator<Item = (&'_ Box<Path>, &'_ LocalCachedRepository)> {
-        self.cached_repos_new.iter()
+        (self.sorted_paths.iter()).map(|path| (path, &self.cached_repos_new[path]))
+    }
+
+    /// Updates the order of repositories. This must be called when repositories or priorities are changed.
+    fn sort_repos(&mut self) {
+        let mut paths = self.cached_repos_new.keys().cloned().collect::<Vec<_>>();
+        paths.sort_by(|a, b| {
+            (Reverse(self.get_priority(a)), a).cmp(&(Reverse(self.get_priority(b)), b))
+        });
+        self.sorted_paths = paths;
+    }
+
+    fn get_priority(&self, path: &Path) -> i32 {
+        self.priorities.get(path).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn set_priority(&mut self, path: &Path, priority: i32) {
+        self.priorities.insert(path.into(), priority);
+        self.sort_repos();
+    }
+
+    /// Returns true if the repository at `path` may provide the package.
+    pub(crate) fn allows_package(&self, path: &Path, package: &str) -> bool {
+        match self.package_filters.get(path) {
+            Some(filter) => filter.allows(package),
+            None => true,
+        }
+    }
+
+    pub(crate) fn set_package_filter(&mut self, path: &Path, filter: PackageFilter) {
+        self.package_filters.insert(path.into(), filter);
     }
 
     pub(crate) fn get_repo(&self, path: &Path) -> Option<&LocalCachedRepository> {
         self.cached_repos_new.get(path)
     }
 
+    pub(crate) fn get_fetch_status(&self, path: &Path) -> Option<&RepositoryFetchStatus> {
+        self.fetch_statuses.get(path)
+    }
+
+    pub(crate) fn take_news(&mut self) -> Vec<RepositoryNews> {
+        std::mem::take(&mut self.news)
+    }
+
     pub(crate) fn remove_repo(&mut self, path: &Path) {
         self.cached_repos_new.remove(path);
+        self.fetch_statuses.remove(path);
+        self.priorities.remove(path);
+        self.package_filters.remove(path);
+        self.sorted_paths.retain(|x| x.as_ref() != path);
     }
 }
 
+fn repo_name(repo: &LocalCachedRepository) -> &str {
+    repo.id()
+        .or(repo.url().map(Url::as_str))
+        .unwrap_or("(no id)")
+}
+
+/// Downloads the repository from `url`, or from `mirrors` in order if it fails.
+///
+/// `headers` are sent as is so references in them must be resolved by the caller.
+/// `timeout` is for the whole fetch including retries and mirrors so a dead repository cannot stall commands.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_from_mirrors(
+    client: Option<&impl HttpClient>,
+    io: &impl EnvironmentIo,
+    url: &Url,
+    mirrors: &[Url],
+    headers: &IndexMap<Box<str>, Box<str>>,
+    current_etag: Option<&str>,
+    public_key: Option<&str>,
+    timeout: Duration,
+) -> io::Result<Option<(RemoteRepository, Option<Box<str>>)>> {
+    let no_headers = IndexMap::new();
+    let fetch = async {
+        let mut fetch_urls = std::iter::once(url).chain(mirrors).peekable();
+        loop {
+            let fetch_url = fetch_urls.next().unwrap();
+            // headers may have credentials for the repository so they're not sent to other hosts
+            let headers = if fetch_url.origin() == url.origin() {
+                headers
+            } else {
+                &no_headers
+            };
+            let result = fetch_with_retry(fetch_url, || {
+                RemoteRepository::download_with_etag_from(
+                    client,
+                    io,
+                    fetch_url,
+                    url,
+                    headers,
+                    current_etag,
+                    public_key,
+                )
+            })
+            .await;
+
+            match result {
+                Err(e) if fetch_urls.peek().is_some() => {
+                    warn!(
+                        "fetching remote repo '{}' failed, trying next mirror: {}",
+                        fetch_url, e
+                    );
+                }
+                result => return result,
+            }
+        }
+    };
+
+    with_timeout(timeout, fetch).await
+}
+
+/// Runs `fetch`, retrying with exponential backoff for transient errors.
+async fn fetch_with_retry<T, F, Fut>(url: &Url, fetch: F) -> io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    fetch_with_backoff(url, INITIAL_BACKOFF, fetch).await
+}
+
+async fn fetch_with_backoff<T, F, Fut>(
+    url: &Url,
+    mut backoff: Duration,
+    mut fetch: F,
+) -> io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut retries = 0;
+    loop {
+        match fetch().await {
+            Err(e) if retries < MAX_RETRIES && is_transient(&e) => {
+                warn!(
+                    "fetching remote repo '{}' failed, retrying in {}ms: {}",
+                    url,
+                    backoff.as_millis(),
+                    e
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    use io::ErrorKind::*;
+
+    if let Some(error) = error
+        .get_ref()
+        .and_then(|x| x.downcast_ref::<reqwest::Error>())
+    {
+        return error.is_timeout()
+            || error.is_connect()
+            || error.status().is_some_and(is_transient_status);
+    }
+
+    // ConnectionAborted is not here since it's used for offline mode
+    matches!(
+        error.kind(),
+        TimedOut | Interrupted | ConnectionReset | UnexpectedEof
+    )
+}
+
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
 impl PackageCollection for RepoHolder {
     fn get_all_packages(&self) -> impl Iterator<Item = PackageInfo> {
-        self.get_repos()
-            .into_iter()
-            .flat_map(|repo| repo.get_all_packages())
+        self.get_repo_with_path().flat_map(|(path, repo)| {
+            repo.get_all_packages()
+                .filter(|x| self.allows_package(path, x.name()))
+        })
     }
 
     fn find_packages(&self, package: &str) -> impl Iterator<Item = PackageInfo> {
-        self.get_repos()
-            .into_iter()
-            .flat_map(|repo| repo.find_packages(package))
+        self.get_repo_with_path()
+            .filter(move |(path, _)| self.allows_package(path, package))
+            .flat_map(|(_, repo)| repo.find_packages(package))
     }
 
     fn find_package_by_name(
@@ -174,9 +492,201 @@ impl PackageCollection for RepoHolder {
         package: &str,
         package_selector: VersionSelector,
     ) -> Option<PackageInfo> {
-        self.get_repos()
-            .into_iter()
-            .flat_map(|repo| repo.find_package_by_name(package, package_selector))
-            .max_by_key(|x| x.version())
+        package_selector.select_preferred(
+            self.get_repo_with_path()
+                .filter(|(path, _)| self.allows_package(path, package))
+                .flat_map(|(_, repo)| repo.find_package_by_name(package, package_selector)),
+            |x| x.version(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use reqwest::StatusCode;
+    use std::cell::Cell;
+
+    const BACKOFF: Duration = Duration::from_millis(1);
+
+    fn fetch_count(mut result: impl FnMut(u32) -> io::Result<()>) -> (io::Result<()>, u32) {
+        let url = Url::parse("https://vpm.anatawa12.com/vpm.json").unwrap();
+        let count = Cell::new(0);
+        let result = block_on(fetch_with_backoff(&url, BACKOFF, || {
+            count.set(count.get() + 1);
+            future::ready(result(count.get()))
+        }));
+        (result, count.get())
+    }
+
+    #[test]
+    fn retry_transient_errors() {
+        let (result, count) = fetch_count(|_| Err(io::ErrorKind::ConnectionReset.into()));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(count, MAX_RETRIES + 1);
+
+        let (result, count) = fetch_count(|count| {
+            if count == 1 {
+                Err(io::ErrorKind::TimedOut.into())
+            } else {
+                Ok(())
+            }
+        });
+        result.unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn no_retry_for_other_errors() {
+        for kind in [
+            io::ErrorKind::NotFound,
+            io::ErrorKind::InvalidData,
+            io::ErrorKind::PermissionDenied,
+            io::ErrorKind::ConnectionAborted,
+        ] {
+            let (result, count) = fetch_count(|_| Err(kind.into()));
+            assert_eq!(result.unwrap_err().kind(), kind);
+            assert_eq!(count, 1);
+        }
+    }
+
+    fn repo_holder(repos: &[(&str, i32, serde_json::Value)]) -> RepoHolder {
+        let mut holder = RepoHolder::new();
+        for (path, priority, json) in repos {
+            let repo = RemoteRepository::parse(json.as_object().unwrap().clone()).unwrap();
+            let path: Box<Path> = Path::new(path).into();
+            (holder.cached_repos_new).insert(
+                path.clone(),
+                LocalCachedRepository::new(repo, IndexMap::new()),
+            );
+            holder.priorities.insert(path, *priority);
+        }
+        holder.sort_repos();
+        holder
+    }
+
+    fn package(sha256: &str) -> serde_json::Value {
+        serde_json::json!({
+            "com.anatawa12.package": {
+                "versions": {
+                    "1.0.0": { "name": "com.anatawa12.package", "version": "1.0.0", "zipSHA256": sha256 },
+                },
+            },
+        })
+    }
+
+    fn repo_paths(holder: &RepoHolder) -> Vec<&Path> {
+        (holder.get_repo_with_path())
+            .map(|(path, _)| path.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn repos_in_priority_order() {
+        let empty = || serde_json::json!({ "packages": {} });
+        let mut holder = repo_holder(&[
+            ("a.json", 0, empty()),
+            ("b.json", 10, empty()),
+            ("c.json", 0, empty()),
+            ("d.json", -1, empty()),
+        ]);
+        // same priority is ordered by path
+        let paths = ["b.json", "a.json", "c.json", "d.json"].map(Path::new);
+        assert_eq!(repo_paths(&holder), paths);
+
+        holder.set_priority("d.json".as_ref(), 20);
+        let paths = ["d.json", "b.json", "a.json", "c.json"].map(Path::new);
+        assert_eq!(repo_paths(&holder), paths);
+
+        holder.remove_repo("b.json".as_ref());
+        let paths = ["d.json", "a.json", "c.json"].map(Path::new);
+        assert_eq!(repo_paths(&holder), paths);
+    }
+
+    #[test]
+    fn conflicting_packages() {
+        let version = Version::new(1, 0, 0);
+        let holder = repo_holder(&[
+            (
+                "a.json",
+                0,
+                serde_json::json!({ "id": "a", "packages": package("aaaa") }),
+            ),
+            (
+                "b.json",
+                10,
+                serde_json::json!({ "id": "b", "packages": package("bbbb") }),
+            ),
+            (
+                "c.json",
+                0,
+                serde_json::json!({ "id": "c", "packages": package("aaaa") }),
+            ),
+        ]);
+        assert_eq!(
+            holder.conflicting_packages(),
+            vec![("com.anatawa12.package", &version, vec!["b", "a", "c"])]
+        );
+
+        // no conflict if all zips are the same
+        let holder = repo_holder(&[
+            (
+                "a.json",
+                0,
+                serde_json::json!({ "id": "a", "packages": package("aaaa") }),
+            ),
+            (
+                "c.json",
+                0,
+                serde_json::json!({ "id": "c", "packages": package("aaaa") }),
+            ),
+        ]);
+        assert_eq!(holder.conflicting_packages(), vec![]);
+    }
+
+    #[test]
+    fn select_preferred_from_found_packages() {
+        let holder = repo_holder(&[
+            (
+                "a.json",
+                0,
+                serde_json::json!({ "packages": package("aaaa") }),
+            ),
+            (
+                "b.json",
+                10,
+                serde_json::json!({ "packages": package("bbbb") }),
+            ),
+        ]);
+        let selector = VersionSelector::latest_for(None, false);
+
+        // the repository with higher priority is preferred for the same version,
+        // like find_package_by_name
+        let found = (holder.find_packages("com.anatawa12.package"))
+            .map(|x| x.package_json().zip_sha_256())
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![Some("bbbb"), Some("aaaa")]);
+        let preferred = selector
+            .select_preferred(holder.find_packages("com.anatawa12.package"), |x| {
+                x.version()
+            })
+            .unwrap();
+        assert_eq!(preferred.package_json().zip_sha_256(), Some("bbbb"));
+        let by_name = (holder.find_package_by_name("com.anatawa12.package", selector)).unwrap();
+        assert_eq!(by_name.package_json().zip_sha_256(), Some("bbbb"));
+    }
+
+    #[test]
+    fn transient_status() {
+        assert!(is_transient_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_transient_status(StatusCode::FORBIDDEN));
+        assert!(!is_transient_status(StatusCode::BAD_REQUEST));
     }
 }
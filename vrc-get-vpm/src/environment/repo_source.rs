@@ -1,3 +1,4 @@
+use crate::environment::PackageFilter;
 use indexmap::IndexMap;
 use std::path::Path;
 use url::Url;
@@ -6,6 +7,12 @@ pub(crate) struct RepoSource<'a> {
     cache_path: &'a Path,
     headers: &'a IndexMap<Box<str>, Box<str>>,
     url: Option<&'a Url>,
+    mirrors: &'a [Url],
+    public_key: Option<&'a str>,
+    priority: i32,
+    allowed_packages: &'a [Box<str>],
+    denied_packages: &'a [Box<str>],
+    header_references: bool,
 }
 
 impl<'a> RepoSource<'a> {
@@ -13,14 +20,27 @@ impl<'a> RepoSource<'a> {
         cache_path: &'a Path,
         headers: &'a IndexMap<Box<str>, Box<str>>,
         url: Option<&'a Url>,
+        mirrors: &'a [Url],
+        public_key: Option<&'a str>,
     ) -> Self {
         Self {
             cache_path,
             headers,
             url,
+            mirrors,
+            public_key,
+            priority: 0,
+            allowed_packages: &[],
+            denied_packages: &[],
+            header_references: false,
         }
     }
 
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn cache_path(&self) -> &Path {
         self.cache_path
     }
@@ -29,7 +49,44 @@ impl<'a> RepoSource<'a> {
         self.headers
     }
 
-    pub fn url(&self) -> Option<&Url> {
+    pub fn url(&self) -> Option<&'a Url> {
         self.url
     }
+
+    /// The mirror URLs of the repository, tried in order if fetching from the url fails
+    pub fn mirrors(&self) -> &[Url] {
+        self.mirrors
+    }
+
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn with_package_filter(
+        mut self,
+        allowed_packages: &'a [Box<str>],
+        denied_packages: &'a [Box<str>],
+    ) -> Self {
+        self.allowed_packages = allowed_packages;
+        self.denied_packages = denied_packages;
+        self
+    }
+
+    pub fn package_filter(&self) -> PackageFilter {
+        PackageFilter::new(self.allowed_packages, self.denied_packages)
+    }
+
+    pub fn with_header_references(mut self, header_references: bool) -> Self {
+        self.header_references = header_references;
+        self
+    }
+
+    /// Whether references in header values are resolved. See [`RepositorySettings`](crate::environment::RepositorySettings)
+    pub fn header_references(&self) -> bool {
+        self.header_references
+    }
 }
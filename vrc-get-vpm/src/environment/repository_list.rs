@@ -0,0 +1,221 @@
+use crate::environment::RepositorySettings;
+use crate::io;
+use crate::UserRepoSetting;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The value of headers replaced with when exporting with redaction.
+///
+/// Headers with this value are not added on import.
+/// Only values exactly one `${env:NAME}` reference are kept since they don't contain secrets.
+pub const REDACTED_HEADER_VALUE: &str = "<redacted>";
+
+/// The portable list of user repositories to share the repository configuration with others.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryList {
+    repositories: Vec<ExportedRepository>,
+}
+
+impl RepositoryList {
+    /// Creates the list from the user repositories with their vrc-get specific settings and headers.
+    pub(crate) fn from_user_repos<'a>(
+        repos: impl Iterator<
+            Item = (
+                &'a UserRepoSetting,
+                &'a RepositorySettings,
+                &'a IndexMap<Box<str>, Box<str>>,
+            ),
+        >,
+        redact_headers: bool,
+    ) -> Self {
+        let repositories = repos
+            .filter_map(|(repo, settings, headers)| {
+                let headers = (headers.iter())
+                    .map(|(name, value)| {
+                        if redact_headers && !is_env_reference(value) {
+                            (name.clone(), REDACTED_HEADER_VALUE.into())
+                        } else {
+                            (name.clone(), value.clone())
+                        }
+                    })
+                    .collect();
+
+                Some(ExportedRepository {
+                    url: repo.url()?.clone(),
+                    name: repo.name().map(Into::into),
+                    id: repo.id().map(Into::into),
+                    headers,
+                    mirrors: settings.mirrors().to_vec(),
+                    public_key: settings.public_key().map(Into::into),
+                    priority: settings.priority(),
+                    allowed_packages: settings.allowed_packages().to_vec(),
+                    denied_packages: settings.denied_packages().to_vec(),
+                })
+            })
+            .collect();
+
+        Self { repositories }
+    }
+
+    pub fn parse(json: &[u8]) -> io::Result<Self> {
+        let no_bom = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+        Ok(serde_json::from_slice(no_bom)?)
+    }
+
+    pub fn to_json(&self) -> io::Result<Vec<u8>> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        Ok(json)
+    }
+
+    pub fn repositories(&self) -> &[ExportedRepository] {
+        &self.repositories
+    }
+}
+
+/// The user repository in [`RepositoryList`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedRepository {
+    url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    headers: IndexMap<Box<str>, Box<str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mirrors: Vec<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    priority: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_packages: Vec<Box<str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    denied_packages: Vec<Box<str>>,
+}
+
+impl ExportedRepository {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the headers except redacted ones.
+    pub fn headers(&self) -> IndexMap<Box<str>, Box<str>> {
+        (self.headers.iter())
+            .filter(|(_, value)| value.as_ref() != REDACTED_HEADER_VALUE)
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Returns the names of headers redacted on export.
+    pub fn redacted_headers(&self) -> impl Iterator<Item = &str> {
+        (self.headers.iter())
+            .filter(|(_, value)| value.as_ref() == REDACTED_HEADER_VALUE)
+            .map(|(name, _)| name.as_ref())
+    }
+
+    pub fn mirrors(&self) -> &[Url] {
+        &self.mirrors
+    }
+
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn allowed_packages(&self) -> &[Box<str>] {
+        &self.allowed_packages
+    }
+
+    pub fn denied_packages(&self) -> &[Box<str>] {
+        &self.denied_packages
+    }
+
+    /// Returns true if the repository is already in `repos` with the same url or id.
+    pub fn is_added_to(&self, repos: &[UserRepoSetting]) -> bool {
+        repos.iter().any(|repo| {
+            repo.url() == Some(&self.url) || (self.id.is_some() && repo.id() == self.id())
+        })
+    }
+}
+
+/// Returns true if the value is exactly one `${env:NAME}` reference
+pub(crate) fn is_env_reference(value: &str) -> bool {
+    value
+        .strip_prefix("${env:")
+        .and_then(|x| x.strip_suffix('}'))
+        .is_some_and(|name| {
+            !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        })
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_export_and_parse() {
+        let remote = UserRepoSetting::new(
+            Path::new("Repos/remote.json").into(),
+            Some("Remote".into()),
+            Some(Url::parse("https://vpm.anatawa12.com/vpm.json").unwrap()),
+            Some("com.anatawa12.vpm".into()),
+        );
+        let local = UserRepoSetting::new(Path::new("local.json").into(), None, None, None);
+
+        let mut headers = IndexMap::<Box<str>, Box<str>>::new();
+        headers.insert("Authorization".into(), "Bearer secret".into());
+        headers.insert("X-Token".into(), "${env:TOKEN}".into());
+        headers.insert("X-Mixed".into(), "hardcoded-secret ${env:TOKEN}".into());
+        headers.insert("X-Command".into(), "${cmd:credential-helper}".into());
+        let no_headers = IndexMap::new();
+        let settings = RepositorySettings::default();
+
+        let list = RepositoryList::from_user_repos(
+            [
+                (&remote, &settings, &headers),
+                (&local, &settings, &no_headers),
+            ]
+            .into_iter(),
+            true,
+        );
+        let list = RepositoryList::parse(&list.to_json().unwrap()).unwrap();
+
+        // local repositories are not exported
+        assert_eq!(list.repositories().len(), 1);
+        let repo = &list.repositories()[0];
+        assert_eq!(repo.url().as_str(), "https://vpm.anatawa12.com/vpm.json");
+        assert_eq!(repo.name(), Some("Remote"));
+        assert_eq!(repo.id(), Some("com.anatawa12.vpm"));
+        assert_eq!(
+            repo.headers().into_iter().collect::<Vec<_>>(),
+            [("X-Token".into(), "${env:TOKEN}".into())]
+        );
+        assert_eq!(
+            repo.redacted_headers().collect::<Vec<_>>(),
+            ["Authorization", "X-Mixed", "X-Command"]
+        );
+        assert!(repo.is_added_to(&[remote]));
+        assert!(!repo.is_added_to(&[local]));
+    }
+}
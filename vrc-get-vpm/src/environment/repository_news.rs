@@ -0,0 +1,232 @@
+use crate::io;
+use crate::io::EnvironmentIo;
+use crate::repository::RemoteRepository;
+use crate::utils::{load_json_or_default, to_vec_pretty_os_eol};
+use crate::version::Version;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const JSON_PATH: &str = "vrc-get/repository-news.json";
+/// The number of news kept in the file. Older news are removed.
+const MAX_NEWS: usize = 50;
+
+/// The changes of packages in a repository found when the repository is refreshed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryNews {
+    /// id or url of the repository
+    repository_id: Box<str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repository_name: Option<Box<str>>,
+    fetched_at: DateTime<Utc>,
+    changes: Vec<PackageNews>,
+}
+
+impl RepositoryNews {
+    pub fn repository_id(&self) -> &str {
+        &self.repository_id
+    }
+
+    pub fn repository_name(&self) -> Option<&str> {
+        self.repository_name.as_deref()
+    }
+
+    pub fn fetched_at(&self) -> DateTime<Utc> {
+        self.fetched_at
+    }
+
+    /// Returns the changes sorted by package name and version.
+    pub fn changes(&self) -> &[PackageNews] {
+        &self.changes
+    }
+}
+
+/// The change of a package version in the repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageNews {
+    kind: PackageNewsKind,
+    name: Box<str>,
+    version: Version,
+}
+
+impl PackageNews {
+    pub fn kind(&self) -> PackageNewsKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum PackageNewsKind {
+    /// The package is new to the repository
+    NewPackage,
+    /// The version is added to the existing package
+    NewVersion,
+    /// The version is yanked
+    Yanked,
+    /// The version is removed from the repository
+    Removed,
+}
+
+/// Computes the changes from `old` to `new` repository.
+///
+/// Returns `None` if nothing is changed.
+pub(crate) fn diff_repository(
+    old: &RemoteRepository,
+    new: &RemoteRepository,
+) -> Option<RepositoryNews> {
+    fn versions(repo: &RemoteRepository) -> HashMap<(&str, &Version), bool> {
+        (repo.get_packages())
+            .flat_map(|x| x.all_versions())
+            .map(|x| ((x.name(), x.version()), x.is_yanked()))
+            .collect()
+    }
+
+    let old_versions = versions(old);
+    let new_versions = versions(new);
+    let old_packages = (old_versions.keys())
+        .map(|(name, _)| *name)
+        .collect::<HashSet<_>>();
+
+    let mut changes = Vec::new();
+    let mut push = |kind, (name, version): (&str, &Version)| {
+        changes.push(PackageNews {
+            kind,
+            name: name.into(),
+            version: version.clone(),
+        })
+    };
+
+    for (&key, &yanked) in &new_versions {
+        match old_versions.get(&key) {
+            // new versions already yanked are not interesting
+            None if yanked => {}
+            None if old_packages.contains(key.0) => push(PackageNewsKind::NewVersion, key),
+            None => push(PackageNewsKind::NewPackage, key),
+            Some(false) if yanked => push(PackageNewsKind::Yanked, key),
+            Some(_) => {}
+        }
+    }
+
+    for &key in old_versions.keys() {
+        if !new_versions.contains_key(&key) {
+            push(PackageNewsKind::Removed, key);
+        }
+    }
+
+    if changes.is_empty() {
+        return None;
+    }
+
+    changes.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    // id of the cache is kept if the new repository doesn't have id
+    let repository_id = (new.id().or(old.id()))
+        .or(new.url().map(|x| x.as_str()))
+        .unwrap_or_default();
+
+    Some(RepositoryNews {
+        repository_id: repository_id.into(),
+        repository_name: new.name().map(Into::into),
+        fetched_at: Utc::now(),
+        changes,
+    })
+}
+
+/// Loads the saved news, the oldest first.
+pub(crate) async fn load_news(io: &impl EnvironmentIo) -> io::Result<Vec<RepositoryNews>> {
+    load_json_or_default(io, JSON_PATH.as_ref()).await
+}
+
+/// Adds the news to the file, removing old news exceeding the limit.
+pub(crate) async fn save_news(
+    io: &impl EnvironmentIo,
+    news: impl IntoIterator<Item = RepositoryNews>,
+) -> io::Result<()> {
+    let mut saved = load_news(io).await?;
+    saved.extend(news);
+    if saved.len() > MAX_NEWS {
+        saved.drain(..saved.len() - MAX_NEWS);
+    }
+
+    io.create_dir_all(Path::new(JSON_PATH).parent().unwrap())
+        .await?;
+    io.write(JSON_PATH.as_ref(), &to_vec_pretty_os_eol(&saved)?)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repository(versions: &[(&str, &str, bool)]) -> RemoteRepository {
+        let mut packages = serde_json::Map::new();
+        for &(name, version, yanked) in versions {
+            let package = packages
+                .entry(name)
+                .or_insert_with(|| json!({"versions": {}}));
+            package["versions"][version] = json!({
+                "name": name,
+                "version": version,
+                "vrc-get": {"yanked": yanked},
+            });
+        }
+        let json = json!({"id": "com.anatawa12.repository", "packages": packages});
+        RemoteRepository::parse(json.as_object().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_diff_repository() {
+        let old = repository(&[
+            ("com.anatawa12.a", "1.0.0", false),
+            ("com.anatawa12.a", "1.1.0", false),
+            ("com.anatawa12.a", "1.2.0", false),
+        ]);
+        let new = repository(&[
+            ("com.anatawa12.a", "1.0.0", false),
+            ("com.anatawa12.a", "1.1.0", true),
+            ("com.anatawa12.a", "2.0.0", false),
+            ("com.anatawa12.a", "2.0.1", true),
+            ("com.anatawa12.b", "1.0.0", false),
+        ]);
+
+        assert!(diff_repository(&old, &old).is_none());
+
+        let news = diff_repository(&old, &new).unwrap();
+        let changes = (news.changes().iter())
+            .map(|x| (x.kind(), x.name(), x.version().to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(news.repository_id(), "com.anatawa12.repository");
+        assert_eq!(
+            changes,
+            [
+                (PackageNewsKind::Yanked, "com.anatawa12.a", "1.1.0".into()),
+                (PackageNewsKind::Removed, "com.anatawa12.a", "1.2.0".into()),
+                (
+                    PackageNewsKind::NewVersion,
+                    "com.anatawa12.a",
+                    "2.0.0".into()
+                ),
+                (
+                    PackageNewsKind::NewPackage,
+                    "com.anatawa12.b",
+                    "1.0.0".into()
+                ),
+            ]
+        );
+    }
+}
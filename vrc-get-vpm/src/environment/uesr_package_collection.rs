@@ -53,8 +53,10 @@ impl PackageCollection for UserPackageCollection {
         package: &str,
         package_selector: VersionSelector,
     ) -> Option<PackageInfo> {
-        self.find_packages(package)
-            .filter(|x| package_selector.satisfies(x.package_json()))
-            .max_by_key(|x| x.version())
+        package_selector.select_preferred(
+            self.find_packages(package)
+                .filter(|x| package_selector.satisfies(x.package_json())),
+            |x| x.version(),
+        )
     }
 }
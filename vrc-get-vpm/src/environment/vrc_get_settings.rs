@@ -1,7 +1,14 @@
 use crate::io;
 use crate::io::EnvironmentIo;
 use crate::utils::{read_json_file, SaveController};
+use crate::UserRepoSetting;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::Duration;
+use url::Url;
+
+const DEFAULT_REPOSITORY_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// since this file is vrc-get specific, additional keys can be removed
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -11,6 +18,104 @@ struct AsJson {
     ignore_official_repository: bool,
     #[serde(default)]
     ignore_curated_repository: bool,
+    /// refuse to install remote packages without `zipSHA256`
+    #[serde(default)]
+    require_zip_sha256: bool,
+    /// timeout in seconds for fetching each remote repository. 0 is ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repository_timeout: Option<u64>,
+    /// prefix-based rewrites of package zip urls. the first matching prefix is replaced
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    url_rewrites: IndexMap<Box<str>, Box<str>>,
+    /// vrc-get specific settings of user repositories keyed by [`repository_key`]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    repositories: IndexMap<Box<str>, RepositorySettings>,
+}
+
+/// The security settings of the user repository.
+///
+/// Those are not saved in `settings.json` since VCC and other tools may drop unknown keys of user repositories,
+/// and verification should not be disabled silently by that.
+/// In addition, `settings.json` may be copied from other machines, so it must not opt in to running commands.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepositorySettings {
+    /// The minisign public key to verify the signature of the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<Box<str>>,
+    /// The mirror URLs of the repository, tried in order if fetching from the url fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mirrors: Vec<Url>,
+    /// The priority of the repository.
+    /// If multiple repositories have the same version of the package, the one with higher priority is used
+    #[serde(default, skip_serializing_if = "is_zero")]
+    priority: i32,
+    /// The package name patterns the repository may provide. If empty, all packages are allowed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_packages: Vec<Box<str>>,
+    /// The package name patterns the repository must not provide
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    denied_packages: Vec<Box<str>>,
+    /// Whether `${env:NAME}` and `${cmd:COMMAND}` references in header values are resolved.
+    /// This must only be enabled by the user on this machine
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    header_references: bool,
+}
+
+static DEFAULT_REPOSITORY_SETTINGS: RepositorySettings = RepositorySettings {
+    public_key: None,
+    mirrors: Vec::new(),
+    priority: 0,
+    allowed_packages: Vec::new(),
+    denied_packages: Vec::new(),
+    header_references: false,
+};
+
+impl RepositorySettings {
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
+    pub fn mirrors(&self) -> &[Url] {
+        &self.mirrors
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn allowed_packages(&self) -> &[Box<str>] {
+        &self.allowed_packages
+    }
+
+    pub fn denied_packages(&self) -> &[Box<str>] {
+        &self.denied_packages
+    }
+
+    pub fn header_references(&self) -> bool {
+        self.header_references
+    }
+
+    fn is_empty(&self) -> bool {
+        self.public_key.is_none()
+            && self.mirrors.is_empty()
+            && self.priority == 0
+            && self.allowed_packages.is_empty()
+            && self.denied_packages.is_empty()
+            && !self.header_references
+    }
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+/// The key of the user repository in `repositories`: the url for remote repositories and the path for local ones
+fn repository_key(repo: &UserRepoSetting) -> Cow<'_, str> {
+    match repo.url() {
+        Some(url) => Cow::Borrowed(url.as_str()),
+        None => repo.local_path().to_string_lossy(),
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +161,97 @@ impl VrcGetSettings {
         self.controller.as_mut().ignore_curated_repository = value;
     }
 
+    pub fn require_zip_sha256(&self) -> bool {
+        self.controller.require_zip_sha256
+    }
+
+    pub fn set_require_zip_sha256(&mut self, value: bool) {
+        self.controller.as_mut().require_zip_sha256 = value;
+    }
+
+    pub fn repository_timeout(&self) -> Duration {
+        self.controller
+            .repository_timeout
+            .filter(|&x| x != 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REPOSITORY_TIMEOUT)
+    }
+
+    pub fn set_repository_timeout(&mut self, value: Option<Duration>) {
+        self.controller.as_mut().repository_timeout = value.map(|x| x.as_secs().max(1));
+    }
+
+    pub fn url_rewrites(&self) -> &IndexMap<Box<str>, Box<str>> {
+        &self.controller.url_rewrites
+    }
+
+    pub fn set_url_rewrite(&mut self, prefix: &str, replacement: Option<&str>) {
+        match replacement {
+            Some(replacement) => {
+                let url_rewrites = &mut self.controller.as_mut().url_rewrites;
+                url_rewrites.insert(prefix.into(), replacement.into());
+            }
+            None => {
+                (self.controller)
+                    .may_changing(|json| json.url_rewrites.shift_remove(prefix).is_some());
+            }
+        }
+    }
+
+    pub fn repository(&self, repo: &UserRepoSetting) -> &RepositorySettings {
+        (self.controller.repositories)
+            .get(repository_key(repo).as_ref())
+            .unwrap_or(&DEFAULT_REPOSITORY_SETTINGS)
+    }
+
+    pub fn set_repository_public_key(&mut self, repo: &UserRepoSetting, value: Option<Box<str>>) {
+        self.update_repository(repo, |settings| settings.public_key = value);
+    }
+
+    pub fn set_repository_mirrors(&mut self, repo: &UserRepoSetting, value: Vec<Url>) {
+        self.update_repository(repo, |settings| settings.mirrors = value);
+    }
+
+    pub fn set_repository_priority(&mut self, repo: &UserRepoSetting, value: i32) {
+        self.update_repository(repo, |settings| settings.priority = value);
+    }
+
+    pub fn set_repository_package_filter(
+        &mut self,
+        repo: &UserRepoSetting,
+        allowed: Vec<Box<str>>,
+        denied: Vec<Box<str>>,
+    ) {
+        self.update_repository(repo, |settings| {
+            settings.allowed_packages = allowed;
+            settings.denied_packages = denied;
+        });
+    }
+
+    pub fn set_repository_header_references(&mut self, repo: &UserRepoSetting, value: bool) {
+        self.update_repository(repo, |settings| settings.header_references = value);
+    }
+
+    fn update_repository(
+        &mut self,
+        repo: &UserRepoSetting,
+        f: impl FnOnce(&mut RepositorySettings),
+    ) {
+        let repositories = &mut self.controller.as_mut().repositories;
+        let key = repository_key(repo);
+        let settings = repositories.entry(key.as_ref().into()).or_default();
+        f(settings);
+        if settings.is_empty() {
+            repositories.shift_remove(key.as_ref());
+        }
+    }
+
+    pub fn remove_repository(&mut self, repo: &UserRepoSetting) {
+        let key = repository_key(repo);
+        self.controller
+            .may_changing(|json| json.repositories.shift_remove(key.as_ref()).is_some());
+    }
+
     pub async fn save(&mut self, io: &impl EnvironmentIo) -> io::Result<()> {
         self.controller.save(io, JSON_PATH.as_ref()).await
     }
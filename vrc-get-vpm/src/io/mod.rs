@@ -49,6 +49,8 @@ pub trait IoTrait: Sync {
     fn remove_file(&self, path: &Path) -> impl Future<Output = Result<()>> + Send;
     fn remove_dir_all(&self, path: &Path) -> impl Future<Output = Result<()>> + Send;
     fn metadata(&self, path: &Path) -> impl Future<Output = Result<Metadata>> + Send;
+    /// Renames a file or a directory. The destination should not exist.
+    fn rename(&self, from: &Path, to: &Path) -> impl Future<Output = Result<()>> + Send;
 
     type DirEntry: DirEntry;
     type ReadDirStream: Stream<Item = Result<Self::DirEntry>> + Unpin + Send;
@@ -76,6 +78,7 @@ pub trait IoTrait: Sync {
     type FileStream: AsyncRead + AsyncWrite + AsyncSeek + Unpin + Send;
 
     fn create_new(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
+    /// Creates the file, or truncates the file if it already exists.
     fn create(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
     fn open(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
 }
@@ -192,6 +192,10 @@ impl<T: TokioIoTraitImpl + Sync> IoTrait for T {
         fs::metadata(self.resolve(path)?).await.map(Into::into)
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(self.resolve(from)?, self.resolve(to)?).await
+    }
+
     type DirEntry = DirEntry;
     type ReadDirStream = ReadDir;
 
@@ -214,6 +218,7 @@ impl<T: TokioIoTraitImpl + Sync> IoTrait for T {
     async fn create(&self, path: &Path) -> io::Result<Self::FileStream> {
         fs::OpenOptions::new()
             .create(true)
+            .truncate(true)
             .write(true)
             .read(true)
             .open(self.resolve(path)?)
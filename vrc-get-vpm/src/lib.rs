@@ -14,6 +14,7 @@ use version::{ReleaseType, UnityVersion, Version, VersionRange};
 pub mod environment;
 pub mod io;
 mod package_json;
+pub mod package_zip;
 pub mod repository;
 mod structs;
 mod traits;
@@ -41,6 +41,11 @@ impl PackageManifest {
         self
     }
 
+    pub fn with_unity(mut self, major: u16, minor: u8) -> Self {
+        self.unity = Some(PartialUnityVersion::new(major, minor));
+        self
+    }
+
     pub fn add_legacy_package(mut self, name: impl Into<Box<str>>) -> Self {
         self.legacy_packages.push(name.into());
         self
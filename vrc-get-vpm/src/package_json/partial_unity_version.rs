@@ -5,6 +5,10 @@ use serde::{Deserialize, Deserializer};
 pub struct PartialUnityVersion(u16, u8);
 
 impl PartialUnityVersion {
+    pub fn new(major: u16, minor: u8) -> Self {
+        Self(major, minor)
+    }
+
     pub fn major(&self) -> u16 {
         self.0
     }
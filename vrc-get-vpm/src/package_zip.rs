@@ -0,0 +1,357 @@
+//! Creating package zips from package folders.
+
+use crate::io;
+use crate::io::{AsyncWrite, DirEntry, IoTrait};
+use crate::utils::{wildcard_matches, MapResultExt};
+use crate::version::{Version, VersionRange};
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use futures::prelude::*;
+use serde_json::{Map, Value};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use url::Url;
+
+/// The name of the file listing files not to be included in the package zip.
+///
+/// Each line is a pattern of the name of files and folders to ignore.
+/// Patterns with `/` at the start or in the middle match the path from the package folder,
+/// and patterns with `/` at the end only match folders.
+/// `*` in patterns matches any characters, and lines starting with `#` are comments.
+pub const IGNORE_FILE_NAME: &str = ".vpmignore";
+
+/// Creates the zip of the package folder at the root of `io`.
+///
+/// The zip is laid out with `package.json` at the root, the way vrc-get extracts packages.
+/// Files matching [`IGNORE_FILE_NAME`] are not included.
+/// The zip is deterministic: entries are sorted by path and timestamps are not recorded,
+/// so packing the same files always produces the same zip.
+///
+/// The returned writer is flushed so the zip can be read right after this.
+pub async fn create_package_zip<W: AsyncWrite + Unpin>(
+    io: &impl IoTrait,
+    writer: W,
+) -> io::Result<W> {
+    let mut files = (list_entries(io).await?.into_iter())
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| Ok((zip_entry_name(&entry.path)?, entry.path)))
+        .collect::<io::Result<Vec<_>>>()?;
+    files.sort();
+
+    let mut zip = ZipFileWriter::new(writer);
+
+    for (name, path) in files {
+        let mut content = Vec::new();
+        io.open(path.as_ref())
+            .await?
+            .read_to_end(&mut content)
+            .await?;
+
+        let entry = ZipEntryBuilder::new(name.into(), Compression::Deflate);
+        zip.write_entry_whole(entry, &content).await.err_mapped()?;
+    }
+
+    let mut writer = zip.close().await.err_mapped()?;
+    writer.flush().await?;
+    Ok(writer)
+}
+
+/// The problem of the package folder found by [`check_package`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PackageProblem {
+    PackageJsonNotFound,
+    InvalidPackageJson {
+        error: Box<str>,
+    },
+    InvalidName {
+        name: Box<str>,
+    },
+    InvalidVersion {
+        version: Box<str>,
+    },
+    InvalidDependency {
+        dependency: Box<str>,
+        range: Box<str>,
+    },
+    InvalidUrl {
+        url: Box<str>,
+    },
+    MissingMeta {
+        path: Box<str>,
+    },
+}
+
+impl fmt::Display for PackageProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageProblem::PackageJsonNotFound => write!(f, "package.json not found"),
+            PackageProblem::InvalidPackageJson { error } => {
+                write!(f, "package.json is not valid: {error}")
+            }
+            PackageProblem::InvalidName { name } => write!(
+                f,
+                "name '{name}' is not valid. it must consist of lowercase letters, digits, '-', '_', and '.'"
+            ),
+            PackageProblem::InvalidVersion { version } => {
+                write!(f, "version '{version}' is not valid semver")
+            }
+            PackageProblem::InvalidDependency { dependency, range } => write!(
+                f,
+                "version range '{range}' of dependency {dependency} is not valid"
+            ),
+            PackageProblem::InvalidUrl { url } => write!(f, "url '{url}' is not valid"),
+            PackageProblem::MissingMeta { path } => {
+                write!(f, "{path} does not have .meta file")
+            }
+        }
+    }
+}
+
+/// Checks the package folder at the root of `io` can be packed.
+///
+/// This checks `package.json` has valid `name`, `version`, `vpmDependencies`, and `url`,
+/// and all files and folders Unity imports have `.meta` files.
+pub async fn check_package(io: &impl IoTrait) -> io::Result<Vec<PackageProblem>> {
+    let mut problems = Vec::new();
+
+    match read_file(io, Path::new("package.json")).await? {
+        Some(json) => check_package_json(&json, &mut problems),
+        None => problems.push(PackageProblem::PackageJsonNotFound),
+    }
+
+    let entries = list_entries(io).await?;
+    let paths = (entries.iter())
+        .map(|entry| entry.path.as_path())
+        .collect::<HashSet<_>>();
+    let mut missing_meta = Vec::new();
+
+    for entry in &entries {
+        if !unity_imports(&entry.path) || entry.path.extension() == Some("meta".as_ref()) {
+            continue;
+        }
+        let mut meta = entry.path.clone().into_os_string();
+        meta.push(".meta");
+        if !paths.contains(Path::new(&meta)) {
+            missing_meta.push(zip_entry_name(&entry.path)?);
+        }
+    }
+
+    missing_meta.sort();
+    problems.extend(
+        (missing_meta.into_iter()).map(|path| PackageProblem::MissingMeta { path: path.into() }),
+    );
+
+    Ok(problems)
+}
+
+fn check_package_json(json: &[u8], problems: &mut Vec<PackageProblem>) {
+    let no_bom = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+    let json = match serde_json::from_slice::<Map<String, Value>>(no_bom) {
+        Ok(json) => json,
+        Err(e) => {
+            return problems.push(PackageProblem::InvalidPackageJson {
+                error: e.to_string().into(),
+            })
+        }
+    };
+
+    match json.get("name") {
+        Some(Value::String(name)) if is_valid_name(name) => {}
+        name => problems.push(PackageProblem::InvalidName {
+            name: value_to_string(name),
+        }),
+    }
+
+    match json.get("version") {
+        Some(Value::String(version)) if Version::from_str(version).is_ok() => {}
+        version => problems.push(PackageProblem::InvalidVersion {
+            version: value_to_string(version),
+        }),
+    }
+
+    match json.get("vpmDependencies") {
+        None => {}
+        Some(Value::Object(dependencies)) => {
+            for (dependency, range) in dependencies {
+                match range {
+                    Value::String(range) if VersionRange::from_str(range).is_ok() => {}
+                    range => problems.push(PackageProblem::InvalidDependency {
+                        dependency: dependency.as_str().into(),
+                        range: value_to_string(Some(range)),
+                    }),
+                }
+            }
+        }
+        Some(_) => problems.push(PackageProblem::InvalidPackageJson {
+            error: "vpmDependencies is not an object".into(),
+        }),
+    }
+
+    match json.get("url") {
+        None => {}
+        Some(Value::String(url)) if Url::parse(url).is_ok() => {}
+        url => problems.push(PackageProblem::InvalidUrl {
+            url: value_to_string(url),
+        }),
+    }
+}
+
+fn value_to_string(value: Option<&Value>) -> Box<str> {
+    match value {
+        None => "".into(),
+        Some(Value::String(value)) => value.as_str().into(),
+        Some(value) => value.to_string().into(),
+    }
+}
+
+/// Unity requires package names to match `^[a-z0-9][a-z0-9-._]{0,213}$`
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name.len() <= 214
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c))
+}
+
+/// Returns false if Unity doesn't import the path, so no .meta file is needed.
+///
+/// Unity ignores hidden files and files ending with `~`, and everything in such folders.
+fn unity_imports(path: &Path) -> bool {
+    path.components().all(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        !name.starts_with('.') && !name.ends_with('~') && !name.eq_ignore_ascii_case("cvs")
+    }) && path.extension() != Some("tmp".as_ref())
+}
+
+struct Entry {
+    path: PathBuf,
+    is_dir: bool,
+}
+
+/// Lists relative paths of all files and folders in `io` not ignored by [`IGNORE_FILE_NAME`]
+async fn list_entries(io: &impl IoTrait) -> io::Result<Vec<Entry>> {
+    let ignore = IgnoreRules::load(io).await?;
+    let mut entries = Vec::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(PathBuf::new());
+
+    while let Some(dir) = queue.pop_front() {
+        let mut iter = io.read_dir(&dir).await?;
+        while let Some(entry) = iter.try_next().await? {
+            let path = dir.join(entry.file_name());
+            let file_type = entry.file_type().await?;
+            let is_dir = file_type.is_dir();
+            if !(is_dir || file_type.is_file()) || ignore.ignores(&path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                queue.push_back(path.clone());
+            }
+            entries.push(Entry { path, is_dir });
+        }
+    }
+
+    Ok(entries)
+}
+
+struct IgnorePattern {
+    components: Vec<String>,
+    anchored: bool,
+    dir_only: bool,
+}
+
+struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    async fn load(io: &impl IoTrait) -> io::Result<Self> {
+        let mut patterns = vec![
+            // the ignore file and git folder are never packed
+            IgnorePattern::parse(&format!("/{IGNORE_FILE_NAME}")).unwrap(),
+            IgnorePattern::parse(".git").unwrap(),
+        ];
+
+        if let Some(content) = read_file(io, Path::new(IGNORE_FILE_NAME)).await? {
+            let content = String::from_utf8_lossy(&content);
+            patterns.extend(content.lines().filter_map(IgnorePattern::parse));
+        }
+
+        Ok(Self { patterns })
+    }
+
+    fn ignores(&self, path: &Path, is_dir: bool) -> bool {
+        let components = (path.components())
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.dir_only && !is_dir {
+                return false;
+            }
+            if pattern.anchored {
+                pattern.components.len() == components.len()
+                    && (pattern.components.iter())
+                        .zip(&components)
+                        .all(|(pattern, name)| wildcard_matches(pattern, name))
+            } else {
+                (components.last())
+                    .is_some_and(|name| wildcard_matches(&pattern.components[0], name))
+            }
+        })
+    }
+}
+
+impl IgnorePattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (line, dir_only) = match line.strip_suffix('/') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+
+        Some(Self {
+            components: line.split('/').map(ToOwned::to_owned).collect(),
+            anchored,
+            dir_only,
+        })
+    }
+}
+
+async fn read_file(io: &impl IoTrait, path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let mut file = match io.open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).await?;
+    Ok(Some(content))
+}
+
+fn zip_entry_name(path: &Path) -> io::Result<String> {
+    let mut name = String::new();
+    for component in path.components() {
+        let Some(component) = component.as_os_str().to_str() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("path is not utf8: {}", path.display()),
+            ));
+        };
+        if !name.is_empty() {
+            name.push('/');
+        }
+        name.push_str(component);
+    }
+    Ok(name)
+}
@@ -0,0 +1,203 @@
+use indexmap::IndexMap;
+use std::fmt;
+use std::str::FromStr;
+use url::Url;
+
+/// The `vcc://vpm/addRepo` link repository landing pages use to add the repository to VCC.
+///
+/// The link has the url of the repository in `url` query parameter and
+/// headers in `headers[]` query parameters with `name:value` format like
+/// `vcc://vpm/addRepo?url=https%3A%2F%2Fvpm.anatawa12.com%2Fvpm.json&headers[]=Authorization:Bearer%20token`.
+///
+/// Since links come from web pages, header values with `${` are rejected
+/// not to be confused with references to environment variables or commands.
+#[derive(Debug, Clone)]
+pub struct AddRepositoryLink {
+    url: Url,
+    headers: IndexMap<Box<str>, Box<str>>,
+}
+
+impl AddRepositoryLink {
+    pub fn parse(link: &str) -> Result<Self, ParseAddRepositoryLinkErr> {
+        let link = Url::parse(link.trim()).map_err(|_| ParseAddRepositoryLinkErr::NotVccLink)?;
+
+        if link.scheme() != "vcc" || link.host_str() != Some("vpm") {
+            return Err(ParseAddRepositoryLinkErr::NotVccLink);
+        }
+
+        if link.path() != "/addRepo" {
+            return Err(ParseAddRepositoryLinkErr::UnsupportedAction {
+                action: link.path().trim_start_matches('/').into(),
+            });
+        }
+
+        let mut url = None;
+        let mut headers = IndexMap::<Box<str>, Box<str>>::new();
+
+        for (key, value) in link.query_pairs() {
+            match key.as_ref() {
+                "url" => {
+                    if url.is_some() {
+                        return Err(ParseAddRepositoryLinkErr::MultipleUrl);
+                    }
+                    url = Some(Url::parse(&value).map_err(|_| {
+                        ParseAddRepositoryLinkErr::InvalidUrl {
+                            url: value.as_ref().into(),
+                        }
+                    })?);
+                }
+                "headers[]" => {
+                    let Some((name, value)) = value.split_once(':') else {
+                        return Err(ParseAddRepositoryLinkErr::InvalidHeader {
+                            header: value.as_ref().into(),
+                        });
+                    };
+                    if value.contains("${") {
+                        return Err(ParseAddRepositoryLinkErr::HeaderReference {
+                            name: name.trim().into(),
+                        });
+                    }
+                    headers.insert(name.trim().into(), value.trim().into());
+                }
+                // unknown parameters are ignored for future extensions
+                _ => {}
+            }
+        }
+
+        let url = url.ok_or(ParseAddRepositoryLinkErr::NoUrl)?;
+
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(ParseAddRepositoryLinkErr::InvalidUrl {
+                url: url.as_str().into(),
+            });
+        }
+
+        Ok(Self { url, headers })
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn headers(&self) -> &IndexMap<Box<str>, Box<str>> {
+        &self.headers
+    }
+
+    /// Returns the url and headers to pass to [`Environment::add_remote_repo`](crate::environment::Environment::add_remote_repo)
+    pub fn into_parts(self) -> (Url, IndexMap<Box<str>, Box<str>>) {
+        (self.url, self.headers)
+    }
+}
+
+impl FromStr for AddRepositoryLink {
+    type Err = ParseAddRepositoryLinkErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseAddRepositoryLinkErr {
+    NotVccLink,
+    UnsupportedAction { action: Box<str> },
+    NoUrl,
+    MultipleUrl,
+    InvalidUrl { url: Box<str> },
+    InvalidHeader { header: Box<str> },
+    HeaderReference { name: Box<str> },
+}
+
+impl fmt::Display for ParseAddRepositoryLinkErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAddRepositoryLinkErr::NotVccLink => f.write_str("not a vcc://vpm link"),
+            ParseAddRepositoryLinkErr::UnsupportedAction { action } => {
+                write!(f, "unsupported action: {action}")
+            }
+            ParseAddRepositoryLinkErr::NoUrl => f.write_str("url of the repository not found"),
+            ParseAddRepositoryLinkErr::MultipleUrl => {
+                f.write_str("multiple urls of the repository found")
+            }
+            ParseAddRepositoryLinkErr::InvalidUrl { url } => {
+                write!(f, "url '{url}' is not valid")
+            }
+            ParseAddRepositoryLinkErr::InvalidHeader { header } => {
+                write!(f, "header '{header}' is not in 'name:value' format")
+            }
+            ParseAddRepositoryLinkErr::HeaderReference { name } => {
+                write!(
+                    f,
+                    "value of header '{name}' contains '${{' which is not allowed in links"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseAddRepositoryLinkErr {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let link = AddRepositoryLink::parse(
+            "vcc://vpm/addRepo?url=https%3A%2F%2Fvpm.anatawa12.com%2Fvpm.json&headers[]=Authorization:Bearer%20token&headers[]=X-Key:%20value",
+        )
+        .unwrap();
+        assert_eq!(link.url().as_str(), "https://vpm.anatawa12.com/vpm.json");
+        assert_eq!(
+            link.headers().iter().collect::<Vec<_>>(),
+            [
+                (&"Authorization".into(), &"Bearer token".into()),
+                (&"X-Key".into(), &"value".into()),
+            ]
+        );
+
+        let link =
+            AddRepositoryLink::parse("vcc://vpm/addRepo?url=https://vpm.anatawa12.com/vpm.json")
+                .unwrap();
+        assert_eq!(link.url().as_str(), "https://vpm.anatawa12.com/vpm.json");
+        assert!(link.headers().is_empty());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        use ParseAddRepositoryLinkErr::*;
+
+        let parse = |link: &str| AddRepositoryLink::parse(link).unwrap_err();
+
+        assert!(matches!(
+            parse("https://vpm.anatawa12.com/vpm.json"),
+            NotVccLink
+        ));
+        assert!(matches!(
+            parse("vcc://vpm/addPackage?url=https://a.com"),
+            UnsupportedAction { .. }
+        ));
+        assert!(matches!(parse("vcc://vpm/addRepo"), NoUrl));
+        assert!(matches!(
+            parse("vcc://vpm/addRepo?url=https://a.com&url=https://b.com"),
+            MultipleUrl
+        ));
+        assert!(matches!(
+            parse("vcc://vpm/addRepo?url=file:///repo.json"),
+            InvalidUrl { .. }
+        ));
+        assert!(matches!(
+            parse("vcc://vpm/addRepo?url=https://a.com&headers[]=value"),
+            InvalidHeader { .. }
+        ));
+        assert!(matches!(
+            parse("vcc://vpm/addRepo?url=https://a.com&headers[]=X-Token:${cmd:rm%20-rf%20~}"),
+            HeaderReference { name } if &*name == "X-Token"
+        ));
+        assert!(matches!(
+            parse("vcc://vpm/addRepo?url=https://a.com&headers[]=X-Token:Bearer%20$%7Benv:GITHUB_TOKEN%7D"),
+            HeaderReference { .. }
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::io;
+use crate::io::{AsyncRead, AsyncSeek, SeekFrom};
+use crate::utils::{deserialize_json, MapResultExt, Sha256AsyncWrite};
+use crate::PackageJson;
+use async_zip::base::read::seek::ZipFileReader;
+use futures::prelude::*;
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+use url::Url;
+
+type JsonMap = Map<String, Value>;
+
+/// The package zip read for adding to the repository.
+pub struct PackageZip {
+    json: JsonMap,
+    package_json: PackageJson,
+    sha256: String,
+}
+
+impl PackageZip {
+    /// Reads `package.json` at the root of the zip and computes the SHA-256 of the zip.
+    pub async fn read(mut zip: impl AsyncRead + AsyncSeek + Unpin) -> io::Result<Self> {
+        zip.seek(SeekFrom::Start(0)).await?;
+        let mut hasher = Sha256AsyncWrite::new(io::sink());
+        io::copy(&mut zip, &mut hasher).await?;
+        let sha256 = hex::encode(&hasher.finalize().1[..]);
+
+        zip.seek(SeekFrom::Start(0)).await?;
+        let mut reader = ZipFileReader::new(zip).await.err_mapped()?;
+        let Some(index) = reader
+            .file()
+            .entries()
+            .iter()
+            .position(|x| x.filename().as_str().ok() == Some("package.json"))
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "package.json not found in the zip",
+            ));
+        };
+
+        let mut bytes = Vec::new();
+        reader
+            .reader_without_entry(index)
+            .await
+            .err_mapped()?
+            .read_to_end(&mut bytes)
+            .await?;
+
+        let no_bom = bytes
+            .strip_prefix(b"\xEF\xBB\xBF")
+            .unwrap_or(bytes.as_ref());
+        let json: JsonMap = serde_json::from_slice(no_bom)?;
+        let package_json = deserialize_json(Value::Object(json.clone()))?;
+
+        Ok(Self {
+            json,
+            package_json,
+            sha256,
+        })
+    }
+
+    pub fn package_json(&self) -> &PackageJson {
+        &self.package_json
+    }
+
+    /// The hex-encoded SHA-256 of the zip
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+/// The result of [`RepositoryBuilder::add_package`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum AddPackageResult {
+    /// The version is newly added
+    Added,
+    /// The same version with the same zip and url is already in the repository
+    Unchanged,
+    /// The same version with the different zip or url is replaced
+    Replaced,
+}
+
+/// Builds `index.json` of a VPM repository.
+///
+/// Properties and packages not touched are kept as is,
+/// so this can be used to add packages to an existing repository.
+pub struct RepositoryBuilder {
+    repository: JsonMap,
+}
+
+impl Default for RepositoryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepositoryBuilder {
+    /// Creates an empty repository.
+    pub fn new() -> Self {
+        let mut repository = JsonMap::new();
+        repository.insert("packages".into(), Value::Object(JsonMap::new()));
+        Self { repository }
+    }
+
+    /// Parses the existing repository to add packages to.
+    pub fn parse(json: &[u8]) -> io::Result<Self> {
+        let no_bom = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+        let mut repository: JsonMap = serde_json::from_slice(no_bom)?;
+
+        match repository
+            .entry("packages")
+            .or_insert_with(|| Value::Object(JsonMap::new()))
+        {
+            Value::Object(_) => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "packages of the repository is not an object",
+                ))
+            }
+        }
+
+        Ok(Self { repository })
+    }
+
+    /// Sets the top-level property of the repository like `name`, `id`, `url`, or `author`.
+    pub fn set_property(&mut self, key: &str, value: &str) {
+        self.repository
+            .insert(key.into(), Value::String(value.into()));
+    }
+
+    /// Returns what [`add_package`](Self::add_package) would do without changing the repository.
+    pub fn check_package(&self, zip: &PackageZip, url: &Url) -> AddPackageResult {
+        let previous = (self.repository.get("packages"))
+            .and_then(|x| x.get(zip.package_json.name()))
+            .and_then(|x| x.get("versions"))
+            .and_then(|x| x.get(zip.package_json.version().to_string()));
+
+        match previous {
+            None => AddPackageResult::Added,
+            Some(previous)
+                if previous.get("zipSHA256").and_then(Value::as_str) == Some(&zip.sha256)
+                    && previous.get("url").and_then(Value::as_str) == Some(url.as_str()) =>
+            {
+                AddPackageResult::Unchanged
+            }
+            Some(_) => AddPackageResult::Replaced,
+        }
+    }
+
+    /// Adds the package zip downloadable from `url` to the repository.
+    ///
+    /// The same version in the repository is replaced. Use [`check_package`](Self::check_package)
+    /// before this to refuse replacing published versions.
+    pub fn add_package(&mut self, zip: &PackageZip, url: &Url) -> AddPackageResult {
+        let result = self.check_package(zip, url);
+
+        let mut json = zip.json.clone();
+        json.insert("url".into(), Value::String(url.to_string()));
+        json.insert("zipSHA256".into(), Value::String(zip.sha256.clone()));
+
+        let packages = self
+            .repository
+            .get_mut("packages")
+            .and_then(Value::as_object_mut)
+            .expect("packages must be an object");
+
+        let package = packages
+            .entry(zip.package_json.name())
+            .or_insert_with(|| Value::Object(JsonMap::new()));
+        if !package.is_object() {
+            *package = Value::Object(JsonMap::new());
+        }
+        let versions = package
+            .as_object_mut()
+            .unwrap()
+            .entry("versions")
+            .or_insert_with(|| Value::Object(JsonMap::new()));
+        if !versions.is_object() {
+            *versions = Value::Object(JsonMap::new());
+        }
+
+        versions
+            .as_object_mut()
+            .unwrap()
+            .insert(zip.package_json.version().to_string(), Value::Object(json));
+
+        result
+    }
+
+    /// Returns the `index.json` of the repository
+    pub fn to_json(&self) -> io::Result<Vec<u8>> {
+        // packages are placed at the end to make the properties easy to find
+        let repository = (self.repository.iter())
+            .filter(|(key, _)| key.as_str() != "packages")
+            .chain(self.repository.get_key_value("packages"))
+            .collect::<IndexMap<_, _>>();
+        let mut json = serde_json::to_vec_pretty(&repository)?;
+        json.push(b'\n');
+        Ok(json)
+    }
+}
@@ -99,9 +99,11 @@ impl PackageCollection for LocalCachedRepository {
                 .get_package_version(package, version)
                 .map(|pkg| PackageInfo::remote(pkg, self))
         } else {
-            self.find_packages(package)
-                .filter(|x| package_selector.satisfies(x.package_json()))
-                .max_by_key(|x| x.version())
+            package_selector.select_preferred(
+                self.find_packages(package)
+                    .filter(|x| package_selector.satisfies(x.package_json())),
+                |x| x.version(),
+            )
         }
     }
 }
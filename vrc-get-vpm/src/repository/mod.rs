@@ -1,5 +1,14 @@
-pub(crate) mod local;
+mod add_repository_link;
+mod builder;
+pub mod local;
 mod remote;
 
+pub use add_repository_link::AddRepositoryLink;
+pub use add_repository_link::ParseAddRepositoryLinkErr;
+
+pub use builder::AddPackageResult;
+pub use builder::PackageZip;
+pub use builder::RepositoryBuilder;
+
 pub use remote::RemotePackages;
 pub use remote::RemoteRepository;
@@ -1,10 +1,15 @@
+use crate::io::IoTrait;
 use crate::traits::HttpClient;
-use crate::utils::{deserialize_json, deserialize_json_slice};
+use crate::utils::{
+    deserialize_json, deserialize_json_slice, file_url_path, get_bytes, offline_error,
+    read_file_with_etag,
+};
 use crate::version::Version;
 use crate::PackageManifest;
 use crate::{io, VersionSelector};
 use futures::prelude::*;
 use indexmap::IndexMap;
+use minisign_verify::{PublicKey, Signature};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{Map, Value};
 use std::collections::HashMap;
@@ -39,31 +44,74 @@ impl RemoteRepository {
         })
     }
 
+    /// Downloads the repository.
+    ///
+    /// `file://` urls are read with `io` so `client` can be `None` for them.
     pub async fn download(
-        client: &impl HttpClient,
+        client: Option<&impl HttpClient>,
+        io: &impl IoTrait,
         url: &Url,
         headers: &IndexMap<Box<str>, Box<str>>,
+        public_key: Option<&str>,
     ) -> io::Result<(RemoteRepository, Option<Box<str>>)> {
-        match Self::download_with_etag(client, url, headers, None).await {
-            Ok(None) => unreachable!("downloading without etag should must return Ok(Some)"),
+        match Self::download_with_etag(client, io, url, headers, None, public_key).await {
+            Ok(None) => unreachable!("downloading without etag must return Ok(Some)"),
             Ok(Some(repo_and_etag)) => Ok(repo_and_etag),
             Err(err) => Err(err),
         }
     }
 
+    /// Downloads the repository if it's changed from `current_etag`.
+    ///
+    /// If `public_key` is specified, the minisign signature at `<url>.minisig` is downloaded
+    /// and the repository is rejected unless the signature is valid.
+    ///
+    /// For `file://` urls, the file is read with `io` and the etag is the hash of the content.
     pub async fn download_with_etag(
-        client: &impl HttpClient,
+        client: Option<&impl HttpClient>,
+        io: &impl IoTrait,
         url: &Url,
         headers: &IndexMap<Box<str>, Box<str>>,
         current_etag: Option<&str>,
+        public_key: Option<&str>,
     ) -> io::Result<Option<(RemoteRepository, Option<Box<str>>)>> {
-        let Some((mut stream, etag)) = client.get_with_etag(url, headers, current_etag).await?
-        else {
-            return Ok(None);
+        Self::download_with_etag_from(client, io, url, url, headers, current_etag, public_key).await
+    }
+
+    /// Downloads the repository at `url` from the mirror at `fetch_url`.
+    ///
+    /// The signature is downloaded from the mirror as well.
+    pub(crate) async fn download_with_etag_from(
+        client: Option<&impl HttpClient>,
+        io: &impl IoTrait,
+        fetch_url: &Url,
+        url: &Url,
+        headers: &IndexMap<Box<str>, Box<str>>,
+        current_etag: Option<&str>,
+        public_key: Option<&str>,
+    ) -> io::Result<Option<(RemoteRepository, Option<Box<str>>)>> {
+        let (bytes, etag) = if let Some(path) = file_url_path(fetch_url)? {
+            let Some((bytes, etag)) = read_file_with_etag(io, &path, current_etag).await? else {
+                return Ok(None);
+            };
+            (bytes, Some(etag))
+        } else {
+            let client = client.ok_or_else(offline_error)?;
+            let Some((stream, etag)) = client
+                .get_with_etag(fetch_url, headers, current_etag)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            let mut bytes = Vec::new();
+            pin!(stream).read_to_end(&mut bytes).await?;
+            (bytes, etag)
         };
 
-        let mut bytes = Vec::new();
-        pin!(stream).read_to_end(&mut bytes).await?;
+        if let Some(public_key) = public_key {
+            verify_signature(client, io, fetch_url, headers, &bytes, public_key).await?;
+        }
 
         let no_bom = bytes
             .strip_prefix(b"\xEF\xBB\xBF")
@@ -127,6 +175,51 @@ impl RemoteRepository {
     }
 }
 
+/// Returns the URL of the detached minisign signature of the repository.
+pub fn signature_url(url: &Url) -> Url {
+    let mut signature_url = url.clone();
+    signature_url.set_path(&format!("{}.minisig", url.path()));
+    signature_url
+}
+
+async fn verify_signature(
+    client: Option<&impl HttpClient>,
+    io: &impl IoTrait,
+    url: &Url,
+    headers: &IndexMap<Box<str>, Box<str>>,
+    bytes: &[u8],
+    public_key: &str,
+) -> io::Result<()> {
+    let public_key = PublicKey::from_base64(public_key).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid public key: {e}"),
+        )
+    })?;
+
+    let signature = get_bytes(client, io, &signature_url(url), headers).await?;
+    let signature = String::from_utf8(signature).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid signature of {url}: {e}"),
+        )
+    })?;
+
+    let signature = Signature::decode(&signature).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid signature of {url}: {e}"),
+        )
+    })?;
+
+    public_key.verify(bytes, &signature, false).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("signature verification failed for {url}: {e}"),
+        )
+    })
+}
+
 impl Serialize for RemoteRepository {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
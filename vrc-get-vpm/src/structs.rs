@@ -1,5 +1,5 @@
 pub mod setting {
-    use crate::environment::RepoSource;
+    use crate::environment::{RepoSource, RepositorySettings};
     use indexmap::IndexMap;
     use serde::{Deserialize, Serialize};
     use std::path::Path;
@@ -56,8 +56,17 @@ pub mod setting {
             &self.headers
         }
 
-        pub(crate) fn to_source(&self) -> RepoSource {
-            RepoSource::new(&self.local_path, &self.headers, self.url.as_ref())
+        pub(crate) fn to_source<'a>(&'a self, settings: &'a RepositorySettings) -> RepoSource<'a> {
+            RepoSource::new(
+                &self.local_path,
+                &self.headers,
+                self.url.as_ref(),
+                settings.mirrors(),
+                settings.public_key(),
+            )
+            .with_priority(settings.priority())
+            .with_package_filter(settings.allowed_packages(), settings.denied_packages())
+            .with_header_references(settings.header_references())
         }
     }
 }
@@ -23,6 +23,9 @@ pub trait PackageCollection {
     fn get_all_packages(&self) -> impl Iterator<Item = PackageInfo>;
 
     /// get all package versions of the specified package
+    ///
+    /// If multiple packages have the same version, the one preferred by
+    /// [`find_package_by_name`](Self::find_package_by_name) comes first.
     fn find_packages(&self, package: &str) -> impl Iterator<Item = PackageInfo>;
 
     /// get specified version of specified package
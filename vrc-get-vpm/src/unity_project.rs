@@ -1,4 +1,6 @@
 mod add_package;
+mod bundle;
+mod check_unity;
 mod find_legacy_assets;
 mod migrate_unity_2022;
 mod migrate_vpm;
@@ -14,7 +16,7 @@ use crate::io;
 use crate::unity_project::upm_manifest::UpmManifest;
 use crate::unity_project::vpm_manifest::VpmManifest;
 use crate::utils::{try_load_json, PathBufExt};
-use crate::version::{UnityVersion, Version, VersionRange};
+use crate::version::{DependencyRange, UnityVersion, Version, VersionRange};
 use futures::future::try_join;
 use futures::prelude::*;
 use indexmap::IndexMap;
@@ -29,8 +31,11 @@ use crate::io::{DirEntry, FileSystemProjectIo, ProjectIo};
 use crate::PackageJson;
 pub use add_package::AddPackageErr;
 pub use add_package::AddPackageOperation;
+pub use bundle::ProjectBundle;
+pub use check_unity::{PackageCompatibility, UnityCompatibilityReport};
 pub use migrate_unity_2022::MigrateUnity2022Error;
 pub use migrate_vpm::MigrateVpmError;
+pub use package_resolution::ResolutionStrategy;
 pub use pending_project_changes::PendingProjectChanges;
 pub use resolve::ResolvePackageErr;
 
@@ -170,10 +175,51 @@ impl<IO: ProjectIo> UnityProject<IO> {
         self.manifest.dependencies().map(|(name, _)| name)
     }
 
+    pub fn dependencies_with_range(&self) -> impl Iterator<Item = (&str, &DependencyRange)> {
+        self.manifest.dependencies()
+    }
+
     pub fn get_locked(&self, name: &str) -> Option<LockedDependencyInfo> {
         self.manifest.get_locked(name)
     }
 
+    /// Returns the names of packages held to the current version.
+    ///
+    /// Held packages are not upgraded unless explicitly requested.
+    pub fn held_packages(&self) -> impl Iterator<Item = &str> {
+        self.manifest.held_packages()
+    }
+
+    pub fn is_held(&self, name: &str) -> bool {
+        self.manifest.is_held(name)
+    }
+
+    /// Marks the package as held or not held. Call [`save`](Self::save) to persist the change.
+    pub fn set_held(&mut self, name: &str, held: bool) {
+        self.manifest.set_held(name, held)
+    }
+
+    /// Returns the dependency overrides declared in the manifest
+    /// as `(dependant, dependency, range)`.
+    ///
+    /// The range replaces the range `dependant` declares for `dependency` while resolving.
+    pub fn dependency_overrides(&self) -> impl Iterator<Item = (&str, &str, &VersionRange)> {
+        self.manifest.dependency_overrides()
+    }
+
+    /// Returns the range to use for `dependency` of `dependant`,
+    /// which is the override if exists and `declared` otherwise.
+    pub fn dependency_range<'a>(
+        &'a self,
+        dependant: &str,
+        dependency: &str,
+        declared: &'a VersionRange,
+    ) -> &'a VersionRange {
+        self.manifest
+            .get_dependency_override(dependant, dependency)
+            .unwrap_or(declared)
+    }
+
     pub fn is_locked(&self, name: &str) -> bool {
         self.manifest.get_locked(name).is_some()
     }
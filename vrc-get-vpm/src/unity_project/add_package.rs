@@ -1,7 +1,8 @@
 use crate::io::ProjectIo;
+use crate::unity_project::package_resolution::DependencyOverrides;
 use crate::unity_project::pending_project_changes::RemoveReason;
 use crate::unity_project::vpm_manifest::VpmManifest;
-use crate::unity_project::{package_resolution, PendingProjectChanges};
+use crate::unity_project::{package_resolution, PendingProjectChanges, ResolutionStrategy};
 use crate::version::DependencyRange;
 use crate::{PackageCollection, PackageInfo, UnityProject};
 use log::debug;
@@ -60,6 +61,26 @@ impl<IO: ProjectIo> UnityProject<IO> {
         packages: &[PackageInfo<'env>],
         operation: AddPackageOperation,
         allow_prerelease: bool,
+    ) -> Result<PendingProjectChanges<'env>, AddPackageErr> {
+        self.add_package_request_with_strategy(
+            env,
+            packages,
+            operation,
+            allow_prerelease,
+            ResolutionStrategy::Latest,
+        )
+        .await
+    }
+
+    /// Same as [`add_package_request`](Self::add_package_request) but the versions of
+    /// dependencies are chosen with the specified strategy.
+    pub async fn add_package_request_with_strategy<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+        packages: &[PackageInfo<'env>],
+        operation: AddPackageOperation,
+        allow_prerelease: bool,
+        strategy: ResolutionStrategy,
     ) -> Result<PendingProjectChanges<'env>, AddPackageErr> {
         // if same or newer requested package is in locked dependencies,
         // just add requested version into dependencies
@@ -184,6 +205,8 @@ impl<IO: ProjectIo> UnityProject<IO> {
             }),
             self.manifest.all_locked(),
             |pkg| self.manifest.get_locked(pkg),
+            &DependencyOverrides::new(self.manifest.dependency_overrides()),
+            strategy,
             self.unity_version(),
             env,
             adding_packages,
@@ -198,6 +221,10 @@ impl<IO: ProjectIo> UnityProject<IO> {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        for (package, chains) in result.requirement_chains {
+            changes.conflict_requirement_chains(package, chains);
+        }
+
         for name in result
             .found_legacy_packages
             .into_iter()
@@ -0,0 +1,224 @@
+use crate::io;
+use crate::io::{AsyncWrite, ProjectIo};
+use crate::repository::local::LocalCachedRepository;
+use crate::repository::{PackageZip, RemoteRepository, RepositoryBuilder};
+use crate::traits::RemotePackageDownloader;
+use crate::unity_project::vpm_manifest::{VpmManifest, MANIFEST_PATH};
+use crate::utils::{deserialize_json_slice, MapResultExt};
+use crate::version::Version;
+use crate::{PackageCollection, PackageManifest, UnityProject, VersionSelector};
+use async_zip::base::read::mem::ZipFileReader;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use futures::io::Cursor;
+use futures::prelude::*;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::mem::take;
+use std::path::Path;
+
+const INDEX_JSON: &str = "index.json";
+const VPM_MANIFEST_JSON: &str = "vpm-manifest.json";
+
+fn package_zip_path(name: &str, version: &Version) -> String {
+    format!("packages/{name}-{version}.zip")
+}
+
+/// The archive of the packages locked in the project to resolve the project without network.
+///
+/// The archive is a zip with the package zips in `packages/`,
+/// `index.json` describing them as a repository, and the copy of `vpm-manifest.json`.
+pub struct ProjectBundle {
+    repository: LocalCachedRepository,
+    vpm_manifest: Vec<u8>,
+    package_zips: HashMap<String, Vec<u8>>,
+}
+
+impl ProjectBundle {
+    /// Parses the bundle created with [`UnityProject::create_bundle`].
+    ///
+    /// This checks all packages in `index.json` are in the bundle with the same `zipSHA256`.
+    pub async fn parse(bundle: Vec<u8>) -> io::Result<Self> {
+        let reader = ZipFileReader::new(bundle).await.err_mapped()?;
+
+        let mut files = HashMap::new();
+        for index in 0..reader.file().entries().len() {
+            let entry = &reader.file().entries()[index];
+            let Ok(name) = entry.filename().as_str() else {
+                continue;
+            };
+            if entry.dir().unwrap_or(false) {
+                continue;
+            }
+            let name = name.to_owned();
+
+            let mut content = Vec::new();
+            reader
+                .reader_without_entry(index)
+                .await
+                .err_mapped()?
+                .read_to_end(&mut content)
+                .await?;
+            files.insert(name, content);
+        }
+
+        let not_found = |name: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} not found in the bundle"),
+            )
+        };
+
+        let index = files
+            .remove(INDEX_JSON)
+            .ok_or_else(|| not_found(INDEX_JSON))?;
+        let vpm_manifest =
+            (files.remove(VPM_MANIFEST_JSON)).ok_or_else(|| not_found(VPM_MANIFEST_JSON))?;
+
+        let no_bom = index.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&index);
+        let repository = RemoteRepository::parse(deserialize_json_slice(no_bom)?)?;
+
+        let mut package_zips = HashMap::new();
+        for package in repository.get_packages().flat_map(|x| x.all_versions()) {
+            let path = package_zip_path(package.name(), package.version());
+            let zip = files.remove(&path).ok_or_else(|| not_found(&path))?;
+
+            let sha256 = PackageZip::read(Cursor::new(&zip)).await?;
+            if package.zip_sha_256() != Some(sha256.sha256()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("zipSHA256 of {path} mismatched"),
+                ));
+            }
+
+            package_zips.insert(path, zip);
+        }
+
+        Ok(Self {
+            repository: LocalCachedRepository::new(repository, IndexMap::new()),
+            vpm_manifest,
+            package_zips,
+        })
+    }
+
+    /// Returns the repository of the bundled packages.
+    ///
+    /// The repository can be used as the [`PackageCollection`] to resolve the project,
+    /// after adding the package zips to the cache of the environment.
+    pub fn repository(&self) -> &LocalCachedRepository {
+        &self.repository
+    }
+
+    /// Returns the content of `vpm-manifest.json` of the bundled project.
+    pub fn vpm_manifest(&self) -> &[u8] {
+        &self.vpm_manifest
+    }
+
+    /// Returns the bundled packages with the content of their zips.
+    pub fn packages(&self) -> impl Iterator<Item = (&PackageManifest, &[u8])> {
+        (self.repository.repo().get_packages())
+            .flat_map(|x| x.all_versions())
+            .map(|package| {
+                let path = package_zip_path(package.name(), package.version());
+                (package, self.package_zips[&path].as_slice())
+            })
+    }
+}
+
+impl<IO: ProjectIo> UnityProject<IO> {
+    /// Uses `vpm-manifest.json` in the bundle as the manifest of the project.
+    ///
+    /// This is for projects without `vpm-manifest.json`.
+    /// The manifest is changed in memory and written with [`UnityProject::save`].
+    pub fn use_bundle_manifest(&mut self, bundle: &ProjectBundle) -> io::Result<()> {
+        self.manifest = VpmManifest::from_slice(bundle.vpm_manifest())?;
+
+        // packages in the Packages folder may be locked in the new manifest
+        let manifest = &self.manifest;
+        let (installed, unlocked) = take(&mut self.unlocked_packages)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(name, package)| {
+                package.as_ref().is_some_and(|package| {
+                    package.name() == name.as_ref() && manifest.get_locked(name).is_some()
+                })
+            });
+        self.unlocked_packages = unlocked;
+        self.installed_packages
+            .extend((installed.into_iter()).map(|(name, package)| (name, package.unwrap())));
+
+        Ok(())
+    }
+
+    /// Writes the [`ProjectBundle`] archive of the packages locked in the project.
+    ///
+    /// The package zips are taken from the cache of the environment, or downloaded if not cached.
+    /// Packages not from repositories, like user packages, cannot be bundled.
+    pub async fn create_bundle<Env, W>(&self, env: &Env, writer: W) -> io::Result<W>
+    where
+        Env: PackageCollection + RemotePackageDownloader,
+        W: AsyncWrite + Unpin,
+    {
+        let mut vpm_manifest = Vec::new();
+        self.io
+            .open(Path::new(MANIFEST_PATH))
+            .await?
+            .read_to_end(&mut vpm_manifest)
+            .await?;
+
+        let mut locked = self.locked_packages().collect::<Vec<_>>();
+        locked.sort_by_key(|x| x.name());
+
+        let mut builder = RepositoryBuilder::new();
+        builder.set_property("name", "Project Bundle");
+
+        let mut zips = Vec::with_capacity(locked.len());
+
+        for dep in locked {
+            let not_bundleable = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} version {} {reason}", dep.name(), dep.version()),
+                )
+            };
+
+            let package = env
+                .find_package_by_name(dep.name(), VersionSelector::specific_version(dep.version()))
+                .ok_or_else(|| not_bundleable("not found in repositories"))?;
+            let repository =
+                (package.repo()).ok_or_else(|| not_bundleable("is not from repositories"))?;
+            let url = (package.package_json().url())
+                .ok_or_else(|| not_bundleable("has no url in the repository"))?;
+
+            let mut zip = Vec::new();
+            env.get_package(repository, package.package_json())
+                .await?
+                .read_to_end(&mut zip)
+                .await?;
+
+            let package_zip = PackageZip::read(Cursor::new(&zip)).await?;
+            let package_json = package_zip.package_json();
+            let path = package_zip_path(package_json.name(), package_json.version());
+            builder.add_package(&package_zip, url);
+            zips.push((path, zip));
+        }
+
+        let mut bundle = ZipFileWriter::new(writer);
+
+        let index = builder.to_json()?;
+        let entry = ZipEntryBuilder::new(INDEX_JSON.into(), Compression::Deflate);
+        bundle.write_entry_whole(entry, &index).await.err_mapped()?;
+
+        let entry = ZipEntryBuilder::new(VPM_MANIFEST_JSON.into(), Compression::Deflate);
+        (bundle.write_entry_whole(entry, &vpm_manifest).await).err_mapped()?;
+
+        for (path, zip) in zips {
+            // zips are already compressed
+            let entry = ZipEntryBuilder::new(path.into(), Compression::Stored);
+            bundle.write_entry_whole(entry, &zip).await.err_mapped()?;
+        }
+
+        let mut writer = bundle.close().await.err_mapped()?;
+        writer.flush().await?;
+        Ok(writer)
+    }
+}
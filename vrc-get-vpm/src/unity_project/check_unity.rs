@@ -0,0 +1,158 @@
+use crate::io::ProjectIo;
+use crate::unity_project::package_resolution::DependencyOverrides;
+use crate::unity_project::{
+    package_resolution, pending_project_changes, AddPackageErr, PendingProjectChanges,
+    ResolutionStrategy,
+};
+use crate::version::{UnityVersion, Version};
+use crate::{unity_compatible, PackageCollection, PackageInfo, UnityProject, VersionSelector};
+use std::collections::HashSet;
+
+/// The result of checking compatibility of the project with another unity version.
+pub struct UnityCompatibilityReport<'env> {
+    unity_version: UnityVersion,
+    packages: Vec<PackageCompatibility<'env>>,
+    changes: PendingProjectChanges<'env>,
+}
+
+impl<'env> UnityCompatibilityReport<'env> {
+    pub fn unity_version(&self) -> UnityVersion {
+        self.unity_version
+    }
+
+    /// Returns the compatibility of each locked package.
+    pub fn packages(&self) -> &[PackageCompatibility<'env>] {
+        &self.packages
+    }
+
+    /// Returns the changes needed to use the unity version, simulated by resolving
+    /// the project with the newest compatible versions of incompatible packages.
+    ///
+    /// Packages which are still incompatible are reported as conflicts with unity.
+    pub fn simulated_changes(&self) -> &PendingProjectChanges<'env> {
+        &self.changes
+    }
+
+    /// Returns true if the project can use the unity version with the simulated changes.
+    pub fn is_compatible(&self) -> bool {
+        self.changes.conflicts().is_empty()
+    }
+}
+
+/// The compatibility of a locked package with the unity version.
+pub struct PackageCompatibility<'env> {
+    name: Box<str>,
+    locked_version: Version,
+    compatible: Option<bool>,
+    newest_compatible: Option<PackageInfo<'env>>,
+}
+
+impl<'env> PackageCompatibility<'env> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn locked_version(&self) -> &Version {
+        &self.locked_version
+    }
+
+    /// Returns whether the locked version is compatible with the unity version.
+    ///
+    /// This returns `None` if the locked version is not found in the repositories.
+    pub fn compatible(&self) -> Option<bool> {
+        self.compatible
+    }
+
+    /// Returns the newest version compatible with the unity version if the locked version is not.
+    pub fn newest_compatible(&self) -> Option<PackageInfo<'env>> {
+        self.newest_compatible
+    }
+}
+
+impl<IO: ProjectIo> UnityProject<IO> {
+    /// Checks whether locked packages are compatible with the specified unity version.
+    ///
+    /// This doesn't change the project.
+    pub async fn check_unity_compatibility<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+        unity_version: UnityVersion,
+    ) -> Result<UnityCompatibilityReport<'env>, AddPackageErr> {
+        let mut packages = Vec::new();
+        let mut replacements = Vec::new();
+
+        for locked in self.manifest.all_locked() {
+            let compatible = env
+                .find_package_by_name(
+                    locked.name(),
+                    VersionSelector::specific_version(locked.version()),
+                )
+                .map(|x| unity_compatible(x.package_json(), unity_version));
+
+            let newest_compatible = if compatible == Some(false) {
+                env.find_package_by_name(
+                    locked.name(),
+                    VersionSelector::latest_for(Some(unity_version), locked.version().is_pre()),
+                )
+            } else {
+                None
+            };
+
+            replacements.extend(newest_compatible);
+
+            packages.push(PackageCompatibility {
+                name: locked.name().into(),
+                locked_version: locked.version().clone(),
+                compatible,
+                newest_compatible,
+            });
+        }
+
+        let mut changes = pending_project_changes::Builder::new();
+        let mut replaced = HashSet::new();
+
+        if !replacements.is_empty() {
+            let allow_prerelease = replacements.iter().any(|x| x.version().is_pre());
+
+            let result = package_resolution::collect_adding_packages(
+                self.manifest.dependencies(),
+                self.manifest.all_locked(),
+                |pkg| self.manifest.get_locked(pkg),
+                &DependencyOverrides::new(self.manifest.dependency_overrides()),
+                ResolutionStrategy::Latest,
+                Some(unity_version),
+                env,
+                replacements,
+                allow_prerelease,
+            )?;
+
+            // compatibility of installing packages are checked by build_resolve_for_unity
+            for x in result.new_packages {
+                changes.install_to_locked(x);
+                replaced.insert(x.name());
+            }
+
+            for (package, conflicts_with) in result.conflicts {
+                changes.conflict_multiple(package, conflicts_with);
+            }
+
+            for (package, chains) in result.requirement_chains {
+                changes.conflict_requirement_chains(package, chains);
+            }
+        }
+
+        for package in &packages {
+            if package.compatible == Some(false) && !replaced.contains(package.name()) {
+                changes.conflicts_unity(package.name.clone());
+            }
+        }
+
+        Ok(UnityCompatibilityReport {
+            unity_version,
+            packages,
+            changes: changes
+                .build_resolve_for_unity(self, Some(unity_version))
+                .await,
+        })
+    }
+}
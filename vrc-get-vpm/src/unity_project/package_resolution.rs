@@ -1,9 +1,15 @@
 use crate::traits::PackageCollection;
+use crate::unity_project::pending_project_changes::RequirementEdge;
 use crate::unity_project::{AddPackageErr, LockedDependencyInfo};
 use crate::version::{DependencyRange, UnityVersion, Version, VersionRange};
 use crate::{PackageInfo, VersionSelector};
 use std::collections::{HashMap, HashSet, VecDeque};
 
+/// The maximum number of candidates tried while backtracking.
+/// If we cannot find a solution within this, we fall back to the greedy resolution.
+const BACKTRACK_BUDGET: usize = 10_000;
+
+#[derive(Clone)]
 struct PackageQueue<'a> {
     pending_queue: VecDeque<PackageInfo<'a>>,
 }
@@ -29,15 +35,93 @@ impl<'a> PackageQueue<'a> {
     }
 }
 
+/// Which version to choose from versions satisfying the requested ranges.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ResolutionStrategy {
+    /// Use the latest version. This is the default.
+    #[default]
+    Latest,
+    /// Use the lowest version.
+    /// This is useful to check the lower bounds of `vpmDependencies` are correct.
+    Lowest,
+}
+
+impl ResolutionStrategy {
+    pub(crate) fn range_for<'a>(
+        self,
+        unity_version: Option<UnityVersion>,
+        range: &'a VersionRange,
+    ) -> VersionSelector<'a> {
+        match self {
+            ResolutionStrategy::Latest => VersionSelector::range_for(unity_version, range),
+            ResolutionStrategy::Lowest => VersionSelector::lowest_range_for(unity_version, range),
+        }
+    }
+
+    pub(crate) fn ranges_for<'a>(
+        self,
+        unity_version: Option<UnityVersion>,
+        ranges: &'a [&'a VersionRange],
+    ) -> VersionSelector<'a> {
+        match self {
+            ResolutionStrategy::Latest => VersionSelector::ranges_for(unity_version, ranges),
+            ResolutionStrategy::Lowest => VersionSelector::lowest_ranges_for(unity_version, ranges),
+        }
+    }
+}
+
+/// The version ranges replacing ones declared by packages.
+#[derive(Default)]
+pub(crate) struct DependencyOverrides<'a> {
+    // dependant -> dependency -> range
+    overrides: HashMap<&'a str, HashMap<&'a str, &'a VersionRange>>,
+}
+
+impl<'a> DependencyOverrides<'a> {
+    pub(crate) fn new(
+        overrides: impl Iterator<Item = (&'a str, &'a str, &'a VersionRange)>,
+    ) -> Self {
+        let mut result = Self::default();
+        for (dependant, dependency, range) in overrides {
+            result
+                .overrides
+                .entry(dependant)
+                .or_default()
+                .insert(dependency, range);
+        }
+        result
+    }
+
+    /// Returns the range to use for `dependency` declared by `dependant`.
+    fn range(
+        &self,
+        dependant: &str,
+        dependency: &str,
+        declared: &'a VersionRange,
+    ) -> &'a VersionRange {
+        self.overrides
+            .get(dependant)
+            .and_then(|x| x.get(dependency))
+            .copied()
+            .unwrap_or(declared)
+    }
+}
+
+#[derive(Clone)]
 struct ResolutionContext<'env, 'a>
 where
     'env: 'a,
 {
     allow_prerelease: bool,
+    overrides: &'a DependencyOverrides<'a>,
     pub pending_queue: PackageQueue<'env>,
+    // (dependant, dependency, range) of the package being processed, in reverse order
+    pending_edges: Vec<(&'env str, &'env str, &'a VersionRange)>,
     dependencies: HashMap<&'a str, DependencyInfo<'env, 'a>>,
 }
 
+#[derive(Clone)]
 struct Legacy<'env>(&'env [Box<str>]);
 
 impl<'env> Default for Legacy<'env> {
@@ -47,7 +131,7 @@ impl<'env> Default for Legacy<'env> {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct DependencyInfo<'env, 'a> {
     using: Option<PackageInfo<'env>>,
     current: Option<&'a Version>,
@@ -115,11 +199,17 @@ where
 }
 
 impl<'env, 'a> ResolutionContext<'env, 'a> {
-    fn new(allow_prerelease: bool, packages: Vec<PackageInfo<'env>>) -> Self {
+    fn new(
+        allow_prerelease: bool,
+        overrides: &'a DependencyOverrides<'a>,
+        packages: Vec<PackageInfo<'env>>,
+    ) -> Self {
         let mut this = Self {
             dependencies: HashMap::new(),
             pending_queue: PackageQueue::new(packages),
+            pending_edges: Vec::new(),
             allow_prerelease,
+            overrides,
         };
 
         for pkg in &this.pending_queue.pending_queue {
@@ -177,6 +267,7 @@ where
         }
 
         for (dependency, range) in locked.dependencies() {
+            let range = self.overrides.range(locked.name(), dependency, range);
             self.dependencies
                 .entry(dependency)
                 .or_default()
@@ -213,6 +304,7 @@ where
             self.dependencies.get_mut(*dep).unwrap().remove_range(name);
         }
         for (dependency, range) in vpm_dependencies.iter() {
+            let range = self.overrides.range(name, dependency, range);
             self.dependencies
                 .entry(dependency)
                 .or_default()
@@ -271,8 +363,9 @@ where
 }
 
 impl<'env, 'a> ResolutionContext<'env, 'a> {
-    pub(crate) fn build_result(self) -> PackageResolutionResult<'env> {
-        let mut conflicts = HashMap::<Box<str>, Vec<Box<str>>>::new();
+    // conflict dependency -> conflicting package[]
+    fn collect_conflicts(&self) -> HashMap<&'a str, Vec<&'a str>> {
+        let mut conflicts = HashMap::<&'a str, Vec<&'a str>>::new();
         for (&name, info) in &self.dependencies {
             if !info.is_legacy() && info.touched {
                 if let Some(version) = &info.current {
@@ -291,14 +384,83 @@ impl<'env, 'a> ResolutionContext<'env, 'a> {
                                 .iter()
                                 .any(|x| self.dependencies[*x].using.is_some()))
                     {
-                        let vec = conflicts.entry(name.into()).or_default();
-                        for source in conflicts_with_this {
-                            vec.push(source.into())
-                        }
+                        conflicts
+                            .entry(name)
+                            .or_default()
+                            .extend(conflicts_with_this);
                     }
                 }
             }
         }
+        conflicts
+    }
+
+    /// Finds the shortest chain of requirements from the project (or a requested package)
+    /// to `source`, and appends the requirement from `source` to `dependency`.
+    fn requirement_chain(&self, dependency: &'a str, source: &'a str) -> Vec<RequirementEdge> {
+        // child -> the package requires the child on the shortest path to the source
+        let mut requiring = HashMap::<&'a str, &'a str>::new();
+        let mut queue = VecDeque::from([source]);
+        let mut top = source;
+
+        while let Some(current) = queue.pop_front() {
+            let Some(info) = self.dependencies.get(current) else {
+                top = current;
+                break;
+            };
+            if current.is_empty() || info.requirements.is_empty() {
+                top = current;
+                break;
+            }
+            for &requirer in info.requirements.keys() {
+                if requirer != source && !requiring.contains_key(requirer) {
+                    requiring.insert(requirer, current);
+                    queue.push_back(requirer);
+                }
+            }
+        }
+
+        let mut chain = Vec::new();
+        let mut current = top;
+        while current != source {
+            let next = requiring[current];
+            chain.push(self.requirement_edge(current, next));
+            current = next;
+        }
+        chain.push(self.requirement_edge(source, dependency));
+        chain
+    }
+
+    fn requirement_edge(&self, dependant: &str, dependency: &str) -> RequirementEdge {
+        let range = self.dependencies[dependency].requirements[dependant].clone();
+        if dependant.is_empty() {
+            RequirementEdge::new(None, dependency.into(), range)
+        } else {
+            let version = self.dependencies[dependant].current.cloned();
+            RequirementEdge::new(Some((dependant.into(), version)), dependency.into(), range)
+        }
+    }
+
+    pub(crate) fn build_result(self) -> PackageResolutionResult<'env> {
+        let mut requirement_chains = HashMap::<Box<str>, Vec<Vec<RequirementEdge>>>::new();
+        let conflicts = self
+            .collect_conflicts()
+            .into_iter()
+            .map(|(name, sources)| {
+                // the requirements satisfied by the selected version tell why the version is selected
+                let mut selecting = (self.dependencies[name].requirements.keys())
+                    .copied()
+                    .filter(|x| !sources.contains(x))
+                    .collect::<Vec<_>>();
+                selecting.sort_unstable();
+
+                let chains = (sources.iter().chain(&selecting))
+                    .map(|source| self.requirement_chain(name, source))
+                    .collect();
+                requirement_chains.insert(name.into(), chains);
+                (name.into(), sources.into_iter().map(Into::into).collect())
+            })
+            .collect();
 
         let found_legacy_packages = self
             .dependencies
@@ -317,6 +479,7 @@ impl<'env, 'a> ResolutionContext<'env, 'a> {
         PackageResolutionResult {
             new_packages,
             conflicts,
+            requirement_chains,
             found_legacy_packages,
         }
     }
@@ -326,20 +489,25 @@ pub struct PackageResolutionResult<'env> {
     pub new_packages: Vec<PackageInfo<'env>>,
     // conflict dependency -> conflicting package[])
     pub conflicts: HashMap<Box<str>, Vec<Box<str>>>,
+    // conflict dependency -> chains of requirements that caused the conflict
+    pub requirement_chains: HashMap<Box<str>, Vec<Vec<RequirementEdge>>>,
     // list of names of legacy packages we found
     pub found_legacy_packages: Vec<Box<str>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn collect_adding_packages<'a, 'env>(
     dependencies: impl Iterator<Item = (&'a str, &'a DependencyRange)>,
     locked_dependencies: impl Iterator<Item = LockedDependencyInfo<'a>>,
     get_locked: impl Fn(&str) -> Option<LockedDependencyInfo<'a>>,
+    overrides: &DependencyOverrides<'a>,
+    strategy: ResolutionStrategy,
     unity_version: Option<UnityVersion>,
     env: &'env impl PackageCollection,
     packages: Vec<PackageInfo<'env>>,
     allow_prerelease: bool,
 ) -> Result<PackageResolutionResult<'env>, AddPackageErr> {
-    let mut context = ResolutionContext::<'env, '_>::new(allow_prerelease, packages);
+    let mut context = ResolutionContext::<'env, '_>::new(allow_prerelease, overrides, packages);
 
     // first, add dependencies
     let root_dependencies = dependencies
@@ -374,38 +542,188 @@ pub(crate) fn collect_adding_packages<'a, 'env>(
         context.add_locked_dependency(locked, env);
     }
 
-    while let Some(x) = context.pending_queue.next_package() {
-        log::debug!("processing package {} version {}", x.name(), x.version());
-        let name = x.name();
-        let vpm_dependencies = &x.vpm_dependencies();
+    // First, we try to find a solution without any conflicts, with backtracking to older versions.
+    // If there is no such solution, we resolve greedily and report the conflicts.
+    let mut resolver = Resolver {
+        env,
+        unity_version,
+        strategy,
+        strict: true,
+        remaining_budget: BACKTRACK_BUDGET,
+    };
+
+    if let Some(resolved) = resolver.resolve(context.clone())? {
+        return Ok(resolved.build_result());
+    }
+
+    if resolver.remaining_budget == 0 {
+        log::debug!(
+            "backtracking resolution exceeded the budget, falling back to greedy resolution"
+        );
+    } else {
+        log::debug!("no conflict-free solution found, falling back to greedy resolution");
+    }
+
+    resolver.strict = false;
+    let resolved = resolver
+        .resolve(context)?
+        .expect("greedy resolution never fails");
+
+    Ok(resolved.build_result())
+}
+
+struct Resolver<'env, Env: PackageCollection> {
+    env: &'env Env,
+    unity_version: Option<UnityVersion>,
+    strategy: ResolutionStrategy,
+    // if true, only candidates matching all requirements are used and conflicts are failure
+    strict: bool,
+    remaining_budget: usize,
+}
 
-        if context.add_package(x) {
-            // add new dependencies
-            for (dependency, range) in vpm_dependencies.iter() {
+impl<'env, Env: PackageCollection> Resolver<'env, Env> {
+    /// Processes the pending queue until it becomes empty.
+    ///
+    /// Returns `None` if there is no conflict-free solution in strict mode.
+    fn resolve<'a>(
+        &mut self,
+        mut context: ResolutionContext<'env, 'a>,
+    ) -> Result<Option<ResolutionContext<'env, 'a>>, AddPackageErr>
+    where
+        'env: 'a,
+    {
+        loop {
+            if let Some((name, dependency, range)) = context.pending_edges.pop() {
                 log::debug!("processing package {name}: dependency {dependency} version {range}");
 
-                if context.should_add_package(dependency, range) {
-                    let found = env
-                        .find_package_by_name(
-                            dependency,
-                            VersionSelector::range_for(unity_version, range),
-                        )
-                        .or_else(|| {
-                            env.find_package_by_name(
-                                dependency,
-                                VersionSelector::range_for(None, range),
-                            )
-                        })
-                        .ok_or_else(|| AddPackageErr::DependencyNotFound {
-                            dependency_name: dependency.clone(),
-                        })?;
+                if !context.should_add_package(dependency, range) {
+                    continue;
+                }
+
+                let preferred = match self.find_preferred(dependency, range) {
+                    Ok(preferred) => preferred,
+                    // other versions of the dependant may not require this, so this is a dead branch.
+                    // if there is no solution, the greedy resolution reports the error.
+                    Err(_) if self.strict => return Ok(None),
+                    Err(e) => return Err(e),
+                };
 
+                if !self.strict {
                     // remove existing if existing
-                    context.pending_queue.add_pending_package(found);
+                    context.pending_queue.add_pending_package(preferred);
+                    continue;
+                }
+
+                for candidate in self.candidates(&context, preferred, dependency, range) {
+                    if self.remaining_budget == 0 {
+                        return Ok(None);
+                    }
+                    self.remaining_budget -= 1;
+
+                    log::debug!(
+                        "processing package {name}: trying {dependency} version {}",
+                        candidate.version()
+                    );
+                    let mut branch = context.clone();
+                    branch.pending_queue.add_pending_package(candidate);
+                    if let Some(resolved) = self.resolve(branch)? {
+                        return Ok(Some(resolved));
+                    }
                 }
+
+                log::debug!(
+                    "processing package {name}: no version of {dependency} satisfies all requirements"
+                );
+                return Ok(None);
+            } else if let Some(x) = context.pending_queue.next_package() {
+                log::debug!("processing package {} version {}", x.name(), x.version());
+                let name = x.name();
+                let vpm_dependencies = x.vpm_dependencies();
+
+                if context.add_package(x) {
+                    // add new dependencies
+                    let overrides = context.overrides;
+                    context.pending_edges = vpm_dependencies
+                        .iter()
+                        .rev()
+                        .map(|(dependency, range)| {
+                            let range = overrides.range(name, dependency, range);
+                            (name, dependency.as_ref(), range)
+                        })
+                        .collect();
+                }
+            } else {
+                break;
             }
         }
+
+        if self.strict && !context.collect_conflicts().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(context))
+    }
+
+    fn find_preferred(
+        &self,
+        dependency: &str,
+        range: &VersionRange,
+    ) -> Result<PackageInfo<'env>, AddPackageErr> {
+        self.env
+            .find_package_by_name(
+                dependency,
+                self.strategy.range_for(self.unity_version, range),
+            )
+            .or_else(|| {
+                self.env
+                    .find_package_by_name(dependency, self.strategy.range_for(None, range))
+            })
+            .ok_or_else(|| AddPackageErr::DependencyNotFound {
+                dependency_name: dependency.into(),
+            })
     }
 
-    Ok(context.build_result())
+    /// Lists versions of the dependency which satisfy all current requirements.
+    ///
+    /// The preferred version is the first, then other versions compatible with the unity version,
+    /// then versions incompatible with the unity version, in the order of the strategy.
+    fn candidates(
+        &self,
+        context: &ResolutionContext<'env, '_>,
+        preferred: PackageInfo<'env>,
+        dependency: &str,
+        range: &VersionRange,
+    ) -> Vec<PackageInfo<'env>> {
+        let info = &context.dependencies[dependency];
+        let allow_prerelease = info.allow_pre || context.allow_prerelease;
+
+        let mut compatible = Vec::new();
+        let mut incompatible = Vec::new();
+        for package in self.env.find_packages(dependency) {
+            let package_json = package.package_json();
+            if VersionSelector::range_for(self.unity_version, range).satisfies(package_json) {
+                compatible.push(package);
+            } else if VersionSelector::range_for(None, range).satisfies(package_json) {
+                incompatible.push(package);
+            }
+        }
+        let order = |a: &PackageInfo, b: &PackageInfo| match self.strategy {
+            ResolutionStrategy::Latest => b.version().cmp(a.version()),
+            ResolutionStrategy::Lowest => a.version().cmp(b.version()),
+        };
+        compatible.sort_by(order);
+        incompatible.sort_by(order);
+
+        let mut versions = HashSet::new();
+        std::iter::once(preferred)
+            .chain(compatible)
+            .chain(incompatible)
+            .filter(|package| {
+                info.requirements
+                    .values()
+                    .all(|range| range.match_pre(package.version(), allow_prerelease))
+            })
+            .filter(|package| versions.insert(package.version()))
+            .collect()
+    }
 }
@@ -3,17 +3,19 @@ use crate::io::ProjectIo;
 use crate::traits::EnvironmentIoHolder;
 use crate::unity_project::find_legacy_assets::collect_legacy_assets;
 use crate::utils::{copy_recursive, extract_zip};
-use crate::version::DependencyRange;
+use crate::version::{DependencyRange, UnityVersion, Version, VersionRange};
 use crate::{
     unity_compatible, PackageInfo, PackageInfoInner, RemotePackageDownloader, UnityProject,
 };
 use either::Either;
-use futures::future::{join3, join_all, try_join_all};
+use futures::future::{join, join_all, try_join_all};
+use futures::StreamExt;
 use log::debug;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::pin::pin;
 
 /// Represents Packages to be added and folders / packages to be removed
 ///
@@ -98,6 +100,7 @@ pub enum RemoveReason {
 pub struct ConflictInfo {
     conflicts_packages: Vec<Box<str>>,
     conflicts_with_unity: bool,
+    requirement_chains: Vec<Vec<RequirementEdge>>,
 }
 
 impl ConflictInfo {
@@ -108,6 +111,58 @@ impl ConflictInfo {
     pub fn conflicts_with_unity(&self) -> bool {
         self.conflicts_with_unity
     }
+
+    /// The chains of `vpmDependencies` requirements which caused this conflict.
+    ///
+    /// Each chain starts from the project or a requested package, and ends with the requirement
+    /// to the conflicting package.
+    /// The chains of requirements not satisfied by the selected version come first,
+    /// followed by the chains of requirements the selected version is chosen for.
+    pub fn requirement_chains(&self) -> &[Vec<RequirementEdge>] {
+        self.requirement_chains.as_slice()
+    }
+}
+
+/// A requirement to a package by the project or another package
+#[derive(Debug, Clone)]
+pub struct RequirementEdge {
+    dependant: Option<(Box<str>, Option<Version>)>,
+    dependency: Box<str>,
+    range: VersionRange,
+}
+
+impl RequirementEdge {
+    pub(crate) fn new(
+        dependant: Option<(Box<str>, Option<Version>)>,
+        dependency: Box<str>,
+        range: VersionRange,
+    ) -> Self {
+        Self {
+            dependant,
+            dependency,
+            range,
+        }
+    }
+
+    /// The name of the package requiring the dependency, or `None` if it's the project itself
+    pub fn dependant_name(&self) -> Option<&str> {
+        self.dependant.as_ref().map(|(name, _)| name.as_ref())
+    }
+
+    /// The version of the package requiring the dependency if known
+    pub fn dependant_version(&self) -> Option<&Version> {
+        self.dependant
+            .as_ref()
+            .and_then(|(_, version)| version.as_ref())
+    }
+
+    pub fn dependency(&self) -> &str {
+        &self.dependency
+    }
+
+    pub fn range(&self) -> &VersionRange {
+        &self.range
+    }
 }
 
 pub(crate) struct Builder<'env> {
@@ -223,6 +278,19 @@ impl<'env> Builder<'env> {
         self
     }
 
+    pub fn conflict_requirement_chains(
+        &mut self,
+        name: Box<str>,
+        chains: impl IntoIterator<Item = Vec<RequirementEdge>>,
+    ) -> &mut Self {
+        self.conflicts
+            .entry(name)
+            .or_default()
+            .requirement_chains
+            .extend(chains);
+        self
+    }
+
     pub fn conflicts_unity(&mut self, name: Box<str>) -> &mut Self {
         self.conflicts.entry(name).or_default().conflicts_with_unity = true;
         self
@@ -317,8 +385,20 @@ impl<'env> Builder<'env> {
     }
 
     pub async fn build_resolve(
+        self,
+        unity_project: &UnityProject<impl ProjectIo>,
+    ) -> PendingProjectChanges<'env> {
+        let unity_version = unity_project.unity_version;
+        self.build_resolve_for_unity(unity_project, unity_version)
+            .await
+    }
+
+    /// Same as [`build_resolve`](Self::build_resolve) but checks compatibility of installing
+    /// packages with the specified unity version instead of the one of the project.
+    pub(crate) async fn build_resolve_for_unity(
         mut self,
         unity_project: &UnityProject<impl ProjectIo>,
+        unity_version: Option<UnityVersion>,
     ) -> PendingProjectChanges<'env> {
         let installs = Vec::from_iter(
             self.package_changes
@@ -328,7 +408,7 @@ impl<'env> Builder<'env> {
                 .map(|x| x.package.unwrap()),
         );
 
-        if let Some(unity) = unity_project.unity_version {
+        if let Some(unity) = unity_version {
             for package in installs
                 .iter()
                 .filter(|pkg| !unity_compatible(pkg.package_json(), unity))
@@ -495,12 +575,23 @@ impl PendingProjectChanges<'_> {
 
 impl<IO: ProjectIo> UnityProject<IO> {
     /// Applies the changes specified in `AddPackageRequest` to the project.
+    ///
+    /// The packages are extracted to the temporary folder in the project first,
+    /// and then swapped with the existing package folders.
+    /// If installing or removing some package fails, package folders are restored to the previous state
+    /// and the manifest is not updated.
+    ///
+    /// Legacy assets are removed after the package folders are swapped, and they are not backed up.
+    /// The manifest is updated only in memory, so callers need to save the project,
+    /// and the package folders are not restored if saving fails.
     pub async fn apply_pending_changes<'env, Env: RemotePackageDownloader + EnvironmentIoHolder>(
         &mut self,
         env: &'env Env,
         request: PendingProjectChanges<'env>,
     ) -> io::Result<()> {
         let mut installs = Vec::new();
+        let mut add_to_locked = Vec::new();
+        let mut add_to_dependencies = Vec::new();
         let mut remove_names = Vec::new();
 
         for (name, change) in request.package_changes {
@@ -509,16 +600,12 @@ impl<IO: ProjectIo> UnityProject<IO> {
                     if let Some(package) = change.package {
                         installs.push(package);
                         if change.add_to_locked {
-                            self.manifest.add_locked(
-                                package.name(),
-                                package.version().clone(),
-                                package.vpm_dependencies().clone(),
-                            );
+                            add_to_locked.push(package);
                         }
                     }
 
                     if let Some(version) = change.to_dependencies {
-                        self.manifest.add_dependency(&name, version);
+                        add_to_dependencies.push((name, version));
                     }
                 }
                 PackageChange::Remove(_) => {
@@ -527,11 +614,24 @@ impl<IO: ProjectIo> UnityProject<IO> {
             }
         }
 
+        install_packages(&self.io, env, &installs, &remove_names).await?;
+
+        // package folders are updated successfully so we update the manifest
+        for package in add_to_locked {
+            self.manifest.add_locked(
+                package.name(),
+                package.version().clone(),
+                package.vpm_dependencies().clone(),
+            );
+        }
+
+        for (name, version) in add_to_dependencies {
+            self.manifest.add_dependency(&name, version);
+        }
+
         self.manifest
             .remove_packages(remove_names.iter().map(Box::as_ref));
 
-        install_packages(&self.io, env, &installs).await?;
-
         remove_assets(
             &self.io,
             request.remove_legacy_files.iter().map(|(p, _)| p.as_ref()),
@@ -539,7 +639,6 @@ impl<IO: ProjectIo> UnityProject<IO> {
                 .remove_legacy_folders
                 .iter()
                 .map(|(p, _)| p.as_ref()),
-            remove_names.iter().map(Box::as_ref),
         )
         .await;
 
@@ -547,38 +646,181 @@ impl<IO: ProjectIo> UnityProject<IO> {
     }
 }
 
+/// The folder to stage the package changes.
+///
+/// This is not in the `Temp` folder since unity removes it,
+/// and the folder may contain the only copy of packages if restoring them failed.
+const TRANSACTION_FOLDER: &str = ".vrc-get-transaction";
+
 async fn install_packages<Env: RemotePackageDownloader + EnvironmentIoHolder>(
     io: &impl ProjectIo,
     env: &Env,
     packages: &[PackageInfo<'_>],
+    remove_names: &[Box<str>],
 ) -> io::Result<()> {
-    // resolve all packages
-    try_join_all(
-        packages
-            .iter()
-            .map(|package| add_package(io, env, *package)),
+    let transaction = Path::new(TRANSACTION_FOLDER);
+    let staging = transaction.join("staging");
+    let backup = transaction.join("backup");
+
+    // the backup folder left by previous failed process may contain the only copy of packages
+    if has_entries(io, &backup).await {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} is left by previous failed process. move packages in it back to Packages and remove it",
+                backup.display()
+            ),
+        ));
+    }
+
+    // the staging folder left by previous failed process is safe to remove
+    io.remove_dir_all(transaction).await.ok();
+
+    // first, extract all packages to the staging folder
+    let staged = async {
+        io.create_dir_all(&staging).await?;
+        io.create_dir_all(&backup).await?;
+        io.create_dir_all("Packages".as_ref()).await?;
+
+        try_join_all(
+            packages
+                .iter()
+                .map(|package| add_package(io, env, *package, staging.join(package.name()))),
+        )
+        .await
+    }
+    .await;
+
+    if let Err(err) = staged {
+        remove_transaction_folder(io).await;
+        return Err(err);
+    }
+
+    // then, swap the package folders
+    let swapped = swap_packages(
+        io,
+        &staging,
+        &backup,
+        packages.iter().map(|x| x.name()),
+        remove_names.iter().map(Box::as_ref),
     )
-    .await?;
+    .await;
 
-    Ok(())
+    match swapped {
+        Ok(()) => {
+            remove_transaction_folder(io).await;
+            Ok(())
+        }
+        Err(SwapError::RolledBack(err)) => {
+            remove_transaction_folder(io).await;
+            Err(err)
+        }
+        Err(SwapError::RollbackFailed(err)) => {
+            // we keep the transaction folder since it may contain the only copy of packages
+            log::error!(
+                "failed to restore package folders. previous packages are kept in {}",
+                backup.display()
+            );
+            Err(err)
+        }
+    }
+}
+
+async fn has_entries(io: &impl ProjectIo, path: &Path) -> bool {
+    match io.read_dir(path).await {
+        Ok(entries) => pin!(entries).next().await.is_some(),
+        Err(_) => false,
+    }
+}
+
+async fn remove_transaction_folder(io: &impl ProjectIo) {
+    if let Some(err) = io.remove_dir_all(TRANSACTION_FOLDER.as_ref()).await.err() {
+        log::error!("error removing {TRANSACTION_FOLDER}: {err}");
+    }
+}
+
+enum SwapError {
+    RolledBack(io::Error),
+    RollbackFailed(io::Error),
+}
+
+async fn swap_packages<'a>(
+    io: &impl ProjectIo,
+    staging: &Path,
+    backup: &Path,
+    installs: impl Iterator<Item = &'a str> + Clone,
+    removes: impl Iterator<Item = &'a str>,
+) -> Result<(), SwapError> {
+    let packages = Path::new("Packages");
+
+    // names of packages moved to the backup folder
+    let mut backed_up = Vec::new();
+    // names of packages moved from the staging folder
+    let mut installed = Vec::new();
+
+    let result = async {
+        for name in installs.clone().chain(removes) {
+            match io.rename(&packages.join(name), &backup.join(name)).await {
+                Ok(()) => backed_up.push(name),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        for name in installs {
+            io.rename(&staging.join(name), &packages.join(name)).await?;
+            installed.push(name);
+        }
+
+        Ok(())
+    }
+    .await;
+
+    let Err(err) = result else {
+        return Ok(());
+    };
+
+    log::error!("error updating package folders, restoring previous packages: {err}");
+
+    let mut rollback_failed = false;
+
+    for name in installed.iter().rev() {
+        if let Some(err) = io.remove_dir_all(&packages.join(name)).await.err() {
+            log::error!("error removing package {name}: {err}");
+            rollback_failed = true;
+        }
+    }
+
+    for name in backed_up.iter().rev() {
+        if let Some(err) = io
+            .rename(&backup.join(name), &packages.join(name))
+            .await
+            .err()
+        {
+            log::error!("error restoring package {name}: {err}");
+            rollback_failed = true;
+        }
+    }
+
+    if rollback_failed {
+        Err(SwapError::RollbackFailed(err))
+    } else {
+        Err(SwapError::RolledBack(err))
+    }
 }
 
 async fn remove_assets(
     io: &impl ProjectIo,
     legacy_files: impl Iterator<Item = &Path>,
     legacy_folders: impl Iterator<Item = &Path>,
-    legacy_packages: impl Iterator<Item = &str>,
 ) {
-    join3(
+    join(
         join_all(legacy_files.map(|relative| async move {
             remove_file(io, relative).await;
         })),
         join_all(legacy_folders.map(|relative| async move {
             remove_folder(io, relative).await;
         })),
-        join_all(legacy_packages.map(|name| async move {
-            remove_package(io, name).await;
-        })),
     )
     .await;
 
@@ -607,25 +849,15 @@ async fn remove_assets(
         }
         remove_meta_file(io, path.to_owned()).await;
     }
-
-    async fn remove_package(io: &impl ProjectIo, name: &str) {
-        if let Some(err) = io
-            .remove_dir_all(format!("Packages/{}", name).as_ref())
-            .await
-            .err()
-        {
-            log::error!("error removing legacy package {}: {}", name, err);
-        }
-    }
 }
 
 pub(crate) async fn add_package<Env: RemotePackageDownloader + EnvironmentIoHolder>(
     io: &impl ProjectIo,
     env: &Env,
     package: PackageInfo<'_>,
+    dest_folder: PathBuf,
 ) -> io::Result<()> {
     log::debug!("adding package {}", package.name());
-    let dest_folder = PathBuf::from(format!("Packages/{}", package.name()));
     match package.inner {
         PackageInfoInner::Remote(package, user_repo) => {
             let zip_file = env.get_package(user_repo, package).await?;
@@ -1,9 +1,10 @@
 use crate::io::ProjectIo;
+use crate::unity_project::package_resolution::DependencyOverrides;
 use crate::unity_project::{
     package_resolution, pending_project_changes, AddPackageErr, LockedDependencyInfo,
-    PendingProjectChanges,
+    PendingProjectChanges, ResolutionStrategy,
 };
-use crate::version::DependencyRange;
+use crate::version::{DependencyRange, Version, VersionRange};
 use crate::{PackageCollection, UnityProject, VersionSelector};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
@@ -12,7 +13,25 @@ use std::fmt;
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum ResolvePackageErr {
-    DependencyNotFound { dependency_name: Box<str> },
+    DependencyNotFound {
+        dependency_name: Box<str>,
+    },
+    LockedVersionNotFound {
+        package_name: Box<str>,
+        version: Version,
+    },
+    DependencyNotLocked {
+        dependency_name: Box<str>,
+        // None for dependencies of the project
+        required_by: Option<Box<str>>,
+    },
+    InconsistentLockedVersion {
+        package_name: Box<str>,
+        locked_version: Version,
+        // None for dependencies of the project
+        required_by: Option<Box<str>>,
+        required_range: VersionRange,
+    },
 }
 
 impl fmt::Display for ResolvePackageErr {
@@ -22,6 +41,42 @@ impl fmt::Display for ResolvePackageErr {
                 f,
                 "Package {dependency_name} (maybe dependencies of the package) not found"
             ),
+            ResolvePackageErr::LockedVersionNotFound {
+                package_name,
+                version,
+            } => write!(f, "Locked package {package_name} version {version} not found"),
+            ResolvePackageErr::DependencyNotLocked {
+                dependency_name,
+                required_by: None,
+            } => write!(
+                f,
+                "Package {dependency_name} is in dependencies but not locked"
+            ),
+            ResolvePackageErr::DependencyNotLocked {
+                dependency_name,
+                required_by: Some(required_by),
+            } => write!(
+                f,
+                "Package {dependency_name} is required by {required_by} but not locked"
+            ),
+            ResolvePackageErr::InconsistentLockedVersion {
+                package_name,
+                locked_version,
+                required_by: None,
+                required_range,
+            } => write!(
+                f,
+                "Locked version {locked_version} of {package_name} does not match {required_range} in dependencies"
+            ),
+            ResolvePackageErr::InconsistentLockedVersion {
+                package_name,
+                locked_version,
+                required_by: Some(required_by),
+                required_range,
+            } => write!(
+                f,
+                "Locked version {locked_version} of {package_name} does not match {required_range} required by {required_by}"
+            ),
         }
     }
 }
@@ -47,6 +102,17 @@ impl<IO: ProjectIo> UnityProject<IO> {
     pub async fn resolve_request<'env>(
         &self,
         env: &'env impl PackageCollection,
+    ) -> Result<PendingProjectChanges<'env>, AddPackageErr> {
+        self.resolve_request_with_strategy(env, ResolutionStrategy::Latest)
+            .await
+    }
+
+    /// Same as [`resolve_request`](Self::resolve_request) but the versions of
+    /// packages not locked yet are chosen with the specified strategy.
+    pub async fn resolve_request_with_strategy<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+        strategy: ResolutionStrategy,
     ) -> Result<PendingProjectChanges<'env>, AddPackageErr> {
         let mut changes = pending_project_changes::Builder::new();
 
@@ -63,10 +129,97 @@ impl<IO: ProjectIo> UnityProject<IO> {
 
         // then, process packages in dependencies but not in locked.
         // This usually happens with template projects.
-        self.add_just_dependency(env, &mut changes)?;
+        self.add_just_dependency(env, strategy, &mut changes)?;
 
         // finally, process dependencies of unlocked packages.
-        self.resolve_unlocked(env, &mut changes)?;
+        self.resolve_unlocked(env, strategy, &mut changes)?;
+
+        Ok(changes.build_resolve(self).await)
+    }
+
+    /// Creates changes to install exactly the locked versions of packages.
+    ///
+    /// Unlike [`resolve_request`](Self::resolve_request), this never changes `vpm-manifest.json`.
+    /// This fails if some locked version is not found, or the locked versions are inconsistent
+    /// with `dependencies` or `dependencies` of other locked packages.
+    pub async fn resolve_request_frozen<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+    ) -> Result<PendingProjectChanges<'env>, ResolvePackageErr> {
+        // first, check the locked versions are consistent with dependencies
+        for (name, range) in self.manifest.dependencies() {
+            let Some(locked) = self.manifest.get_locked(name) else {
+                return Err(ResolvePackageErr::DependencyNotLocked {
+                    dependency_name: name.into(),
+                    required_by: None,
+                });
+            };
+
+            let matches = if let Some(min_ver) = range.as_single_version() {
+                locked.version() >= &min_ver
+            } else {
+                range.as_range().match_pre(locked.version(), true)
+            };
+
+            if !matches {
+                return Err(ResolvePackageErr::InconsistentLockedVersion {
+                    package_name: name.into(),
+                    locked_version: locked.version().clone(),
+                    required_by: None,
+                    required_range: range.as_range(),
+                });
+            }
+        }
+
+        let unlocked_packages = self
+            .unlocked_packages()
+            .iter()
+            .filter_map(|(_, pkg)| pkg.as_ref());
+
+        let unlocked_names: HashSet<_> = unlocked_packages.clone().map(|x| x.name()).collect();
+
+        let dependencies_of_unlocked =
+            unlocked_packages.map(|pkg| (pkg.name(), pkg.vpm_dependencies()));
+
+        let dependencies_of_locked = self
+            .manifest
+            .all_locked()
+            .map(|locked| (locked.name(), locked.dependencies()));
+
+        for (dependant, dependencies) in dependencies_of_locked.chain(dependencies_of_unlocked) {
+            for (dependency, range) in dependencies {
+                let range = self.dependency_range(dependant, dependency, range);
+                if let Some(locked) = self.manifest.get_locked(dependency) {
+                    if !range.match_pre(locked.version(), true) {
+                        return Err(ResolvePackageErr::InconsistentLockedVersion {
+                            package_name: dependency.clone(),
+                            locked_version: locked.version().clone(),
+                            required_by: Some(dependant.into()),
+                            required_range: range.clone(),
+                        });
+                    }
+                } else if !unlocked_names.contains(dependency.as_ref()) {
+                    return Err(ResolvePackageErr::DependencyNotLocked {
+                        dependency_name: dependency.clone(),
+                        required_by: Some(dependant.into()),
+                    });
+                }
+            }
+        }
+
+        // then, install locked packages
+        let mut changes = pending_project_changes::Builder::new();
+
+        for dep in self.manifest.all_locked() {
+            let pkg = env
+                .find_package_by_name(dep.name(), VersionSelector::specific_version(dep.version()))
+                .ok_or_else(|| ResolvePackageErr::LockedVersionNotFound {
+                    package_name: dep.name().into(),
+                    version: dep.version().clone(),
+                })?;
+
+            changes.install_already_locked(pkg);
+        }
 
         Ok(changes.build_resolve(self).await)
     }
@@ -74,6 +227,7 @@ impl<IO: ProjectIo> UnityProject<IO> {
     fn add_just_dependency<'env>(
         &self,
         env: &'env impl PackageCollection,
+        strategy: ResolutionStrategy,
         changes: &mut pending_project_changes::Builder<'env>,
     ) -> Result<(), AddPackageErr> {
         let mut to_install = vec![];
@@ -84,7 +238,7 @@ impl<IO: ProjectIo> UnityProject<IO> {
                 to_install.push(
                     env.find_package_by_name(
                         name,
-                        VersionSelector::range_for(self.unity_version(), &range.as_range()),
+                        strategy.range_for(self.unity_version(), &range.as_range()),
                     )
                     .ok_or_else(|| AddPackageErr::DependencyNotFound {
                         dependency_name: name.into(),
@@ -104,6 +258,8 @@ impl<IO: ProjectIo> UnityProject<IO> {
             self.manifest.dependencies(),
             self.manifest.all_locked(),
             |pkg| self.manifest.get_locked(pkg),
+            &DependencyOverrides::new(self.manifest.dependency_overrides()),
+            strategy,
             self.unity_version(),
             env,
             to_install,
@@ -124,12 +280,17 @@ impl<IO: ProjectIo> UnityProject<IO> {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        for (package, chains) in result.requirement_chains {
+            changes.conflict_requirement_chains(package, chains);
+        }
+
         Ok(())
     }
 
     fn resolve_unlocked<'env>(
         &self,
         env: &'env impl PackageCollection,
+        strategy: ResolutionStrategy,
         changes: &mut pending_project_changes::Builder<'env>,
     ) -> Result<(), AddPackageErr> {
         if self.unlocked_packages().is_empty() {
@@ -155,7 +316,14 @@ impl<IO: ProjectIo> UnityProject<IO> {
             .unlocked_packages
             .iter()
             .filter_map(|(_, pkg)| pkg.as_ref())
-            .flat_map(|pkg| pkg.vpm_dependencies());
+            .flat_map(|pkg| {
+                pkg.vpm_dependencies().iter().map(|(dependency, range)| {
+                    (
+                        dependency,
+                        self.dependency_range(pkg.name(), dependency, range),
+                    )
+                })
+            });
 
         let unlocked_dependencies_versions = dependencies_of_unlocked_packages
             .filter(|(k, _)| self.manifest.get_locked(k.as_ref()).is_none()) // skip if already installed to locked
@@ -184,13 +352,10 @@ impl<IO: ProjectIo> UnityProject<IO> {
         let unlocked_dependencies = unlocked_dependencies_versions
             .into_iter()
             .map(|(pkg_name, ranges)| {
-                env.find_package_by_name(
-                    pkg_name,
-                    VersionSelector::ranges_for(self.unity_version, &ranges),
-                )
-                .ok_or_else(|| AddPackageErr::DependencyNotFound {
-                    dependency_name: pkg_name.clone(),
-                })
+                env.find_package_by_name(pkg_name, strategy.ranges_for(self.unity_version, &ranges))
+                    .ok_or_else(|| AddPackageErr::DependencyNotFound {
+                        dependency_name: pkg_name.clone(),
+                    })
             })
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -202,6 +367,8 @@ impl<IO: ProjectIo> UnityProject<IO> {
             self.manifest.dependencies(),
             virtual_locked_dependencies.values().cloned(),
             |pkg| virtual_locked_dependencies.get(pkg).cloned(),
+            &DependencyOverrides::new(self.manifest.dependency_overrides()),
+            strategy,
             self.unity_version(),
             env,
             unlocked_dependencies,
@@ -216,6 +383,10 @@ impl<IO: ProjectIo> UnityProject<IO> {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        for (package, chains) in result.requirement_chains {
+            changes.conflict_requirement_chains(package, chains);
+        }
+
         Ok(())
     }
 }
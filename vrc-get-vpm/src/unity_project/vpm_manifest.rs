@@ -1,12 +1,12 @@
 use crate::io;
 use crate::io::ProjectIo;
 use crate::unity_project::LockedDependencyInfo;
-use crate::utils::{load_json_or_default, SaveController};
+use crate::utils::{deserialize_json_slice, load_json_or_default, SaveController};
 use crate::version::{DependencyRange, Version, VersionRange};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 
-const MANIFEST_PATH: &str = "Packages/vpm-manifest.json";
+pub(super) const MANIFEST_PATH: &str = "Packages/vpm-manifest.json";
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,6 +15,28 @@ struct AsJson {
     dependencies: IndexMap<Box<str>, VpmDependency>,
     #[serde(default)]
     locked: IndexMap<Box<str>, VpmLockedDependency>,
+    #[serde(rename = "vrc-get")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vrc_get: Option<VrcGetMeta>,
+}
+
+/// vrc-get specific extension of vpm-manifest.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VrcGetMeta {
+    /// The packages which should not be upgraded without explicit request
+    #[serde(default, skip_serializing_if = "IndexSet::is_empty")]
+    held_packages: IndexSet<Box<str>>,
+    /// The version ranges replacing ones declared in `vpmDependencies` of the package.
+    /// The key is the name of the declaring package, and the value maps dependency name to range.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    overrides: IndexMap<Box<str>, IndexMap<Box<str>, VersionRange>>,
+}
+
+impl VrcGetMeta {
+    fn is_empty(&self) -> bool {
+        self.held_packages.is_empty() && self.overrides.is_empty()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -43,6 +65,13 @@ impl VpmManifest {
         })
     }
 
+    /// Parses the manifest to replace the one of the project. The parsed manifest is saved as changed.
+    pub(super) fn from_slice(bytes: &[u8]) -> io::Result<Self> {
+        let mut controller = SaveController::new(AsJson::default());
+        *controller.as_mut() = deserialize_json_slice(bytes)?;
+        Ok(Self { controller })
+    }
+
     pub(super) fn dependencies(&self) -> impl Iterator<Item = (&str, &DependencyRange)> {
         self.controller
             .dependencies
@@ -98,9 +127,63 @@ impl VpmManifest {
         for name in names {
             self.controller.as_mut().locked.shift_remove(name);
             self.controller.as_mut().dependencies.shift_remove(name);
+            self.set_held(name, false);
         }
     }
 
+    pub(super) fn held_packages(&self) -> impl Iterator<Item = &str> {
+        self.controller
+            .vrc_get
+            .iter()
+            .flat_map(|x| x.held_packages.iter())
+            .map(Box::as_ref)
+    }
+
+    pub(super) fn is_held(&self, package: &str) -> bool {
+        (self.controller.vrc_get.as_ref()).is_some_and(|x| x.held_packages.contains(package))
+    }
+
+    pub(super) fn set_held(&mut self, package: &str, held: bool) {
+        self.controller.may_changing(|json| {
+            if held {
+                let meta = json.vrc_get.get_or_insert_with(Default::default);
+                meta.held_packages.insert(package.into())
+            } else if let Some(meta) = json.vrc_get.as_mut() {
+                let changed = meta.held_packages.shift_remove(package);
+                if meta.is_empty() {
+                    json.vrc_get = None;
+                }
+                changed
+            } else {
+                false
+            }
+        });
+    }
+
+    pub(super) fn dependency_overrides(&self) -> impl Iterator<Item = (&str, &str, &VersionRange)> {
+        self.controller
+            .vrc_get
+            .iter()
+            .flat_map(|x| x.overrides.iter())
+            .flat_map(|(dependant, overrides)| {
+                overrides.iter().map(move |(dependency, range)| {
+                    (dependant.as_ref(), dependency.as_ref(), range)
+                })
+            })
+    }
+
+    pub(super) fn get_dependency_override(
+        &self,
+        dependant: &str,
+        dependency: &str,
+    ) -> Option<&VersionRange> {
+        self.controller
+            .vrc_get
+            .as_ref()
+            .and_then(|x| x.overrides.get(dependant))
+            .and_then(|x| x.get(dependency))
+    }
+
     pub(crate) fn has_any(&self) -> bool {
         !self.controller.locked.is_empty() || !self.controller.dependencies.is_empty()
     }
@@ -0,0 +1,87 @@
+use crate::io;
+use crate::io::IoTrait;
+use crate::traits::HttpClient;
+use futures::prelude::*;
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use url::Url;
+
+/// Returns true if the url is `file://` url, which is read from the file system instead of HTTP.
+pub(crate) fn is_file_url(url: &Url) -> bool {
+    url.scheme() == "file"
+}
+
+/// Returns true if `url` listed by the repository at `repo_url` can be used.
+///
+/// Remote repositories must not make us read local files, so `file://` urls are
+/// only allowed in `file://` repositories and local repositories without url.
+pub(crate) fn is_allowed_in_repo(url: &Url, repo_url: Option<&Url>) -> bool {
+    !is_file_url(url) || repo_url.is_none_or(is_file_url)
+}
+
+/// Returns the local path of the `file://` url, or `None` for other urls.
+pub(crate) fn file_url_path(url: &Url) -> io::Result<Option<PathBuf>> {
+    if !is_file_url(url) {
+        return Ok(None);
+    }
+
+    url.to_file_path().map(Some).map_err(|()| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{url}' is not a local file url"),
+        )
+    })
+}
+
+/// Reads the whole file with the etag made from the sha256 of the content.
+///
+/// Like `get_with_etag` of [`HttpClient`], returns `Ok(None)` if the etag matches `current_etag`.
+/// We use content hash instead of modification time since copying files to network shares
+/// or USB drives may not keep modification time.
+pub(crate) async fn read_file_with_etag(
+    io: &impl IoTrait,
+    path: &Path,
+    current_etag: Option<&str>,
+) -> io::Result<Option<(Vec<u8>, Box<str>)>> {
+    let bytes = read_file(io, path).await?;
+    let etag = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+    if current_etag == Some(etag.as_str()) {
+        return Ok(None);
+    }
+    Ok(Some((bytes, etag.into())))
+}
+
+/// Gets the whole resource at the url from the file system for `file://` urls and with `client` for others.
+pub(crate) async fn get_bytes(
+    client: Option<&impl HttpClient>,
+    io: &impl IoTrait,
+    url: &Url,
+    headers: &IndexMap<Box<str>, Box<str>>,
+) -> io::Result<Vec<u8>> {
+    if let Some(path) = file_url_path(url)? {
+        return read_file(io, &path).await;
+    }
+
+    let client = client.ok_or_else(offline_error)?;
+    let mut bytes = Vec::new();
+    pin!(client.get(url, headers).await?)
+        .read_to_end(&mut bytes)
+        .await?;
+    Ok(bytes)
+}
+
+pub(crate) fn offline_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "offline mode")
+}
+
+async fn read_file(io: &impl IoTrait, path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    io.open(path)
+        .await
+        .map_err(|e| io::Error::new(e.kind(), format!("reading {}: {e}", path.display())))?
+        .read_to_end(&mut bytes)
+        .await?;
+    Ok(bytes)
+}
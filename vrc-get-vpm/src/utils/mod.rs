@@ -2,8 +2,11 @@ mod copy_recursive;
 mod crlf_json_formatter;
 mod deup_deserializer;
 mod extract_zip;
+mod file_url;
+mod resolve_headers;
 mod save_controller;
 mod sha256_async_write;
+mod timer;
 
 use crate::io;
 use crate::io::{DirEntry, IoTrait};
@@ -22,8 +25,13 @@ pub(crate) use copy_recursive::copy_recursive;
 pub(crate) use crlf_json_formatter::to_vec_pretty_os_eol;
 pub(crate) use deup_deserializer::DedupForwarder;
 pub(crate) use extract_zip::extract_zip;
+pub(crate) use file_url::{
+    file_url_path, get_bytes, is_allowed_in_repo, is_file_url, offline_error, read_file_with_etag,
+};
+pub(crate) use resolve_headers::resolve_headers;
 pub(crate) use save_controller::SaveController;
 pub(crate) use sha256_async_write::Sha256AsyncWrite;
+pub(crate) use timer::{sleep, with_timeout};
 
 pub(crate) trait PathBufExt {
     fn joined(self, into: impl AsRef<Path>) -> Self;
@@ -268,3 +276,74 @@ where
         Err(e) => Err(e),
     }
 }
+
+/// Returns true if `name` matches `pattern`, where `*` matches any sequence of characters.
+pub(crate) fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always returns at least one element
+    let first = parts.next().unwrap();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let Some(last) = parts.next_back() else {
+        // no '*' in the pattern
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_matches() {
+        // no wildcard
+        assert!(wildcard_matches(
+            "com.anatawa12.package",
+            "com.anatawa12.package"
+        ));
+        assert!(!wildcard_matches(
+            "com.anatawa12.package",
+            "com.anatawa12.package2"
+        ));
+        assert!(!wildcard_matches("com.anatawa12.package", "com.anatawa12"));
+
+        // at the end
+        assert!(wildcard_matches("com.anatawa12.*", "com.anatawa12.package"));
+        assert!(wildcard_matches("com.anatawa12.*", "com.anatawa12."));
+        assert!(!wildcard_matches("com.anatawa12.*", "com.anatawa12"));
+
+        // at the start
+        assert!(wildcard_matches("*.package", "com.anatawa12.package"));
+        assert!(!wildcard_matches(
+            "*.package",
+            "com.anatawa12.package.tools"
+        ));
+
+        // in the middle
+        assert!(wildcard_matches("com.*.package", "com.anatawa12.package"));
+        assert!(wildcard_matches("com.*.package", "com..package"));
+        assert!(!wildcard_matches("com.*.package", "jp.anatawa12.package"));
+        assert!(!wildcard_matches("com.*.package", "com.package"));
+
+        // multiple wildcards
+        assert!(wildcard_matches("*", ""));
+        assert!(wildcard_matches("*anatawa12*", "com.anatawa12.package"));
+        assert!(wildcard_matches(
+            "com.*.*.tools",
+            "com.anatawa12.package.tools"
+        ));
+        assert!(!wildcard_matches("com.*.*.tools", "com.anatawa12.tools"));
+        assert!(!wildcard_matches("*ab*b", "xab"));
+    }
+}
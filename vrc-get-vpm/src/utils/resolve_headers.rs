@@ -0,0 +1,227 @@
+use crate::io;
+use futures::channel::oneshot;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long the output of credential helpers is reused.
+///
+/// Tokens from helpers may expire, so the helper is run again after this in long-running processes like the GUI.
+const CREDENTIAL_CACHE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+struct CachedCredential {
+    value: String,
+    resolved_at: Instant,
+}
+
+lazy_static! {
+    // the helper may be slow or interactive so we don't run it for each request
+    static ref CREDENTIAL_CACHE: Mutex<HashMap<String, CachedCredential>> = Mutex::new(HashMap::new());
+}
+
+/// Resolves references in header values if `resolve_references` is true.
+///
+/// The following references are supported:
+/// - `${env:NAME}`: the value of environment variable `NAME`
+/// - `${cmd:COMMAND}`: the standard output of `COMMAND` run with the shell, with surrounding whitespace trimmed
+///
+/// References are only resolved for repositories the user enabled them for on this machine
+/// since headers may come from repository lists or links made by others.
+/// Without that, values are sent as is.
+///
+/// The resolved values are only for requests and must not be saved anywhere.
+pub(crate) async fn resolve_headers(
+    headers: &IndexMap<Box<str>, Box<str>>,
+    resolve_references: bool,
+) -> io::Result<Cow<'_, IndexMap<Box<str>, Box<str>>>> {
+    if !resolve_references || !has_header_references(headers) {
+        return Ok(Cow::Borrowed(headers));
+    }
+
+    let mut resolved = IndexMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let value = resolve_value(value).await.map_err(|e| {
+            io::Error::new(e.kind(), format!("resolving value of header {name}: {e}"))
+        })?;
+        resolved.insert(name.clone(), value.into());
+    }
+    Ok(Cow::Owned(resolved))
+}
+
+/// Returns true if any of header values looks like containing references.
+pub(crate) fn has_header_references(headers: &IndexMap<Box<str>, Box<str>>) -> bool {
+    headers.values().any(|x| x.contains("${"))
+}
+
+async fn resolve_value(value: &str) -> io::Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference.find('}').ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "unclosed reference '${'")
+        })?;
+        result.push_str(&resolve_reference(&reference[..end]).await?);
+        rest = &reference[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+async fn resolve_reference(reference: &str) -> io::Result<String> {
+    if let Some(name) = reference.strip_prefix("env:") {
+        std::env::var(name).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("environment variable {name} is not set"),
+            )
+        })
+    } else if let Some(command) = reference.strip_prefix("cmd:") {
+        run_credential_helper(command).await
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown reference '${{{reference}}}'"),
+        ))
+    }
+}
+
+async fn run_credential_helper(command: &str) -> io::Result<String> {
+    if let Some(cached) = CREDENTIAL_CACHE.lock().unwrap().get(command) {
+        if cached.resolved_at.elapsed() < CREDENTIAL_CACHE_DURATION {
+            return Ok(cached.value.clone());
+        }
+    }
+
+    // the helper may take long time so we wait for it in another thread
+    // instead of blocking the async runtime
+    let (sender, receiver) = oneshot::channel();
+    let owned_command = command.to_owned();
+    std::thread::spawn(move || {
+        let output = if cfg!(windows) {
+            Command::new("cmd").arg("/C").arg(owned_command).output()
+        } else {
+            Command::new("sh").arg("-c").arg(owned_command).output()
+        };
+        sender.send(output).ok();
+    });
+    let output = receiver
+        .await
+        .map_err(|_| io::Error::other("credential helper thread panicked"))??;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "credential helper '{command}' failed with {}",
+            output.status
+        )));
+    }
+
+    let value = String::from_utf8(output.stdout)
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("output of credential helper '{command}' is not valid UTF-8"),
+            )
+        })?
+        .trim()
+        .to_owned();
+
+    CREDENTIAL_CACHE.lock().unwrap().insert(
+        command.to_owned(),
+        CachedCredential {
+            value: value.clone(),
+            resolved_at: Instant::now(),
+        },
+    );
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_value() {
+        std::env::set_var("VRC_GET_TEST_RESOLVE_HEADERS", "token");
+
+        let resolve_value = |value: &str| futures::executor::block_on(resolve_value(value));
+
+        assert_eq!(resolve_value("plain").unwrap(), "plain");
+        assert_eq!(
+            resolve_value("Bearer ${env:VRC_GET_TEST_RESOLVE_HEADERS}").unwrap(),
+            "Bearer token"
+        );
+        assert_eq!(
+            resolve_value(
+                "${env:VRC_GET_TEST_RESOLVE_HEADERS}:${env:VRC_GET_TEST_RESOLVE_HEADERS}"
+            )
+            .unwrap(),
+            "token:token"
+        );
+        assert!(resolve_value("${env:VRC_GET_TEST_RESOLVE_HEADERS_NOT_SET}").is_err());
+        assert!(resolve_value("${unknown:value}").is_err());
+        assert!(resolve_value("${env:VRC_GET_TEST_RESOLVE_HEADERS").is_err());
+    }
+
+    #[test]
+    fn test_resolve_headers_opt_in() {
+        std::env::set_var("VRC_GET_TEST_RESOLVE_HEADERS_OPT_IN", "token");
+
+        let mut headers = IndexMap::<Box<str>, Box<str>>::new();
+        headers.insert(
+            "Authorization".into(),
+            "Bearer ${env:VRC_GET_TEST_RESOLVE_HEADERS_OPT_IN}".into(),
+        );
+        assert!(has_header_references(&headers));
+
+        let resolve = |enabled| futures::executor::block_on(resolve_headers(&headers, enabled));
+
+        // references are kept as is unless enabled for the repository
+        assert_eq!(
+            &*resolve(false).unwrap()["Authorization"],
+            "Bearer ${env:VRC_GET_TEST_RESOLVE_HEADERS_OPT_IN}"
+        );
+        assert_eq!(&*resolve(true).unwrap()["Authorization"], "Bearer token");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_credential_helper() {
+        let value = futures::executor::block_on(resolve_value("${cmd:echo ' token '}")).unwrap();
+        assert_eq!(value, "token");
+        assert!(futures::executor::block_on(resolve_value("${cmd:exit 1}")).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_credential_helper_cache_expires() {
+        let command = "echo fresh";
+        let cache_with = |resolved_at| {
+            CREDENTIAL_CACHE.lock().unwrap().insert(
+                command.to_owned(),
+                CachedCredential {
+                    value: "cached".to_owned(),
+                    resolved_at,
+                },
+            );
+        };
+        let resolve = || futures::executor::block_on(run_credential_helper(command)).unwrap();
+
+        cache_with(Instant::now());
+        assert_eq!(resolve(), "cached");
+
+        // Instant may not go back that far right after the boot
+        if let Some(expired) = Instant::now().checked_sub(CREDENTIAL_CACHE_DURATION) {
+            cache_with(expired);
+            assert_eq!(resolve(), "fresh");
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Timers independent of async runtime.
+//!
+//! The library is used with tokio and with other executors like `futures::executor`,
+//! so we sleep in another thread and wake the future with a channel.
+//! The thread exits as soon as the future is dropped so completed fetches don't leave sleeping threads.
+
+use crate::io;
+use futures::channel::oneshot;
+use futures::future::{select, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Completes after `duration`.
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    spawn_timer(duration).1
+}
+
+fn spawn_timer(duration: Duration) -> (JoinHandle<()>, impl Future<Output = ()>) {
+    let (wake_sender, wake_receiver) = oneshot::channel();
+    let (cancel_sender, cancel_receiver) = mpsc::channel::<()>();
+    let thread = std::thread::spawn(move || {
+        // disconnected means the future is dropped
+        if let Err(mpsc::RecvTimeoutError::Timeout) = cancel_receiver.recv_timeout(duration) {
+            wake_sender.send(()).ok();
+        }
+    });
+    let future = async move {
+        let _cancel_sender = cancel_sender;
+        wake_receiver.await.ok();
+    };
+    (thread, future)
+}
+
+/// Runs `future` and fails with `TimedOut` if it doesn't complete within `timeout`.
+pub(crate) async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match select(pin!(future), pin!(sleep(timeout))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(((), _)) => Err(io::Error::new(io::ErrorKind::TimedOut, "timed out")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future;
+
+    #[test]
+    fn timed_out() {
+        let result = block_on(with_timeout(
+            Duration::from_millis(10),
+            future::pending::<io::Result<()>>(),
+        ));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn thread_exits_when_dropped() {
+        let (thread, future) = spawn_timer(Duration::from_secs(60 * 60));
+        drop(future);
+        thread.join().unwrap();
+    }
+}
@@ -21,6 +21,14 @@ enum SelectorInner<'a> {
         project_unity: Option<UnityVersion>,
         ranges: &'a [&'a VersionRange],
     },
+    LowestRange {
+        project_unity: Option<UnityVersion>,
+        range: &'a VersionRange,
+    },
+    LowestRanges {
+        project_unity: Option<UnityVersion>,
+        ranges: &'a [&'a VersionRange],
+    },
 }
 
 impl<'a> VersionSelector<'a> {
@@ -56,9 +64,65 @@ impl<'a> VersionSelector<'a> {
             },
         }
     }
+
+    /// Same as [`range_for`](Self::range_for) but the lowest matching version is preferred.
+    pub fn lowest_range_for(unity_version: Option<UnityVersion>, range: &'a VersionRange) -> Self {
+        Self {
+            inner: SelectorInner::LowestRange {
+                project_unity: unity_version,
+                range,
+            },
+        }
+    }
+
+    /// Same as [`ranges_for`](Self::ranges_for) but the lowest matching version is preferred.
+    pub fn lowest_ranges_for(
+        unity_version: Option<UnityVersion>,
+        ranges: &'a [&'a VersionRange],
+    ) -> Self {
+        Self {
+            inner: SelectorInner::LowestRanges {
+                project_unity: unity_version,
+                ranges,
+            },
+        }
+    }
 }
 
 impl<'a> VersionSelector<'a> {
+    /// Returns true if the lowest version satisfying this selector should be used
+    /// instead of the latest one.
+    pub fn prefers_lowest(&self) -> bool {
+        matches!(
+            self.inner,
+            SelectorInner::LowestRange { .. } | SelectorInner::LowestRanges { .. }
+        )
+    }
+
+    /// Selects the preferred one from the packages satisfying this selector.
+    ///
+    /// If there are multiple packages with the same version, the first one is selected.
+    pub fn select_preferred<T>(
+        &self,
+        packages: impl Iterator<Item = T>,
+        version: impl Fn(&T) -> &Version,
+    ) -> Option<T> {
+        let prefers_lowest = self.prefers_lowest();
+        packages.reduce(|selected, package| {
+            let ordering = version(&package).cmp(version(&selected));
+            let preferred = if prefers_lowest {
+                ordering.is_lt()
+            } else {
+                ordering.is_gt()
+            };
+            if preferred {
+                package
+            } else {
+                selected
+            }
+        })
+    }
+
     pub(crate) fn as_specific(&self) -> Option<&Version> {
         match self.inner {
             SelectorInner::Specific(version) => Some(version),
@@ -96,10 +160,18 @@ impl<'a> VersionSelector<'a> {
             SelectorInner::Range {
                 range,
                 project_unity,
+            }
+            | SelectorInner::LowestRange {
+                range,
+                project_unity,
             } => range.matches(package.version()) && unity_and_yank(package, project_unity),
             SelectorInner::Ranges {
                 ranges,
                 project_unity,
+            }
+            | SelectorInner::LowestRanges {
+                ranges,
+                project_unity,
             } => {
                 ranges.iter().all(|x| x.matches(package.version()))
                     && unity_and_yank(package, project_unity)
@@ -3,7 +3,7 @@ use futures::executor::block_on;
 use std::collections::HashSet;
 use std::path::Path;
 use vrc_get_vpm::unity_project::pending_project_changes::RemoveReason;
-use vrc_get_vpm::unity_project::{AddPackageErr, AddPackageOperation};
+use vrc_get_vpm::unity_project::{AddPackageErr, AddPackageOperation, ResolutionStrategy};
 use vrc_get_vpm::version::Version;
 use vrc_get_vpm::PackageManifest;
 
@@ -1122,4 +1122,329 @@ fn conflict_requirements_of_installed_and_installing_related_to_dependencies() {
     })
 }
 
+#[test]
+fn backtrack_to_older_transitive_dependency() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.library", Version::new(1, 1, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.library", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let library_1_0_0 = collection.get_package("com.anatawa12.library", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.remove_legacy_folders().len(), 0);
+        assert_eq!(resolve.remove_legacy_files().len(), 0);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &library_1_0_0);
+    })
+}
+
+#[test]
+fn backtrack_over_version_with_missing_dependency() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new().build().await.unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.library", Version::new(1, 1, 0))
+                    .add_vpm_dependency("com.anatawa12.missing", "^1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let library_1_0_0 = collection.get_package("com.anatawa12.library", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &library_1_0_0);
+    })
+}
+
+#[test]
+fn conflict_requirement_chains() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let base_conflict = resolve.conflicts().get("com.vrchat.base").unwrap();
+        let chains = base_conflict.requirement_chains();
+        assert_eq!(chains.len(), 2);
+
+        let chain = &chains[0];
+        assert_eq!(chain.len(), 2);
+
+        assert_eq!(chain[0].dependant_name(), None);
+        assert_eq!(chain[0].dependency(), "com.vrchat.avatars");
+        assert_eq!(chain[0].range().to_string(), ">=1.0.0");
+
+        assert_eq!(chain[1].dependant_name(), Some("com.vrchat.avatars"));
+        assert_eq!(chain[1].dependant_version(), Some(&Version::new(1, 0, 0)));
+        assert_eq!(chain[1].dependency(), "com.vrchat.base");
+        assert_eq!(chain[1].range().to_string(), "1.0.0");
+
+        // the requirement com.vrchat.base 1.1.0 is selected for
+        let chain = &chains[1];
+        let last = chain.last().unwrap();
+        assert_eq!(last.dependant_name(), Some("com.anatawa12.tool"));
+        assert_eq!(last.dependant_version(), Some(&Version::new(1, 0, 0)));
+        assert_eq!(last.dependency(), "com.vrchat.base");
+        assert_eq!(last.range().to_string(), "^1.1.0");
+    })
+}
+
+#[test]
+fn override_locked_dependency_range() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .add_override("com.vrchat.avatars", "com.vrchat.base", "^1.0.0")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let base_1_1_0 = collection.get_package("com.vrchat.base", Version::new(1, 1, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &base_1_1_0);
+    })
+}
+
+#[test]
+fn override_adding_package_dependency_range() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_override("com.anatawa12.tool", "com.vrchat.base", "^1.0.0")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(2, 0, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^2.0.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let base_1_0_0 = collection.get_package("com.vrchat.base", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &base_1_0_0);
+    })
+}
+
+#[test]
+fn install_lowest_dependency() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new().build().await.unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 2, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let base_1_1_0 = collection.get_package("com.vrchat.base", Version::new(1, 1, 0));
+
+        let resolve = project
+            .add_package_request_with_strategy(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+                ResolutionStrategy::Lowest,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &base_1_1_0);
+    })
+}
+
 // endregion
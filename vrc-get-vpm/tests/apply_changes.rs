@@ -0,0 +1,345 @@
+use crate::common::*;
+use futures::executor::block_on;
+use futures::AsyncReadExt;
+use std::path::Path;
+use vrc_get_vpm::io::IoTrait;
+use vrc_get_vpm::unity_project::AddPackageOperation;
+use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{PackageManifest, UnityProject};
+
+mod common;
+
+async fn read_file(fs: &VirtualFileSystem, path: &str) -> Option<String> {
+    let mut file = fs.open(path.as_ref()).await.ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).await.unwrap();
+    Some(content)
+}
+
+async fn add_package_files(fs: &VirtualFileSystem, name: &str, version: Version) {
+    fs.add_file(
+        format!("Packages/{name}/{version}/package.json").as_ref(),
+        format!(r#"{{"name":"{name}","version":"{version}"}}"#).as_bytes(),
+    )
+    .await
+    .unwrap();
+}
+
+#[test]
+fn install_package() {
+    block_on(async {
+        let project_fs = VirtualProjectBuilder::new().build_fs().await.unwrap();
+        let mut project = UnityProject::load(project_fs.clone()).await.unwrap();
+
+        let env_fs = VirtualFileSystem::new();
+        add_package_files(&env_fs, "com.anatawa12.package", Version::new(1, 0, 0)).await;
+        let env = VirtualEnvironment::new(env_fs);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 0, 0));
+
+        let changes = project
+            .add_package_request(
+                &collection,
+                &[package],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        project.apply_pending_changes(&env, changes).await.unwrap();
+
+        assert_eq!(
+            read_file(&project_fs, "Packages/com.anatawa12.package/package.json")
+                .await
+                .as_deref(),
+            Some(r#"{"name":"com.anatawa12.package","version":"1.0.0"}"#)
+        );
+        assert_eq!(
+            project
+                .get_locked("com.anatawa12.package")
+                .unwrap()
+                .version(),
+            &Version::new(1, 0, 0)
+        );
+        assert!(!project_fs.is_dir(".vrc-get-transaction".as_ref()).await);
+    })
+}
+
+#[test]
+fn upgrade_package_replaces_folder() {
+    block_on(async {
+        let project_fs = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .add_file("Packages/com.anatawa12.package/package.json", "old")
+            .add_file("Packages/com.anatawa12.package/Removed.cs", "removed")
+            .build_fs()
+            .await
+            .unwrap();
+        let mut project = UnityProject::load(project_fs.clone()).await.unwrap();
+
+        let env_fs = VirtualFileSystem::new();
+        add_package_files(&env_fs, "com.anatawa12.package", Version::new(1, 1, 0)).await;
+        let env = VirtualEnvironment::new(env_fs);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 1, 0));
+
+        let changes = project
+            .add_package_request(
+                &collection,
+                &[package],
+                AddPackageOperation::UpgradeLocked,
+                false,
+            )
+            .await
+            .unwrap();
+
+        project.apply_pending_changes(&env, changes).await.unwrap();
+
+        assert_eq!(
+            read_file(&project_fs, "Packages/com.anatawa12.package/package.json")
+                .await
+                .as_deref(),
+            Some(r#"{"name":"com.anatawa12.package","version":"1.1.0"}"#)
+        );
+        assert!(
+            !project_fs
+                .is_file("Packages/com.anatawa12.package/Removed.cs".as_ref())
+                .await
+        );
+        assert_eq!(
+            project
+                .get_locked("com.anatawa12.package")
+                .unwrap()
+                .version(),
+            &Version::new(1, 1, 0)
+        );
+        assert!(!project_fs.is_dir(".vrc-get-transaction".as_ref()).await);
+    })
+}
+
+#[test]
+fn remove_package() {
+    block_on(async {
+        let project_fs = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .add_file("Packages/com.anatawa12.package/package.json", "old")
+            .build_fs()
+            .await
+            .unwrap();
+        let mut project = UnityProject::load(project_fs.clone()).await.unwrap();
+
+        let env = VirtualEnvironment::new(VirtualFileSystem::new());
+
+        let changes = project
+            .remove_request(&["com.anatawa12.package"])
+            .await
+            .unwrap();
+
+        project.apply_pending_changes(&env, changes).await.unwrap();
+
+        assert!(
+            !project_fs
+                .is_dir("Packages/com.anatawa12.package".as_ref())
+                .await
+        );
+        assert!(project.get_locked("com.anatawa12.package").is_none());
+        assert!(!project_fs.is_dir(".vrc-get-transaction".as_ref()).await);
+    })
+}
+
+#[test]
+fn rollback_when_install_failed() {
+    block_on(async {
+        let project_fs = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .add_file("Packages/com.anatawa12.package/package.json", "old")
+            .build_fs()
+            .await
+            .unwrap();
+        let mut project = UnityProject::load(project_fs.clone()).await.unwrap();
+
+        // files of com.anatawa12.broken are missing so installing it fails
+        let env_fs = VirtualFileSystem::new();
+        add_package_files(&env_fs, "com.anatawa12.package", Version::new(1, 1, 0)).await;
+        let env = VirtualEnvironment::new(env_fs);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 1, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.broken",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 1, 0));
+        let broken = collection.get_package("com.anatawa12.broken", Version::new(1, 0, 0));
+
+        let changes = project
+            .add_package_request(
+                &collection,
+                &[package, broken],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        project
+            .apply_pending_changes(&env, changes)
+            .await
+            .expect_err("should fail");
+
+        assert_eq!(
+            read_file(&project_fs, "Packages/com.anatawa12.package/package.json")
+                .await
+                .as_deref(),
+            Some("old")
+        );
+        assert!(
+            !project_fs
+                .is_dir(Path::new("Packages/com.anatawa12.broken"))
+                .await
+        );
+        assert_eq!(
+            project
+                .get_locked("com.anatawa12.package")
+                .unwrap()
+                .version(),
+            &Version::new(1, 0, 0)
+        );
+        assert!(project.get_locked("com.anatawa12.broken").is_none());
+        assert!(!project_fs.is_dir(".vrc-get-transaction".as_ref()).await);
+    })
+}
+
+async fn upgrade_two_packages_project() -> (VirtualFileSystem, UnityProject<VirtualFileSystem>) {
+    let project_fs = VirtualProjectBuilder::new()
+        .add_dependency("com.anatawa12.a", Version::new(1, 0, 0))
+        .add_dependency("com.anatawa12.b", Version::new(1, 0, 0))
+        .add_locked("com.anatawa12.a", Version::new(1, 0, 0), &[])
+        .add_locked("com.anatawa12.b", Version::new(1, 0, 0), &[])
+        .add_file("Packages/com.anatawa12.a/package.json", "old a")
+        .add_file("Packages/com.anatawa12.b/package.json", "old b")
+        .build_fs()
+        .await
+        .unwrap();
+    let project = UnityProject::load(project_fs.clone()).await.unwrap();
+    (project_fs, project)
+}
+
+async fn upgrade_two_packages(
+    project: &mut UnityProject<VirtualFileSystem>,
+) -> std::io::Result<()> {
+    let env_fs = VirtualFileSystem::new();
+    add_package_files(&env_fs, "com.anatawa12.a", Version::new(1, 1, 0)).await;
+    add_package_files(&env_fs, "com.anatawa12.b", Version::new(1, 1, 0)).await;
+    let env = VirtualEnvironment::new(env_fs);
+
+    let collection = PackageCollectionBuilder::new()
+        .add(PackageManifest::new(
+            "com.anatawa12.a",
+            Version::new(1, 1, 0),
+        ))
+        .add(PackageManifest::new(
+            "com.anatawa12.b",
+            Version::new(1, 1, 0),
+        ))
+        .build();
+
+    let a = collection.get_package("com.anatawa12.a", Version::new(1, 1, 0));
+    let b = collection.get_package("com.anatawa12.b", Version::new(1, 1, 0));
+
+    let changes = project
+        .add_package_request(
+            &collection,
+            &[a, b],
+            AddPackageOperation::InstallToDependencies,
+            false,
+        )
+        .await
+        .unwrap();
+
+    project.apply_pending_changes(&env, changes).await
+}
+
+#[test]
+fn rollback_when_swap_failed() {
+    block_on(async {
+        let (project_fs, mut project) = upgrade_two_packages_project().await;
+
+        // moving the new package to Packages fails once, like the folder is locked
+        project_fs.fail_rename_to("Packages/com.anatawa12.b".as_ref(), 1);
+
+        upgrade_two_packages(&mut project)
+            .await
+            .expect_err("should fail");
+
+        assert_eq!(
+            read_file(&project_fs, "Packages/com.anatawa12.a/package.json")
+                .await
+                .as_deref(),
+            Some("old a")
+        );
+        assert_eq!(
+            read_file(&project_fs, "Packages/com.anatawa12.b/package.json")
+                .await
+                .as_deref(),
+            Some("old b")
+        );
+        assert_eq!(
+            project.get_locked("com.anatawa12.a").unwrap().version(),
+            &Version::new(1, 0, 0)
+        );
+        assert!(!project_fs.is_dir(".vrc-get-transaction".as_ref()).await);
+    })
+}
+
+#[test]
+fn keep_backup_when_rollback_failed() {
+    block_on(async {
+        let (project_fs, mut project) = upgrade_two_packages_project().await;
+
+        // both installing and restoring com.anatawa12.b fail
+        project_fs.fail_rename_to("Packages/com.anatawa12.b".as_ref(), usize::MAX);
+
+        upgrade_two_packages(&mut project)
+            .await
+            .expect_err("should fail");
+
+        let backup = ".vrc-get-transaction/backup/com.anatawa12.b/package.json";
+        assert_eq!(
+            read_file(&project_fs, backup).await.as_deref(),
+            Some("old b")
+        );
+
+        // the next apply must not remove the only copy of the package
+        upgrade_two_packages(&mut project)
+            .await
+            .expect_err("should refuse with backup left");
+        assert_eq!(
+            read_file(&project_fs, backup).await.as_deref(),
+            Some("old b")
+        );
+    })
+}
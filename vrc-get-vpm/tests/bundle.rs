@@ -0,0 +1,153 @@
+use crate::common::*;
+use futures::executor::block_on;
+use futures::io::Cursor;
+use futures::AsyncReadExt;
+use indexmap::IndexMap;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io;
+use url::Url;
+use vrc_get_vpm::io::IoTrait;
+use vrc_get_vpm::package_zip::create_package_zip;
+use vrc_get_vpm::unity_project::ProjectBundle;
+use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{Environment, PackageCollection, PackageManifest, UnityProject};
+
+mod common;
+
+const REPO_URL: &str = "https://vpm.anatawa12.com/vpm.json";
+const ZIP_URL: &str = "https://vpm.anatawa12.com/com.anatawa12.package-1.0.0.zip";
+
+async fn package_zip() -> Vec<u8> {
+    let package = VirtualFileSystem::new();
+    package
+        .add_file(
+            "package.json".as_ref(),
+            br#"{"name":"com.anatawa12.package","version":"1.0.0"}"#,
+        )
+        .await
+        .unwrap();
+    package
+        .add_file("Runtime/Script.cs".as_ref(), b"class Script {}")
+        .await
+        .unwrap();
+    create_package_zip(&package, Cursor::new(Vec::new()))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn environment(zip: &[u8]) -> Environment<VirtualHttpClient, VirtualFileSystem> {
+    let http = VirtualHttpClient::new();
+    let repository = json!({
+        "name": "Test Repository",
+        "url": REPO_URL,
+        "packages": {
+            "com.anatawa12.package": {
+                "versions": {
+                    "1.0.0": {
+                        "name": "com.anatawa12.package",
+                        "version": "1.0.0",
+                        "url": ZIP_URL,
+                        "zipSHA256": hex::encode(Sha256::digest(zip)),
+                    }
+                }
+            }
+        }
+    });
+    http.add(REPO_URL, repository.to_string().as_bytes());
+    http.add(ZIP_URL, zip);
+
+    let mut env = Environment::load(Some(http), VirtualFileSystem::new())
+        .await
+        .unwrap();
+    let url = Url::parse(REPO_URL).unwrap();
+    env.add_remote_repo(url, None, IndexMap::new(), vec![], None, false)
+        .await
+        .unwrap();
+    env.load_package_infos(false).await.unwrap();
+    env
+}
+
+#[test]
+fn bundle_round_trip() {
+    block_on(async {
+        let zip = package_zip().await;
+        let env = environment(&zip).await;
+
+        let fs = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .build_fs()
+            .await
+            .unwrap();
+        let mut vpm_manifest = Vec::new();
+        fs.open("Packages/vpm-manifest.json".as_ref())
+            .await
+            .unwrap()
+            .read_to_end(&mut vpm_manifest)
+            .await
+            .unwrap();
+        let project = UnityProject::load(fs).await.unwrap();
+
+        let bundle = project
+            .create_bundle(&env, Cursor::new(Vec::new()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let bundle = ProjectBundle::parse(bundle).await.unwrap();
+        assert_eq!(bundle.vpm_manifest(), vpm_manifest);
+
+        let packages = bundle.packages().collect::<Vec<_>>();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].0.name(), "com.anatawa12.package");
+        assert_eq!(packages[0].0.version(), &Version::new(1, 0, 0));
+        assert_eq!(packages[0].1, zip);
+
+        let package = bundle
+            .repository()
+            .find_packages("com.anatawa12.package")
+            .next()
+            .unwrap();
+        assert_eq!(package.version(), &Version::new(1, 0, 0));
+
+        // the project without vpm-manifest.json uses one in the bundle
+        let fs = VirtualProjectBuilder::new().build_fs().await.unwrap();
+        fs.remove_file("Packages/vpm-manifest.json".as_ref())
+            .await
+            .unwrap();
+        let mut project = UnityProject::load(fs.clone()).await.unwrap();
+        project.use_bundle_manifest(&bundle).unwrap();
+        assert!(project.get_locked("com.anatawa12.package").is_some());
+        assert!(!fs.is_file("Packages/vpm-manifest.json".as_ref()).await);
+
+        project.save().await.unwrap();
+        assert!(fs.is_file("Packages/vpm-manifest.json".as_ref()).await);
+    })
+}
+
+#[test]
+fn reject_package_name_escaping_cache() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let env = Environment::load(None::<VirtualHttpClient>, io.clone())
+            .await
+            .unwrap();
+
+        for name in [
+            "../../evil",
+            "com.anatawa12/evil",
+            "com.anatawa12\\evil",
+            "..",
+        ] {
+            let package = PackageManifest::new(name, Version::new(1, 0, 0));
+            let err = env
+                .add_package_to_cache(&package, b"zip")
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{name}");
+        }
+        assert!(!io.is_file("evil/vrc-get-evil-1.0.0.zip".as_ref()).await);
+    })
+}
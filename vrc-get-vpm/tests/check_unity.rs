@@ -0,0 +1,234 @@
+use crate::common::*;
+use futures::executor::block_on;
+use vrc_get_vpm::version::{UnityVersion, Version};
+use vrc_get_vpm::PackageManifest;
+
+mod common;
+
+const UNITY_2019: UnityVersion = UnityVersion::new_f1(2019, 4, 31);
+const UNITY_2022: UnityVersion = UnityVersion::new_f1(2022, 3, 6);
+
+fn vrcsdk_collection() -> PackageCollectionBuilder {
+    PackageCollectionBuilder::new()
+        .add(
+            PackageManifest::new("com.vrchat.avatars", Version::new(3, 5, 0))
+                .add_vpm_dependency("com.vrchat.base", "3.5.0"),
+        )
+        .add(
+            PackageManifest::new("com.vrchat.avatars", Version::new(3, 4, 0))
+                .add_vpm_dependency("com.vrchat.base", "3.4.0"),
+        )
+        .add(PackageManifest::new(
+            "com.vrchat.base",
+            Version::new(3, 5, 0),
+        ))
+        .add(PackageManifest::new(
+            "com.vrchat.base",
+            Version::new(3, 4, 0),
+        ))
+}
+
+#[test]
+fn compatible_with_upgrades() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(3, 4, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(3, 4, 0),
+                &[("com.vrchat.base", "3.4.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(3, 4, 0), &[])
+            .add_locked("com.anatawa12.tool", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = vrcsdk_collection()
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .with_unity(2022, 3),
+            )
+            .build();
+
+        let report = project
+            .check_unity_compatibility(&collection, UNITY_2022)
+            .await
+            .unwrap();
+
+        assert!(report.is_compatible());
+        assert_eq!(report.packages().len(), 3);
+
+        let avatars = &report.packages()[0];
+        assert_eq!(avatars.name(), "com.vrchat.avatars");
+        assert_eq!(avatars.compatible(), Some(false));
+        assert_eq!(
+            avatars.newest_compatible().map(|x| x.version()),
+            Some(&Version::new(3, 5, 0))
+        );
+
+        let tool = &report.packages()[2];
+        assert_eq!(tool.name(), "com.anatawa12.tool");
+        assert_eq!(tool.compatible(), Some(true));
+        assert!(tool.newest_compatible().is_none());
+
+        let changes = report.simulated_changes();
+        assert_eq!(changes.package_changes().len(), 2);
+        assert_eq!(changes.conflicts().len(), 0);
+
+        let avatars = collection.get_package("com.vrchat.avatars", Version::new(3, 5, 0));
+        let base = collection.get_package("com.vrchat.base", Version::new(3, 5, 0));
+        assert_installing_to_locked_only(changes, &avatars);
+        assert_installing_to_locked_only(changes, &base);
+    })
+}
+
+#[test]
+fn conflicts_with_third_party_package() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(3, 4, 0))
+            .add_dependency("com.anatawa12.tool", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(3, 4, 0),
+                &[("com.vrchat.base", "3.4.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(3, 4, 0), &[])
+            .add_locked(
+                "com.anatawa12.tool",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "~3.4.0")],
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let collection = vrcsdk_collection()
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "~3.4.0"),
+            )
+            .build();
+
+        let report = project
+            .check_unity_compatibility(&collection, UNITY_2022)
+            .await
+            .unwrap();
+
+        assert!(!report.is_compatible());
+
+        let conflicts = report.simulated_changes().conflicts();
+        let base = conflicts.get("com.vrchat.base").unwrap();
+        assert_eq!(base.conflicting_packages(), &["com.anatawa12.tool".into()]);
+    })
+}
+
+#[test]
+fn no_compatible_version() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.core.vpm-resolver", Version::new(0, 1, 26))
+            .add_locked("com.vrchat.core.vpm-resolver", Version::new(0, 1, 26), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.vrchat.core.vpm-resolver",
+                Version::new(0, 1, 26),
+            ))
+            .build();
+
+        let report = project
+            .check_unity_compatibility(&collection, UNITY_2022)
+            .await
+            .unwrap();
+
+        assert!(!report.is_compatible());
+
+        let resolver = &report.packages()[0];
+        assert_eq!(resolver.compatible(), Some(false));
+        assert!(resolver.newest_compatible().is_none());
+
+        let conflicts = report.simulated_changes().conflicts();
+        let resolver = conflicts.get("com.vrchat.core.vpm-resolver").unwrap();
+        assert!(resolver.conflicts_with_unity());
+    })
+}
+
+#[test]
+fn third_party_package_requires_newer_unity() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.tool", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.tool", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .with_unity(2022, 3),
+            )
+            .build();
+
+        let report = project
+            .check_unity_compatibility(&collection, UNITY_2019)
+            .await
+            .unwrap();
+
+        assert!(!report.is_compatible());
+
+        let tool = &report.packages()[0];
+        assert_eq!(tool.name(), "com.anatawa12.tool");
+        assert_eq!(tool.compatible(), Some(false));
+        assert!(tool.newest_compatible().is_none());
+
+        let conflicts = report.simulated_changes().conflicts();
+        assert!(conflicts["com.anatawa12.tool"].conflicts_with_unity());
+    })
+}
+
+#[test]
+fn third_party_package_with_newer_compatible_version() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.tool", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.tool", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .with_unity(2023, 1),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 1, 0))
+                    .with_unity(2022, 3),
+            )
+            .build();
+
+        let report = project
+            .check_unity_compatibility(&collection, UNITY_2022)
+            .await
+            .unwrap();
+
+        assert!(report.is_compatible());
+
+        let tool = &report.packages()[0];
+        assert_eq!(tool.compatible(), Some(false));
+        assert_eq!(
+            tool.newest_compatible().map(|x| x.version()),
+            Some(&Version::new(1, 1, 0))
+        );
+
+        let changes = report.simulated_changes();
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 1, 0));
+        assert_installing_to_locked_only(changes, &tool);
+    })
+}
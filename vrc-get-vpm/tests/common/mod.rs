@@ -3,14 +3,24 @@
 #![allow(unused_imports)]
 
 mod package_collection;
+mod remote_fixtures;
+mod virtual_environment;
 mod virtual_file_system;
+mod virtual_http_client;
 mod virtual_project_builder;
 
 pub use package_collection::PackageCollection;
 pub use package_collection::PackageCollectionBuilder;
+pub use remote_fixtures::{
+    cached_repository, file_url, load_environment, local_path, remote_package, PACKAGE_CACHE_PATH,
+};
+pub use virtual_environment::VirtualEnvironment;
 pub use virtual_file_system::VirtualFileSystem;
+pub use virtual_http_client::VirtualHttpClient;
 pub use virtual_project_builder::VirtualProjectBuilder;
 
+use futures::AsyncReadExt;
+use vrc_get_vpm::io::IoTrait;
 use vrc_get_vpm::unity_project::pending_project_changes::RemoveReason;
 use vrc_get_vpm::unity_project::PendingProjectChanges;
 use vrc_get_vpm::version::{DependencyRange, Version};
@@ -93,3 +103,44 @@ pub fn assert_installing_to_dependencies_only(
         .expect("not installing to dependencies");
     assert_eq!(base_range, &DependencyRange::version(version));
 }
+
+/// Rewrites `settings.json` like VCC, which doesn't keep unknown keys of user repositories.
+pub async fn rewrite_settings_like_vcc(io: &VirtualFileSystem) {
+    const VCC_REPO_KEYS: &[&str] = &["localPath", "name", "url", "id", "headers"];
+
+    edit_settings_json(io, |json| {
+        for repo in json["userRepos"].as_array_mut().unwrap() {
+            (repo.as_object_mut().unwrap()).retain(|key, _| VCC_REPO_KEYS.contains(&key.as_str()));
+        }
+    })
+    .await;
+}
+
+/// Edits `settings.json` shared with VCC like other tools or users do.
+pub async fn edit_settings_json(io: &VirtualFileSystem, f: impl FnOnce(&mut serde_json::Value)) {
+    let mut json = Vec::new();
+    (io.open("settings.json".as_ref()).await.unwrap())
+        .read_to_end(&mut json)
+        .await
+        .unwrap();
+    let mut json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    f(&mut json);
+    io.write(
+        "settings.json".as_ref(),
+        &serde_json::to_vec(&json).unwrap(),
+    )
+    .await
+    .unwrap();
+}
+
+/// A minisign key pair and the signature of `{"name":"Signed Repository","packages":{}}` for testing.
+pub mod signatures {
+    pub const PUBLIC_KEY: &str = "RWQBAgMEBQYHCIqI4910CfGV/VLbLTy6XXLKZwm/HZQSG/N0iAG0D29c";
+    pub const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCPNU9UXySQqCL3wMjsBftxqCHYoRt0H9rwJmTf5IVIROTo7RJ4f81a4iRzbjY4nMKOTYgCadijCF/O0ujYcpnA0=
+trusted comment: timestamp:1700000000\tfile:vpm.json\thashed
+FkDd8peZOpTu4YtyDvpweIatKyEiSq/qVuqtGTawxm4LiwVG8Z0lwHa5rWcIKR7gFDTm8H/JNDQ5Fgp6+ElKBA==
+";
+    /// Another key pair which didn't sign the repository.
+    pub const OTHER_PUBLIC_KEY: &str = "RWQREhMUFRYXGIE5dw6ofRdfVqNUZsNMfszLjYqRtO43ol32D1uPybOU";
+}
@@ -52,7 +52,13 @@ impl vrc_get_vpm::PackageCollection for PackageCollection {
         name: &str,
         version: VersionSelector,
     ) -> Option<PackageInfo<'_>> {
-        self.find_packages(name)
-            .find(|pkg| version.satisfies(pkg.package_json()))
+        let mut found = self
+            .find_packages(name)
+            .filter(|pkg| version.satisfies(pkg.package_json()));
+        if version.prefers_lowest() {
+            found.min_by_key(|pkg| pkg.version())
+        } else {
+            found.next()
+        }
     }
 }
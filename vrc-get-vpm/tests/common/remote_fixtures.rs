@@ -0,0 +1,55 @@
+use crate::common::{VirtualFileSystem, VirtualHttpClient};
+use indexmap::IndexMap;
+use serde_json::json;
+use std::path::PathBuf;
+use url::Url;
+use vrc_get_vpm::repository::local::LocalCachedRepository;
+use vrc_get_vpm::repository::RemoteRepository;
+use vrc_get_vpm::{Environment, PackageManifest};
+
+/// The path of the cached zip of the package created by [`remote_package`] in the environment.
+pub const PACKAGE_CACHE_PATH: &str =
+    "Repos/com.anatawa12.package/vrc-get-com.anatawa12.package-1.0.0.zip";
+
+/// The absolute path in the virtual file system to be used in file:// urls.
+pub fn local_path(path: &str) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(format!("C:\\{}", path.replace('/', "\\")))
+    } else {
+        PathBuf::from(format!("/{path}"))
+    }
+}
+
+pub fn file_url(path: &str) -> Url {
+    Url::from_file_path(local_path(path)).unwrap()
+}
+
+/// Creates an empty cached repository at `url`, to download packages with [`remote_package`].
+pub fn cached_repository(
+    url: &str,
+    headers: IndexMap<Box<str>, Box<str>>,
+) -> LocalCachedRepository {
+    let json = json!({ "url": url, "packages": {} });
+    let repo = RemoteRepository::parse(json.as_object().unwrap().clone()).unwrap();
+    LocalCachedRepository::new(repo, headers)
+}
+
+/// Creates the manifest of `com.anatawa12.package` version 1.0.0 downloaded from `url`.
+pub fn remote_package(url: &str, zip_sha256: Option<&str>) -> PackageManifest {
+    let mut json = json!({
+        "name": "com.anatawa12.package",
+        "version": "1.0.0",
+        "url": url,
+    });
+    if let Some(zip_sha256) = zip_sha256 {
+        json["zipSHA256"] = zip_sha256.into();
+    }
+    serde_json::from_value(json).unwrap()
+}
+
+pub async fn load_environment(
+    http: Option<&VirtualHttpClient>,
+    io: &VirtualFileSystem,
+) -> Environment<VirtualHttpClient, VirtualFileSystem> {
+    Environment::load(http.cloned(), io.clone()).await.unwrap()
+}
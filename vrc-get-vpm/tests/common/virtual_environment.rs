@@ -0,0 +1,43 @@
+use crate::common::virtual_file_system::FileStream;
+use crate::common::VirtualFileSystem;
+use std::io;
+use std::io::ErrorKind;
+use vrc_get_vpm::repository::local::LocalCachedRepository;
+use vrc_get_vpm::{EnvironmentIoHolder, PackageManifest, RemotePackageDownloader};
+
+/// The environment for applying changes to the virtual project.
+///
+/// Local packages are copied from the virtual file system.
+/// Remote packages are not supported.
+pub struct VirtualEnvironment {
+    io: VirtualFileSystem,
+}
+
+impl VirtualEnvironment {
+    pub fn new(io: VirtualFileSystem) -> Self {
+        Self { io }
+    }
+}
+
+impl EnvironmentIoHolder for VirtualEnvironment {
+    type EnvironmentIo = VirtualFileSystem;
+
+    fn io(&self) -> &Self::EnvironmentIo {
+        &self.io
+    }
+}
+
+impl RemotePackageDownloader for VirtualEnvironment {
+    type FileStream = FileStream;
+
+    async fn get_package(
+        &self,
+        _: &LocalCachedRepository,
+        _: &PackageManifest,
+    ) -> io::Result<Self::FileStream> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "remote packages are not supported",
+        ))
+    }
+}
@@ -25,17 +25,30 @@ where
 /// The virtual file system is a TraitIo implementation for testing.
 ///
 /// This struct implements All EnvironmentIo and ProjectIo methods.
+/// Cloned instances share the same file system.
+#[derive(Clone)]
 pub struct VirtualFileSystem {
     root: DirectoryEntry,
+    /// destination paths of rename to fail, with the number of remaining failures
+    failing_renames: Arc<Mutex<Vec<(PathBuf, usize)>>>,
 }
 
 impl VirtualFileSystem {
     pub fn new() -> Self {
         Self {
             root: DirectoryEntry::new(),
+            failing_renames: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Makes renaming to `to` fail `times` times, like folders locked by other processes.
+    pub fn fail_rename_to(&self, to: &Path, times: usize) {
+        self.failing_renames
+            .lock()
+            .unwrap()
+            .push((to.to_owned(), times));
+    }
+
     pub async fn add_file(&self, path: &Path, content: &[u8]) -> io::Result<()> {
         let Some((dir_path, last)) = self.resolve2(path)? else {
             return err(IS_DIRECTORY, "is directory");
@@ -57,9 +70,8 @@ impl VirtualFileSystem {
 
         for x in path.components() {
             match x {
-                Component::Prefix(_) | Component::RootDir => {
-                    panic!("absolute path")
-                }
+                // absolute paths like ones from file:// urls are resolved from the root
+                Component::Prefix(_) | Component::RootDir => result.clear(),
                 Component::CurDir => continue,
                 Component::ParentDir => {
                     if result.pop().is_none() {
@@ -141,6 +153,30 @@ impl vrc_get_vpm::io::IoTrait for VirtualFileSystem {
             .metadata())
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        if let Some((_, times)) = (self.failing_renames.lock().unwrap().iter_mut())
+            .find(|(path, times)| path == to && *times != 0)
+        {
+            *times -= 1;
+            return err(ErrorKind::PermissionDenied, "rename failure injected");
+        }
+
+        let Some((from_dir, from_last)) = self.resolve2(from)? else {
+            return err(ErrorKind::PermissionDenied, "renaming root");
+        };
+        let Some((to_dir, to_last)) = self.resolve2(to)? else {
+            return err(ErrorKind::AlreadyExists, "renaming to root");
+        };
+        let from_dir = self.root.get_folder(&from_dir).await?;
+        let to_dir = self.root.get_folder(&to_dir).await?;
+        if to_dir.get(to_last).await.is_ok() {
+            return err(ErrorKind::AlreadyExists, "file exists");
+        }
+        let entry = from_dir.take(from_last).await?;
+        to_dir.put(to_last, entry).await;
+        Ok(())
+    }
+
     type DirEntry = DirEntry;
     type ReadDirStream = ReadDirStream;
 
@@ -173,15 +209,11 @@ impl vrc_get_vpm::io::IoTrait for VirtualFileSystem {
             return err(IS_DIRECTORY, "is directory");
         };
 
-        Ok(FileStream::new(
-            self.root
-                .get_folder(&dir_path)
-                .await?
-                .create_file(last, false)
-                .await?
-                .content
-                .clone(),
-        ))
+        let file = (self.root.get_folder(&dir_path).await?)
+            .create_file(last, false)
+            .await?;
+        file.content.lock().unwrap().clear();
+        Ok(FileStream::new(file.content.clone()))
     }
 
     async fn open(&self, path: &Path) -> io::Result<Self::FileStream> {
@@ -338,6 +370,21 @@ impl DirectoryEntry {
         }
     }
 
+    async fn take(&self, name: &OsStr) -> io::Result<FileSystemEntry> {
+        self.backed
+            .lock()
+            .unwrap()
+            .shift_remove(name)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "file not found"))
+    }
+
+    async fn put(&self, name: &OsStr, entry: FileSystemEntry) {
+        self.backed
+            .lock()
+            .unwrap()
+            .insert(name.to_os_string(), entry);
+    }
+
     async fn remove_file(&self, name: &OsStr) -> io::Result<FileEntry> {
         let mut backed = self.backed.lock().unwrap();
         match backed.entry(name.to_os_string()) {
@@ -511,7 +558,7 @@ mod file_stream {
 
     impl AsyncWrite for FileStream {
         fn poll_write(
-            self: Pin<&mut Self>,
+            mut self: Pin<&mut Self>,
             _: &mut Context<'_>,
             buf: &[u8],
         ) -> Poll<io::Result<usize>> {
@@ -522,6 +569,7 @@ mod file_stream {
                 guard.resize(new_len, 0);
             }
             guard[self.position..][..buf.len()].copy_from_slice(buf);
+            self.position = new_len;
 
             Poll::Ready(Ok(buf.len()))
         }
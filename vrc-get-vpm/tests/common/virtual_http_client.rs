@@ -0,0 +1,84 @@
+use futures::io::Cursor;
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex};
+use url::Url;
+use vrc_get_vpm::HttpClient;
+
+type Headers = IndexMap<Box<str>, Box<str>>;
+
+/// The HTTP client serving resources in memory for testing.
+///
+/// The etag of resources is the sha256 of the content.
+/// Requests are recorded with their headers. Cloned instances share resources and requests.
+#[derive(Clone, Default)]
+pub struct VirtualHttpClient {
+    resources: Arc<Mutex<HashMap<Url, Vec<u8>>>>,
+    failures: Arc<Mutex<HashMap<Url, VecDeque<io::ErrorKind>>>>,
+    requests: Arc<Mutex<Vec<(Url, Headers)>>>,
+}
+
+impl VirtualHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, url: &str, content: &[u8]) {
+        (self.resources.lock().unwrap()).insert(Url::parse(url).unwrap(), content.to_vec());
+    }
+
+    /// Makes the next `times` requests to `url` fail with `kind`.
+    pub fn fail(&self, url: &str, kind: io::ErrorKind, times: usize) {
+        (self.failures.lock().unwrap())
+            .entry(Url::parse(url).unwrap())
+            .or_default()
+            .extend(std::iter::repeat_n(kind, times));
+    }
+
+    /// Returns the requested urls with headers in the order of requests.
+    pub fn requests(&self) -> Vec<(Url, Headers)> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn request(&self, url: &Url, headers: &IndexMap<Box<str>, Box<str>>) -> io::Result<Vec<u8>> {
+        (self.requests.lock().unwrap()).push((url.clone(), headers.clone()));
+
+        let failure = (self.failures.lock().unwrap())
+            .get_mut(url)
+            .and_then(VecDeque::pop_front);
+        if let Some(kind) = failure {
+            return Err(io::Error::new(kind, format!("failure injected for {url}")));
+        }
+
+        (self.resources.lock().unwrap())
+            .get(url)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{url} not found")))
+    }
+}
+
+impl HttpClient for VirtualHttpClient {
+    async fn get(
+        &self,
+        url: &Url,
+        headers: &IndexMap<Box<str>, Box<str>>,
+    ) -> io::Result<impl futures::AsyncRead + Send> {
+        Ok(Cursor::new(self.request(url, headers)?))
+    }
+
+    async fn get_with_etag(
+        &self,
+        url: &Url,
+        headers: &IndexMap<Box<str>, Box<str>>,
+        current_etag: Option<&str>,
+    ) -> io::Result<Option<(impl futures::AsyncRead + Send, Option<Box<str>>)>> {
+        let content = self.request(url, headers)?;
+        let etag = hex::encode(Sha256::digest(&content));
+        if current_etag == Some(etag.as_str()) {
+            return Ok(None);
+        }
+        Ok(Some((Cursor::new(content), Some(etag.into()))))
+    }
+}
@@ -9,6 +9,7 @@ use vrc_get_vpm::{PackageManifest, UnityProject};
 pub struct VirtualProjectBuilder {
     dependencies: IndexMap<String, String>,
     locked: IndexMap<String, (Version, IndexMap<String, VersionRange>)>,
+    overrides: IndexMap<String, IndexMap<String, String>>,
     files: IndexMap<String, String>,
     directories: Vec<String>,
 }
@@ -18,6 +19,7 @@ impl VirtualProjectBuilder {
         Self {
             dependencies: IndexMap::new(),
             locked: IndexMap::new(),
+            overrides: IndexMap::new(),
             files: IndexMap::new(),
             directories: vec![],
         }
@@ -51,6 +53,19 @@ impl VirtualProjectBuilder {
         self
     }
 
+    pub fn add_override(
+        &mut self,
+        dependant: &str,
+        dependency: &str,
+        range: &str,
+    ) -> &mut VirtualProjectBuilder {
+        self.overrides
+            .entry(dependant.into())
+            .or_default()
+            .insert(dependency.into(), range.into());
+        self
+    }
+
     pub fn add_file(
         &mut self,
         path: impl Into<String>,
@@ -79,6 +94,13 @@ impl VirtualProjectBuilder {
     }
 
     pub async fn build(&self) -> std::io::Result<UnityProject<VirtualFileSystem>> {
+        UnityProject::load(self.build_fs().await?).await
+    }
+
+    /// Builds the file system of the project.
+    ///
+    /// The returned file system can be shared with [UnityProject] by cloning it.
+    pub async fn build_fs(&self) -> std::io::Result<VirtualFileSystem> {
         let vpm_manifest = {
             let mut dependencies = serde_json::Map::new();
             for (dependency, version) in &self.dependencies {
@@ -100,10 +122,16 @@ impl VirtualProjectBuilder {
                 );
             }
 
-            json!({
+            let mut manifest = json!({
                 "dependencies": dependencies,
                 "locked": locked,
-            })
+            });
+
+            if !self.overrides.is_empty() {
+                manifest["vrc-get"] = json!({ "overrides": self.overrides });
+            }
+
+            manifest
         };
 
         let fs = VirtualFileSystem::new();
@@ -121,6 +149,6 @@ impl VirtualProjectBuilder {
             fs.create_dir_all(name.as_ref()).await?;
         }
 
-        UnityProject::load(fs).await
+        Ok(fs)
     }
 }
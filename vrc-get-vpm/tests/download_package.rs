@@ -0,0 +1,180 @@
+use crate::common::*;
+use futures::executor::block_on;
+use futures::AsyncReadExt;
+use indexmap::IndexMap;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io;
+use url::Url;
+use vrc_get_vpm::io::IoTrait;
+use vrc_get_vpm::{Environment, PackageManifest, RemotePackageDownloader};
+
+mod common;
+
+const REPO_URL: &str = "https://vpm.anatawa12.com/vpm.json";
+const ZIP_URL: &str = "https://vpm.anatawa12.com/com.anatawa12.package-1.0.0.zip";
+const ZIP_CONTENT: &[u8] = b"package zip content";
+
+async fn environment() -> (
+    VirtualFileSystem,
+    VirtualHttpClient,
+    Environment<VirtualHttpClient, VirtualFileSystem>,
+) {
+    let io = VirtualFileSystem::new();
+    let http = VirtualHttpClient::new();
+    http.add(ZIP_URL, ZIP_CONTENT);
+    let env = load_environment(Some(&http), &io).await;
+    (io, http, env)
+}
+
+async fn get_package(
+    env: &Environment<VirtualHttpClient, VirtualFileSystem>,
+    package: &PackageManifest,
+) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    (env.get_package(&cached_repository(REPO_URL, IndexMap::new()), package)
+        .await?)
+        .read_to_end(&mut content)
+        .await?;
+    Ok(content)
+}
+
+#[test]
+fn download_with_matching_sha256() {
+    block_on(async {
+        let (io, _, env) = environment().await;
+        let sha256 = hex::encode(Sha256::digest(ZIP_CONTENT));
+
+        let content = get_package(&env, &remote_package(ZIP_URL, Some(&sha256)))
+            .await
+            .unwrap();
+        assert_eq!(content, ZIP_CONTENT);
+        assert!(io.is_file(PACKAGE_CACHE_PATH.as_ref()).await);
+    })
+}
+
+#[test]
+fn download_with_mismatched_sha256() {
+    block_on(async {
+        let (io, _, env) = environment().await;
+        let sha256 = hex::encode(Sha256::digest(b"another content"));
+
+        let err = get_package(&env, &remote_package(ZIP_URL, Some(&sha256)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // the broken zip must not be left in the cache
+        assert!(!io.is_file(PACKAGE_CACHE_PATH.as_ref()).await);
+    })
+}
+
+#[test]
+fn download_with_malformed_sha256() {
+    block_on(async {
+        let (io, http, env) = environment().await;
+
+        let err = get_package(&env, &remote_package(ZIP_URL, Some("not a sha256")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(http.requests().is_empty());
+        assert!(!io.is_file(PACKAGE_CACHE_PATH.as_ref()).await);
+    })
+}
+
+#[test]
+fn download_without_sha256() {
+    block_on(async {
+        let (_, http, mut env) = environment().await;
+
+        let content = get_package(&env, &remote_package(ZIP_URL, None))
+            .await
+            .unwrap();
+        assert_eq!(content, ZIP_CONTENT);
+        assert_eq!(http.requests().len(), 1);
+
+        env.set_require_zip_sha256(true);
+        let err = get_package(&env, &remote_package(ZIP_URL, None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    })
+}
+
+#[test]
+fn cache_is_not_used_if_sha256_mismatched() {
+    block_on(async {
+        let (io, http, env) = environment().await;
+        let sha256 = hex::encode(Sha256::digest(ZIP_CONTENT));
+
+        // cache of another zip with the same version
+        let cached = b"cached content";
+        io.add_file(PACKAGE_CACHE_PATH.as_ref(), cached)
+            .await
+            .unwrap();
+        io.add_file(
+            format!("{PACKAGE_CACHE_PATH}.sha256").as_ref(),
+            hex::encode(Sha256::digest(cached)).as_bytes(),
+        )
+        .await
+        .unwrap();
+
+        let content = get_package(&env, &remote_package(ZIP_URL, Some(&sha256)))
+            .await
+            .unwrap();
+        assert_eq!(content, ZIP_CONTENT);
+        assert_eq!(http.requests().len(), 1);
+    })
+}
+
+#[test]
+fn headers_are_not_sent_to_mirrors_on_other_hosts() {
+    block_on(async {
+        const MIRROR_URL: &str = "https://mirror.example.com/vpm.json";
+        const MIRROR_ZIP_URL: &str = "https://mirror.example.com/com.anatawa12.package-1.0.0.zip";
+
+        let (_, http, mut env) = environment().await;
+        let repository = json!({ "name": "Test", "url": REPO_URL, "packages": {} }).to_string();
+        http.add(REPO_URL, repository.as_bytes());
+        http.add(MIRROR_URL, repository.as_bytes());
+        http.add(MIRROR_ZIP_URL, ZIP_CONTENT);
+        http.fail(REPO_URL, io::ErrorKind::NotFound, 1);
+        http.fail(ZIP_URL, io::ErrorKind::NotFound, 1);
+
+        let mut headers = IndexMap::new();
+        headers.insert("Authorization".into(), "Bearer secret".into());
+        let mirrors = vec![Url::parse(MIRROR_URL).unwrap()];
+        (env.add_remote_repo(
+            Url::parse(REPO_URL).unwrap(),
+            None,
+            headers.clone(),
+            mirrors,
+            None,
+            false,
+        ))
+        .await
+        .unwrap();
+
+        let repo = cached_repository(REPO_URL, headers.clone());
+        let mut content = Vec::new();
+        (env.get_package(&repo, &remote_package(ZIP_URL, None))
+            .await
+            .unwrap())
+        .read_to_end(&mut content)
+        .await
+        .unwrap();
+        assert_eq!(content, ZIP_CONTENT);
+
+        let requests = http.requests();
+        let headers_of = |url: &str| {
+            (requests.iter())
+                .filter(|(x, _)| x.as_str() == url)
+                .map(|(_, headers)| headers.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(headers_of(REPO_URL), vec![headers.clone()]);
+        assert_eq!(headers_of(MIRROR_URL), vec![IndexMap::new()]);
+        assert_eq!(headers_of(ZIP_URL), vec![headers.clone()]);
+        assert_eq!(headers_of(MIRROR_ZIP_URL), vec![IndexMap::new()]);
+    })
+}
@@ -0,0 +1,150 @@
+use futures::executor::block_on;
+use futures::io::Cursor;
+use futures::AsyncReadExt;
+use vrc_get_vpm::io::IoTrait;
+use vrc_get_vpm::package_zip::{check_package, create_package_zip, PackageProblem};
+use vrc_get_vpm::repository::PackageZip;
+
+mod common;
+
+async fn package(files: &[(&str, &str)]) -> common::VirtualFileSystem {
+    let vfs = common::VirtualFileSystem::new();
+    for (path, content) in files {
+        vfs.add_file(path.as_ref(), content.as_bytes())
+            .await
+            .unwrap();
+    }
+    vfs
+}
+
+const PACKAGE_JSON: &str = r#"{"name":"com.anatawa12.package","version":"1.0.0"}"#;
+
+#[test]
+fn valid_package() {
+    block_on(async {
+        let vfs = package(&[
+            ("package.json", PACKAGE_JSON),
+            ("package.json.meta", ""),
+            ("Runtime.meta", ""),
+            ("Runtime/Script.cs", ""),
+            ("Runtime/Script.cs.meta", ""),
+            ("Documentation~/index.md", ""),
+            (".gitignore", ""),
+        ])
+        .await;
+
+        let problems = check_package(&vfs).await.unwrap();
+        assert!(problems.is_empty(), "{problems:?}");
+    })
+}
+
+#[test]
+fn invalid_package() {
+    block_on(async {
+        let vfs = package(&[
+            (
+                "package.json",
+                r#"{
+                    "name": "com.anatawa12.Package",
+                    "version": "1.0",
+                    "vpmDependencies": {"com.anatawa12.dependency": "not a range"},
+                    "url": "not a url"
+                }"#,
+            ),
+            ("Runtime/Script.cs", ""),
+            ("Runtime/Script.cs.meta", ""),
+        ])
+        .await;
+
+        let problems = check_package(&vfs).await.unwrap();
+        assert_eq!(problems.len(), 6, "{problems:?}");
+        assert!(
+            matches!(&problems[0], PackageProblem::InvalidName { name } if &**name == "com.anatawa12.Package")
+        );
+        assert!(
+            matches!(&problems[1], PackageProblem::InvalidVersion { version } if &**version == "1.0")
+        );
+        assert!(
+            matches!(&problems[2], PackageProblem::InvalidDependency { dependency, .. } if &**dependency == "com.anatawa12.dependency")
+        );
+        assert!(matches!(&problems[3], PackageProblem::InvalidUrl { .. }));
+        assert!(
+            matches!(&problems[4], PackageProblem::MissingMeta { path } if &**path == "Runtime")
+        );
+        assert!(
+            matches!(&problems[5], PackageProblem::MissingMeta { path } if &**path == "package.json")
+        );
+    })
+}
+
+#[test]
+fn ignore_file() {
+    block_on(async {
+        let vfs = package(&[
+            ("package.json", PACKAGE_JSON),
+            ("package.json.meta", ""),
+            (
+                ".vpmignore",
+                "# comment\nBuild/\n*.tmp\n/Runtime/Generated.cs\n",
+            ),
+            ("Build/output.txt", ""),
+            ("Runtime/Script.cs", ""),
+            ("Runtime/Generated.cs", ""),
+            ("Runtime/Editor/Generated.cs", ""),
+            ("Runtime/cache.tmp", ""),
+        ])
+        .await;
+
+        let zip = create_package_zip(&vfs, Cursor::new(Vec::new()))
+            .await
+            .unwrap();
+        let zip = zip.into_inner();
+
+        let reader = async_zip::base::read::mem::ZipFileReader::new(zip.clone())
+            .await
+            .unwrap();
+        let names = (reader.file().entries().iter())
+            .map(|x| x.filename().as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "Runtime/Editor/Generated.cs",
+                "Runtime/Script.cs",
+                "package.json",
+                "package.json.meta",
+            ]
+        );
+
+        let zip = PackageZip::read(Cursor::new(zip)).await.unwrap();
+        assert_eq!(zip.package_json().name(), "com.anatawa12.package");
+    })
+}
+
+#[test]
+fn repack_over_larger_file() {
+    block_on(async {
+        let vfs = package(&[("package.json", PACKAGE_JSON), ("package.json.meta", "")]).await;
+
+        let expected = create_package_zip(&vfs, Cursor::new(Vec::new()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let output = common::VirtualFileSystem::new();
+        output
+            .add_file("package.zip".as_ref(), &[0; 4096])
+            .await
+            .unwrap();
+
+        let file = output.create("package.zip".as_ref()).await.unwrap();
+        create_package_zip(&vfs, file).await.unwrap();
+
+        let mut written = Vec::new();
+        (output.open("package.zip".as_ref()).await.unwrap())
+            .read_to_end(&mut written)
+            .await
+            .unwrap();
+        assert_eq!(written, expected);
+    })
+}
@@ -1,6 +1,7 @@
 use crate::common::VirtualProjectBuilder;
 use futures::executor::block_on;
 use vrc_get_vpm::version::{ReleaseType, UnityVersion, Version};
+use vrc_get_vpm::UnityProject;
 
 mod common;
 
@@ -66,3 +67,36 @@ fn read_package_json_with_bad_url() {
         assert_eq!(package_json.name(), "com.anatawa12.package");
     })
 }
+
+#[test]
+fn save_and_read_held_packages() {
+    block_on(async {
+        let fs = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build_fs()
+            .await
+            .unwrap();
+
+        let mut project = UnityProject::load(fs.clone()).await.unwrap();
+        assert!(!project.is_held("com.anatawa12.package"));
+
+        project.set_held("com.anatawa12.package", true);
+        project.save().await.unwrap();
+
+        let mut project = UnityProject::load(fs.clone()).await.unwrap();
+        assert!(project.is_held("com.anatawa12.package"));
+        assert!(!project.is_held("com.anatawa12.library"));
+        assert_eq!(
+            project.held_packages().collect::<Vec<_>>(),
+            vec!["com.anatawa12.package"]
+        );
+
+        project.set_held("com.anatawa12.package", false);
+        project.save().await.unwrap();
+
+        let project = UnityProject::load(fs).await.unwrap();
+        assert!(!project.is_held("com.anatawa12.package"));
+    })
+}
@@ -0,0 +1,597 @@
+use crate::common::*;
+use futures::executor::block_on;
+use futures::AsyncReadExt;
+use indexmap::IndexMap;
+use serde_json::json;
+use std::io;
+use std::time::Duration;
+use url::Url;
+use vrc_get_vpm::environment::{AddRepositoryErr, RepositoryFetchStatus, RepositoryList};
+use vrc_get_vpm::io::IoTrait;
+use vrc_get_vpm::repository::RemoteRepository;
+use vrc_get_vpm::{PackageCollection, RemotePackageDownloader};
+
+mod common;
+
+const REPO_JSON: &[u8] = br#"{"name":"Signed Repository","packages":{}}"#;
+
+async fn download(
+    io: &VirtualFileSystem,
+    url: &Url,
+    current_etag: Option<&str>,
+) -> io::Result<Option<(RemoteRepository, Option<Box<str>>)>> {
+    RemoteRepository::download_with_etag(
+        None::<&VirtualHttpClient>,
+        io,
+        url,
+        &IndexMap::new(),
+        current_etag,
+        None,
+    )
+    .await
+}
+
+#[test]
+fn file_repository_etag() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let url = file_url("repo/vpm.json");
+        io.add_file(&local_path("repo/vpm.json"), REPO_JSON)
+            .await
+            .unwrap();
+
+        let (repo, etag) = download(&io, &url, None).await.unwrap().unwrap();
+        assert_eq!(repo.name(), Some("Signed Repository"));
+        assert_eq!(repo.url(), Some(&url));
+        let etag = etag.unwrap();
+        assert!(etag.starts_with("sha256:"));
+
+        // not modified
+        assert!(download(&io, &url, Some(&etag)).await.unwrap().is_none());
+
+        // modified
+        io.write(
+            &local_path("repo/vpm.json"),
+            br#"{"name":"Modified Repository","packages":{}}"#,
+        )
+        .await
+        .unwrap();
+        let (repo, new_etag) = download(&io, &url, Some(&etag)).await.unwrap().unwrap();
+        assert_eq!(repo.name(), Some("Modified Repository"));
+        assert_ne!(new_etag.as_deref(), Some(etag.as_ref()));
+    })
+}
+
+#[test]
+fn file_repository_not_found() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let url = file_url("repo/vpm.json");
+
+        let err = download(&io, &url, None).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    })
+}
+
+#[test]
+fn file_repository_signature() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let url = file_url("repo/vpm.json");
+        io.add_file(&local_path("repo/vpm.json"), REPO_JSON)
+            .await
+            .unwrap();
+        io.add_file(
+            &local_path("repo/vpm.json.minisig"),
+            signatures::SIGNATURE.as_bytes(),
+        )
+        .await
+        .unwrap();
+
+        let (repo, _) = RemoteRepository::download_with_etag(
+            None::<&VirtualHttpClient>,
+            &io,
+            &url,
+            &IndexMap::new(),
+            None,
+            Some(signatures::PUBLIC_KEY),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(repo.name(), Some("Signed Repository"));
+    })
+}
+
+#[test]
+fn add_file_repository_offline() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let url = file_url("repo/vpm.json");
+        io.add_file(&local_path("repo/vpm.json"), REPO_JSON)
+            .await
+            .unwrap();
+
+        let mut env = load_environment(None, &io).await;
+        env.add_remote_repo(url.clone(), None, IndexMap::new(), vec![], None, false)
+            .await
+            .unwrap();
+        assert_eq!(env.get_user_repos()[0].url(), Some(&url));
+    })
+}
+
+#[test]
+fn download_package_from_file_repository_offline() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        io.add_file(&local_path("repo/package.zip"), b"zip content")
+            .await
+            .unwrap();
+
+        let env = load_environment(None, &io).await;
+        let repo = cached_repository(file_url("repo/vpm.json").as_str(), IndexMap::new());
+        let package = remote_package(file_url("repo/package.zip").as_str(), None);
+
+        let mut content = Vec::new();
+        (env.get_package(&repo, &package).await.unwrap())
+            .read_to_end(&mut content)
+            .await
+            .unwrap();
+        assert_eq!(content, b"zip content");
+    })
+}
+
+#[test]
+fn remote_repository_cannot_list_local_file() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        io.add_file(&local_path("secret/file"), b"secret")
+            .await
+            .unwrap();
+
+        let http = VirtualHttpClient::new();
+        let env = load_environment(Some(&http), &io).await;
+        let repo = cached_repository("https://vpm.anatawa12.com/vpm.json", IndexMap::new());
+        let package = remote_package(file_url("secret/file").as_str(), None);
+
+        let err = env.get_package(&repo, &package).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(http.requests().is_empty());
+        assert!(!io.is_file(PACKAGE_CACHE_PATH.as_ref()).await);
+    })
+}
+
+#[test]
+fn remote_repository_cannot_switch_to_local_file() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let secret = br#"{"name":"Secret","packages":{"com.anatawa12.secret":{"versions":{"1.0.0":{"name":"com.anatawa12.secret","version":"1.0.0"}}}}}"#;
+        io.add_file(&local_path("secret/vpm.json"), secret)
+            .await
+            .unwrap();
+
+        let url = Url::parse("https://vpm.anatawa12.com/vpm.json").unwrap();
+        let http = VirtualHttpClient::new();
+        let hostile =
+            json!({ "name": "Hostile", "url": file_url("secret/vpm.json"), "packages": {} });
+        http.add(url.as_str(), hostile.to_string().as_bytes());
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url.clone(), None, IndexMap::new(), vec![], None, false)
+            .await
+            .unwrap();
+        env.load_package_infos(true).await.unwrap();
+
+        assert!(env.find_packages("com.anatawa12.secret").next().is_none());
+        // refreshed from the url the user added instead
+        let refreshed = http.requests().iter().filter(|(x, _)| x == &url).count();
+        assert_eq!(refreshed, 2);
+    })
+}
+
+#[test]
+fn remote_repository_cannot_declare_local_file_url() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        io.add_file(&local_path("secret/file"), b"secret")
+            .await
+            .unwrap();
+
+        let url = Url::parse("https://vpm.anatawa12.com/vpm.json").unwrap();
+        let http = VirtualHttpClient::new();
+        let package = json!({
+            "name": "com.anatawa12.package",
+            "version": "1.0.0",
+            "url": file_url("secret/file"),
+        });
+        let hostile = json!({
+            "name": "Hostile",
+            "url": file_url("secret/vpm.json"),
+            "packages": { "com.anatawa12.package": { "versions": { "1.0.0": package } } },
+        });
+        http.add(url.as_str(), hostile.to_string().as_bytes());
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url.clone(), None, IndexMap::new(), vec![], None, false)
+            .await
+            .unwrap();
+        env.load_package_infos(false).await.unwrap();
+
+        let package = env.find_packages("com.anatawa12.package").next().unwrap();
+        let err = (env.get_package(package.repo().unwrap(), package.package_json()))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!io.is_file(PACKAGE_CACHE_PATH.as_ref()).await);
+    })
+}
+
+const SIGNED_URL: &str = "https://vpm.anatawa12.com/vpm.json";
+const SIGNATURE_URL: &str = "https://vpm.anatawa12.com/vpm.json.minisig";
+
+async fn download_signed(
+    http: &VirtualHttpClient,
+    public_key: &str,
+) -> io::Result<Option<(RemoteRepository, Option<Box<str>>)>> {
+    RemoteRepository::download_with_etag(
+        Some(http),
+        &VirtualFileSystem::new(),
+        &Url::parse(SIGNED_URL).unwrap(),
+        &IndexMap::new(),
+        None,
+        Some(public_key),
+    )
+    .await
+}
+
+#[test]
+fn valid_signature() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(SIGNATURE_URL, signatures::SIGNATURE.as_bytes());
+
+        let (repo, _) = (download_signed(&http, signatures::PUBLIC_KEY).await)
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.name(), Some("Signed Repository"));
+    })
+}
+
+#[test]
+fn tampered_repository() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(
+            SIGNED_URL,
+            br#"{"name":"Tampered Repository","packages":{}}"#,
+        );
+        http.add(SIGNATURE_URL, signatures::SIGNATURE.as_bytes());
+
+        let err = download_signed(&http, signatures::PUBLIC_KEY)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    })
+}
+
+#[test]
+fn signature_with_wrong_key() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(SIGNATURE_URL, signatures::SIGNATURE.as_bytes());
+
+        let err = download_signed(&http, signatures::OTHER_PUBLIC_KEY)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    })
+}
+
+#[test]
+fn missing_signature() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+
+        let err = download_signed(&http, signatures::PUBLIC_KEY)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    })
+}
+
+#[test]
+fn malformed_signature() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(SIGNATURE_URL, b"not a signature");
+
+        let err = download_signed(&http, signatures::PUBLIC_KEY)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    })
+}
+
+#[test]
+fn public_key_kept_after_settings_rewritten() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(SIGNATURE_URL, signatures::SIGNATURE.as_bytes());
+        let url = Url::parse(SIGNED_URL).unwrap();
+
+        let mut env = load_environment(Some(&http), &io).await;
+        let public_key = Some(signatures::PUBLIC_KEY);
+        env.add_remote_repo(url, None, IndexMap::new(), vec![], public_key, false)
+            .await
+            .unwrap();
+        env.save().await.unwrap();
+
+        rewrite_settings_like_vcc(&io).await;
+        http.add(
+            SIGNED_URL,
+            br#"{"name":"Tampered Repository","packages":{}}"#,
+        );
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.load_package_infos(true).await.unwrap();
+
+        let local_path = env.get_user_repos()[0].local_path().to_owned();
+        assert!(matches!(
+            env.get_repository_fetch_status(&local_path),
+            Some(RepositoryFetchStatus::Failed { .. })
+        ));
+        let (_, repo) = (env.get_repos())
+            .find(|(path, _)| path.as_ref() == local_path)
+            .unwrap();
+        assert_eq!(repo.repo().name(), Some("Signed Repository"));
+    })
+}
+
+#[test]
+fn package_filter_kept_after_settings_rewritten() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        let repo = json!({
+            "name": "Filtered Repository",
+            "packages": {
+                "com.anatawa12.allowed": {"versions": {"1.0.0": {"name": "com.anatawa12.allowed", "version": "1.0.0"}}},
+                "com.anatawa12.denied": {"versions": {"1.0.0": {"name": "com.anatawa12.denied", "version": "1.0.0"}}},
+            },
+        });
+        http.add(SIGNED_URL, repo.to_string().as_bytes());
+        let url = Url::parse(SIGNED_URL).unwrap();
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url.clone(), None, IndexMap::new(), vec![], None, false)
+            .await
+            .unwrap();
+        env.set_repo_package_filter(
+            |x| x.url() == Some(&url),
+            vec![],
+            vec!["com.anatawa12.denied".into()],
+        );
+        env.save().await.unwrap();
+
+        rewrite_settings_like_vcc(&io).await;
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.load_package_infos(false).await.unwrap();
+
+        assert!(env.find_packages("com.anatawa12.allowed").next().is_some());
+        assert!(env.find_packages("com.anatawa12.denied").next().is_none());
+    })
+}
+
+async fn refreshed_header(http: &VirtualHttpClient, io: &VirtualFileSystem) -> Box<str> {
+    let mut env = load_environment(Some(http), io).await;
+    env.load_package_infos(true).await.unwrap();
+    let (_, headers) = http.requests().pop().unwrap();
+    headers["X-Path"].clone()
+}
+
+#[test]
+fn header_references_kept_after_settings_rewritten() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        let url = Url::parse(SIGNED_URL).unwrap();
+        let mut headers = IndexMap::new();
+        headers.insert("X-Path".into(), "${env:PATH}".into());
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url, None, headers, vec![], None, true)
+            .await
+            .unwrap();
+        env.save().await.unwrap();
+
+        rewrite_settings_like_vcc(&io).await;
+
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(refreshed_header(&http, &io).await.as_ref(), path);
+    })
+}
+
+#[test]
+fn header_references_cannot_be_enabled_in_settings_json() {
+    block_on(async {
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        let url = Url::parse(SIGNED_URL).unwrap();
+        let mut headers = IndexMap::new();
+        headers.insert("X-Path".into(), "${env:PATH}".into());
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url, None, headers, vec![], None, false)
+            .await
+            .unwrap();
+        env.save().await.unwrap();
+
+        // settings.json copied from another machine
+        edit_settings_json(&io, |json| {
+            json["userRepos"][0]["headerReferences"] = true.into();
+        })
+        .await;
+
+        assert_eq!(refreshed_header(&http, &io).await.as_ref(), "${env:PATH}");
+    })
+}
+
+#[test]
+fn import_redacted_export_with_env_references() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        let url = Url::parse(SIGNED_URL).unwrap();
+        let mut headers = IndexMap::new();
+        headers.insert("Authorization".into(), "Bearer secret".into());
+        headers.insert("X-Path".into(), "${env:PATH}".into());
+
+        let mut env = load_environment(Some(&http), &VirtualFileSystem::new()).await;
+        env.add_remote_repo(url, None, headers, vec![], None, true)
+            .await
+            .unwrap();
+        // headers are exported from the repository cache
+        env.load_package_infos(false).await.unwrap();
+        let json = env.export_repositories(true).to_json().unwrap();
+        let list = RepositoryList::parse(&json).unwrap();
+
+        let io = VirtualFileSystem::new();
+        let mut env = load_environment(Some(&http), &io).await;
+        env.import_repository(&list.repositories()[0])
+            .await
+            .unwrap();
+        env.save().await.unwrap();
+
+        let (_, headers) = http.requests().pop().unwrap();
+        assert_eq!(headers.get("Authorization"), None);
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(refreshed_header(&http, &io).await.as_ref(), path);
+    })
+}
+
+#[test]
+fn import_refuses_command_references() {
+    block_on(async {
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        let list = json!({
+            "repositories": [{
+                "url": SIGNED_URL,
+                "headers": { "X-Token": "${cmd:credential-helper}" },
+            }],
+        });
+        let list = RepositoryList::parse(list.to_string().as_bytes()).unwrap();
+
+        let mut env = load_environment(Some(&http), &VirtualFileSystem::new()).await;
+        let err = (env.import_repository(&list.repositories()[0]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, AddRepositoryErr::HeaderReference { ref name } if name.as_ref() == "X-Token")
+        );
+        assert!(http.requests().is_empty());
+    })
+}
+
+#[test]
+fn priority_kept_after_settings_rewritten() {
+    block_on(async {
+        const OTHER_URL: &str = "https://other.example.com/vpm.json";
+
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(OTHER_URL, br#"{"name":"Other Repository","packages":{}}"#);
+        let other_url = Url::parse(OTHER_URL).unwrap();
+
+        let mut env = load_environment(Some(&http), &io).await;
+        for url in [SIGNED_URL, OTHER_URL] {
+            let url = Url::parse(url).unwrap();
+            env.add_remote_repo(url, None, IndexMap::new(), vec![], None, false)
+                .await
+                .unwrap();
+        }
+        env.set_repo_priority(|x| x.url() == Some(&other_url), 10);
+        env.save().await.unwrap();
+
+        rewrite_settings_like_vcc(&io).await;
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.load_package_infos(false).await.unwrap();
+
+        let other = (env.get_user_repos().iter())
+            .find(|x| x.url() == Some(&other_url))
+            .unwrap();
+        assert_eq!(env.get_repo_priority(other), 10);
+        let (_, first) = env.get_repos().next().unwrap();
+        assert_eq!(first.name(), Some("Other Repository"));
+    })
+}
+
+#[test]
+fn mirrors_kept_after_settings_rewritten() {
+    block_on(async {
+        const MIRROR_URL: &str = "https://mirror.example.com/vpm.json";
+
+        let io = VirtualFileSystem::new();
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(MIRROR_URL, REPO_JSON);
+        let url = Url::parse(SIGNED_URL).unwrap();
+        let mirrors = vec![Url::parse(MIRROR_URL).unwrap()];
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.add_remote_repo(url, None, IndexMap::new(), mirrors, None, false)
+            .await
+            .unwrap();
+        env.save().await.unwrap();
+
+        rewrite_settings_like_vcc(&io).await;
+        http.fail(SIGNED_URL, io::ErrorKind::NotFound, 1);
+
+        let mut env = load_environment(Some(&http), &io).await;
+        env.load_package_infos(true).await.unwrap();
+
+        let (mirror, _) = http.requests().pop().unwrap();
+        assert_eq!(mirror.as_str(), MIRROR_URL);
+    })
+}
+
+#[test]
+fn repository_timeout_covers_retries_and_mirrors() {
+    block_on(async {
+        const MIRROR_URL: &str = "https://mirror.example.com/vpm.json";
+
+        let http = VirtualHttpClient::new();
+        http.add(SIGNED_URL, REPO_JSON);
+        http.add(MIRROR_URL, REPO_JSON);
+        http.fail(SIGNED_URL, io::ErrorKind::ConnectionReset, 10);
+
+        let mut env = load_environment(Some(&http), &VirtualFileSystem::new()).await;
+        env.set_repository_timeout(Some(Duration::from_secs(1)));
+        let url = Url::parse(SIGNED_URL).unwrap();
+        let mirrors = vec![Url::parse(MIRROR_URL).unwrap()];
+        let err = (env.add_remote_repo(url, None, IndexMap::new(), mirrors, None, false))
+            .await
+            .unwrap_err();
+
+        let AddRepositoryErr::Io(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        // the timeout is reached while waiting for the second retry
+        let requests = http.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|(x, _)| x.as_str() == SIGNED_URL));
+    })
+}
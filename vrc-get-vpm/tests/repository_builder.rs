@@ -0,0 +1,84 @@
+use futures::executor::block_on;
+use futures::io::Cursor;
+use serde_json::json;
+use url::Url;
+use vrc_get_vpm::package_zip::create_package_zip;
+use vrc_get_vpm::repository::{AddPackageResult, PackageZip, RepositoryBuilder};
+
+mod common;
+
+async fn package_zip(version: &str, content: &[u8]) -> PackageZip {
+    let vfs = common::VirtualFileSystem::new();
+    let package_json = json!({"name": "com.anatawa12.package", "version": version});
+    vfs.add_file("package.json".as_ref(), package_json.to_string().as_bytes())
+        .await
+        .unwrap();
+    vfs.add_file("Runtime/Script.cs".as_ref(), content)
+        .await
+        .unwrap();
+
+    let zip = create_package_zip(&vfs, Cursor::new(Vec::new()))
+        .await
+        .unwrap();
+    PackageZip::read(zip).await.unwrap()
+}
+
+#[test]
+fn deterministic_zip() {
+    block_on(async {
+        let first = package_zip("1.0.0", b"content").await;
+        let second = package_zip("1.0.0", b"content").await;
+        let changed = package_zip("1.0.0", b"changed").await;
+
+        assert_eq!(first.sha256(), second.sha256());
+        assert_ne!(first.sha256(), changed.sha256());
+        assert_eq!(first.package_json().name(), "com.anatawa12.package");
+    })
+}
+
+#[test]
+fn merge_into_existing() {
+    block_on(async {
+        let existing = json!({
+            "name": "Test Repository",
+            "packages": {
+                "com.anatawa12.package": {
+                    "versions": {
+                        "0.9.0": {
+                            "name": "com.anatawa12.package",
+                            "version": "0.9.0",
+                            "url": "https://example.com/0.9.0.zip",
+                        }
+                    }
+                }
+            }
+        });
+        let mut builder = RepositoryBuilder::parse(existing.to_string().as_bytes()).unwrap();
+
+        let zip = package_zip("1.0.0", b"content").await;
+        let url = Url::parse("https://example.com/1.0.0.zip").unwrap();
+        assert_eq!(builder.add_package(&zip, &url), AddPackageResult::Added);
+        assert_eq!(builder.add_package(&zip, &url), AddPackageResult::Unchanged);
+
+        let changed = package_zip("1.0.0", b"changed").await;
+        assert_eq!(
+            builder.check_package(&changed, &url),
+            AddPackageResult::Replaced
+        );
+        assert_eq!(
+            builder.check_package(&zip, &url),
+            AddPackageResult::Unchanged
+        );
+        assert_eq!(
+            builder.add_package(&changed, &url),
+            AddPackageResult::Replaced
+        );
+
+        let json: serde_json::Value = serde_json::from_slice(&builder.to_json().unwrap()).unwrap();
+        let versions = &json["packages"]["com.anatawa12.package"]["versions"];
+        assert_eq!(json["name"], "Test Repository");
+        assert_eq!(versions["0.9.0"]["url"], "https://example.com/0.9.0.zip");
+        assert_eq!(versions["1.0.0"]["url"], "https://example.com/1.0.0.zip");
+        assert_eq!(versions["1.0.0"]["zipSHA256"], changed.sha256());
+    })
+}
@@ -1,5 +1,6 @@
 use crate::common::*;
 use futures::executor::block_on;
+use vrc_get_vpm::unity_project::{ResolutionStrategy, ResolvePackageErr};
 use vrc_get_vpm::version::Version;
 use vrc_get_vpm::PackageManifest;
 
@@ -79,6 +80,49 @@ fn resolve_ranged() {
     })
 }
 
+#[test]
+fn resolve_ranged_lowest() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency_range("com.anatawa12.package", "^1.0.0")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 1, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 1, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let result = project
+            .resolve_request_with_strategy(&collection, ResolutionStrategy::Lowest)
+            .await
+            .unwrap();
+
+        assert_eq!(result.package_changes().len(), 2);
+        assert_eq!(result.conflicts().len(), 0);
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 0, 0));
+        let library = collection.get_package("com.anatawa12.library", Version::new(1, 0, 0));
+        assert_installing_to_both(&result, &package);
+        assert_installing_to_locked_only(&result, &library);
+    })
+}
+
 #[test]
 fn resolve_dependencies_of_unlocked() {
     block_on(async {
@@ -252,3 +296,163 @@ fn resolve_both_dependencies_of_unlocked_and_dependencies() {
         assert_installing_to_both(&result, &library2);
     })
 }
+
+#[test]
+fn frozen_resolve_fully_locked() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.library", "1.0.0")],
+            )
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let result = project.resolve_request_frozen(&collection).await.unwrap();
+
+        assert_eq!(result.package_changes().len(), 2);
+        assert_eq!(result.remove_legacy_folders().len(), 0);
+        assert_eq!(result.remove_legacy_files().len(), 0);
+        assert_eq!(result.conflicts().len(), 0);
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 0, 0));
+        let library = collection.get_package("com.anatawa12.library", Version::new(1, 0, 0));
+        assert_install_only(&result, &package);
+        assert_install_only(&result, &library);
+    })
+}
+
+#[test]
+fn frozen_resolve_locked_version_not_found() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let err = project
+            .resolve_request_frozen(&collection)
+            .await
+            .expect_err("should fail");
+
+        match &err {
+            ResolvePackageErr::LockedVersionNotFound {
+                package_name,
+                version,
+            } => {
+                assert_eq!(package_name.as_ref(), "com.anatawa12.package");
+                assert_eq!(version, &Version::new(1, 0, 0));
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    })
+}
+
+#[test]
+fn frozen_resolve_not_locked_dependency() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency_range("com.anatawa12.package", "^1.0.0")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let err = project
+            .resolve_request_frozen(&collection)
+            .await
+            .expect_err("should fail");
+
+        match &err {
+            ResolvePackageErr::DependencyNotLocked {
+                dependency_name,
+                required_by,
+            } => {
+                assert_eq!(dependency_name.as_ref(), "com.anatawa12.package");
+                assert_eq!(required_by, &None);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    })
+}
+
+#[test]
+fn frozen_resolve_inconsistent_locked_version() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.library", "^1.1.0")],
+            )
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.1.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let err = project
+            .resolve_request_frozen(&collection)
+            .await
+            .expect_err("should fail");
+
+        match &err {
+            ResolvePackageErr::InconsistentLockedVersion {
+                package_name,
+                locked_version,
+                required_by,
+                ..
+            } => {
+                assert_eq!(package_name.as_ref(), "com.anatawa12.library");
+                assert_eq!(locked_version, &Version::new(1, 0, 0));
+                assert_eq!(required_by.as_deref(), Some("com.anatawa12.package"));
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    })
+}
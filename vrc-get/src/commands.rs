@@ -1,4 +1,4 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use indexmap::IndexMap;
 use itertools::Itertools;
 
@@ -15,13 +15,21 @@ use std::num::NonZeroU32;
 use std::path::{Component, Path, PathBuf};
 use std::process::exit;
 use std::str::FromStr;
-use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo};
-use vrc_get_vpm::repository::RemoteRepository;
-use vrc_get_vpm::unity_project::pending_project_changes::{PackageChange, RemoveReason};
-use vrc_get_vpm::unity_project::{AddPackageOperation, PendingProjectChanges};
-use vrc_get_vpm::version::Version;
+use vrc_get_vpm::environment::{AddRepositoryErr, PackageNewsKind, RepositoryList};
+use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo, IoTrait};
+use vrc_get_vpm::package_zip::{check_package, create_package_zip};
+use vrc_get_vpm::repository::{
+    AddPackageResult, AddRepositoryLink, PackageZip, RemoteRepository, RepositoryBuilder,
+};
+use vrc_get_vpm::unity_project::pending_project_changes::{
+    PackageChange, RemoveReason, RequirementEdge,
+};
+use vrc_get_vpm::unity_project::{
+    AddPackageOperation, LockedDependencyInfo, PendingProjectChanges, ResolutionStrategy,
+};
+use vrc_get_vpm::version::{UnityVersion, Version};
 use vrc_get_vpm::{
-    PackageCollection, PackageInfo, PackageManifest, UserRepoSetting, VersionSelector,
+    PackageCollection, PackageInfo, PackageJson, PackageManifest, UserRepoSetting, VersionSelector,
 };
 
 type Environment = vrc_get_vpm::Environment<Client, DefaultEnvironmentIo>;
@@ -80,6 +88,35 @@ struct EnvArgs {
     no_update: bool,
 }
 
+#[derive(Args, Default)]
+struct PlanArgs {
+    /// With this option, the changes to be applied are printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
+    /// Print the changes to be applied and exit without applying them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Which version of dependencies to use
+#[derive(ValueEnum, Clone, Copy, Default)]
+enum Resolution {
+    /// Use the latest version satisfying the requirements
+    #[default]
+    Latest,
+    /// Use the lowest version satisfying the requirements
+    Lowest,
+}
+
+impl From<Resolution> for ResolutionStrategy {
+    fn from(value: Resolution) -> Self {
+        match value {
+            Resolution::Latest => ResolutionStrategy::Latest,
+            Resolution::Lowest => ResolutionStrategy::Lowest,
+        }
+    }
+}
+
 async fn load_env(args: &EnvArgs) -> Environment {
     let client = crate::create_client(args.offline);
     let io = DefaultEnvironmentIo::new_default();
@@ -134,6 +171,88 @@ fn get_package<'env>(
         .unwrap_or_else(|| exit_with!("no matching package not found"))
 }
 
+/// The semver level of an upgrade
+#[derive(Serialize, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+enum UpgradeLevel {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl UpgradeLevel {
+    fn between(from: &Version, to: &Version) -> Self {
+        if from.major != to.major {
+            UpgradeLevel::Major
+        } else if from.minor != to.minor {
+            UpgradeLevel::Minor
+        } else {
+            UpgradeLevel::Patch
+        }
+    }
+}
+
+impl Display for UpgradeLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradeLevel::Patch => f.write_str("patch"),
+            UpgradeLevel::Minor => f.write_str("minor"),
+            UpgradeLevel::Major => f.write_str("major"),
+        }
+    }
+}
+
+/// Finds the latest version of the package upgrading `current` at most `max_level`.
+///
+/// Like [`find_package_by_name`](PackageCollection::find_package_by_name), the package from the
+/// preferred source is used if multiple sources have the same version.
+fn get_upgrade_package<'env>(
+    env: &'env Environment,
+    name: &str,
+    current: &Version,
+    selector: VersionSelector,
+    max_level: UpgradeLevel,
+) -> Option<PackageInfo<'env>> {
+    let packages = env
+        .find_packages(name)
+        .filter(|x| selector.satisfies(x.package_json()))
+        .filter(|x| UpgradeLevel::between(current, x.version()) <= max_level);
+    selector.select_preferred(packages, |x| x.version())
+}
+
+async fn upgrade_request<'env>(
+    env: &'env Environment,
+    unity: &UnityProject,
+    updates: &[PackageInfo<'env>],
+    prerelease: bool,
+) -> PendingProjectChanges<'env> {
+    unity
+        .add_package_request(env, updates, AddPackageOperation::UpgradeLocked, prerelease)
+        .await
+        .exit_context("collecting packages to be upgraded")
+}
+
+/// Returns `<name> <version>` of the packages upgraded beyond `max_level` from the locked version.
+///
+/// Dependencies are resolved to the latest versions satisfying the requirements,
+/// so upgrading a package within `max_level` may upgrade its dependencies beyond it.
+fn upgrades_beyond(
+    unity: &UnityProject,
+    changes: &PendingProjectChanges,
+    max_level: UpgradeLevel,
+) -> Vec<String> {
+    (changes.package_changes().iter())
+        .filter_map(|(name, change)| Some((name, change.as_install()?.install_package()?)))
+        .filter(|(name, package)| {
+            unity.get_locked(name).is_some_and(|locked| {
+                UpgradeLevel::between(locked.version(), package.version()) > max_level
+            })
+        })
+        .map(|(name, package)| format!("{name} {}", package.version()))
+        .sorted()
+        .collect()
+}
+
 async fn save_unity(unity: &mut UnityProject) {
     unity.save().await.exit_context("saving manifest file");
 }
@@ -146,13 +265,14 @@ fn confirm_prompt(msg: &str) -> bool {
     use std::io;
     use std::io::Write;
     fn _impl(msg: &str) -> io::Result<bool> {
-        let mut stdout = io::stdout();
+        // prompt on stderr to keep stdout for the plan, which may be json
+        let mut stderr = io::stderr();
         let stdin = io::stdin();
         let mut buf = String::new();
         loop {
             // prompt
-            write!(stdout, "{} [y/n] ", msg)?;
-            stdout.flush()?;
+            write!(stderr, "{} [y/n] ", msg)?;
+            stderr.flush()?;
 
             buf.clear();
             stdin.read_line(&mut buf)?;
@@ -170,7 +290,19 @@ fn confirm_prompt(msg: &str) -> bool {
     _impl(msg).unwrap_or(false)
 }
 
-fn print_prompt_install(changes: &PendingProjectChanges) {
+fn print_prompt_install(changes: &PendingProjectChanges, args: &PlanArgs) {
+    match args.json_format.map(|x| x.get()).unwrap_or(0) {
+        0 => print_changes(changes),
+        1 => print_changes_json(changes),
+        v => exit_with!("unsupported json version: {v}"),
+    }
+
+    if args.dry_run {
+        exit(0);
+    }
+}
+
+fn print_changes(changes: &PendingProjectChanges) {
     if changes.package_changes().is_empty() {
         exit_with!("nothing to do")
     }
@@ -251,6 +383,13 @@ fn print_prompt_install(changes: &PendingProjectChanges) {
                 for conflict in conflicts.conflicting_packages() {
                     println!("- {conflict}");
                 }
+                if !conflicts.requirement_chains().is_empty() {
+                    println!("because of the following requirements:");
+                    for chain in conflicts.requirement_chains() {
+                        let chain = chain.iter().map(RequirementEdgeInfo::from).collect_vec();
+                        println!("- {}", format_requirement_chain(&chain));
+                    }
+                }
             }
         }
     }
@@ -271,9 +410,131 @@ fn print_prompt_install(changes: &PendingProjectChanges) {
     }
 }
 
+/// An edge of a requirement chain, shared by the conflict chains and the paths shown by `why`.
+#[derive(Serialize, Clone)]
+struct RequirementEdgeInfo<'a> {
+    // None for the dependencies of the project
+    dependant_name: Option<&'a str>,
+    dependant_version: Option<&'a Version>,
+    dependency: &'a str,
+    range: String,
+}
+
+impl<'a> From<&'a RequirementEdge> for RequirementEdgeInfo<'a> {
+    fn from(edge: &'a RequirementEdge) -> Self {
+        Self {
+            dependant_name: edge.dependant_name(),
+            dependant_version: edge.dependant_version(),
+            dependency: edge.dependency(),
+            range: edge.range().to_string(),
+        }
+    }
+}
+
+fn format_requirement_chain(chain: &[RequirementEdgeInfo]) -> String {
+    chain
+        .iter()
+        .map(|edge| match (edge.dependant_name, edge.dependant_version) {
+            (None, _) => format!("project requires {} {}", edge.dependency, edge.range),
+            (Some(name), Some(version)) => format!(
+                "{name} {version} requires {} {}",
+                edge.dependency, edge.range
+            ),
+            (Some(name), None) => {
+                format!("{name} requires {} {}", edge.dependency, edge.range)
+            }
+        })
+        .join(" -> ")
+}
+
+fn print_changes_json(changes: &PendingProjectChanges) {
+    #[derive(Serialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    enum PackageChangeInfo<'a> {
+        Install {
+            package_name: &'a str,
+            install_version: Option<&'a Version>,
+            yanked: bool,
+            add_to_locked: bool,
+            to_dependencies: Option<String>,
+        },
+        Remove {
+            package_name: &'a str,
+            reason: &'static str,
+        },
+    }
+
+    #[derive(Serialize)]
+    struct ConflictInfo<'a> {
+        package_name: &'a str,
+        conflicting_packages: &'a [Box<str>],
+        conflicts_with_unity: bool,
+        requirement_chains: Vec<Vec<RequirementEdgeInfo<'a>>>,
+    }
+
+    #[derive(Serialize)]
+    struct ChangesInfo<'a> {
+        package_changes: Vec<PackageChangeInfo<'a>>,
+        remove_legacy_files: Vec<&'a Path>,
+        remove_legacy_folders: Vec<&'a Path>,
+        conflicts: Vec<ConflictInfo<'a>>,
+    }
+
+    let package_changes = (changes.package_changes().iter())
+        .sorted_by_key(|(name, _)| *name)
+        .map(|(name, change)| match change {
+            PackageChange::Install(change) => PackageChangeInfo::Install {
+                package_name: name,
+                install_version: change.install_package().map(|x| x.version()),
+                yanked: change.install_package().is_some_and(|x| x.is_yanked()),
+                add_to_locked: change.is_adding_to_locked(),
+                to_dependencies: change.to_dependencies().map(|x| x.to_string()),
+            },
+            PackageChange::Remove(change) => PackageChangeInfo::Remove {
+                package_name: name,
+                reason: match change.reason() {
+                    RemoveReason::Requested => "requested",
+                    RemoveReason::Legacy => "legacy",
+                    RemoveReason::Unused => "unused",
+                },
+            },
+        })
+        .collect();
+
+    let conflicts = (changes.conflicts().iter())
+        .sorted_by_key(|(name, _)| *name)
+        .map(|(name, conflict)| ConflictInfo {
+            package_name: name,
+            conflicting_packages: conflict.conflicting_packages(),
+            conflicts_with_unity: conflict.conflicts_with_unity(),
+            requirement_chains: (conflict.requirement_chains().iter())
+                .map(|chain| chain.iter().map(RequirementEdgeInfo::from).collect())
+                .collect(),
+        })
+        .collect();
+
+    let info = ChangesInfo {
+        package_changes,
+        remove_legacy_files: (changes.remove_legacy_files().iter())
+            .map(|(x, _)| x.as_ref())
+            .collect(),
+        remove_legacy_folders: (changes.remove_legacy_folders().iter())
+            .map(|(x, _)| x.as_ref())
+            .collect(),
+        conflicts,
+    };
+
+    println!("{}", serde_json::to_string(&info).unwrap());
+
+    // empty package_changes in the json tells nothing to do, which is not an error
+    if changes.package_changes().is_empty() {
+        exit(0);
+    }
+}
+
 fn prompt_install(yes: bool) {
     if yes {
-        println!("--yes is set. skipping confirm");
+        eprintln!("--yes is set. skipping confirm");
     } else if !confirm_prompt("Do you want to apply those changes?") {
         exit(1);
     }
@@ -335,8 +596,11 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
     }
 }
 
+mod bundle;
 mod info;
 mod migrate;
+mod repo_serve;
+mod settings;
 #[cfg(feature = "experimental-vcc")]
 mod vcc;
 
@@ -351,15 +615,24 @@ pub enum Command {
     Remove(Remove),
     Update(Update),
     Outdated(Outdated),
+    Why(Why),
+    CheckUnity(CheckUnity),
     Upgrade(Upgrade),
+    Hold(Hold),
+    Unhold(Unhold),
     Downgrade(Downgrade),
     Search(Search),
+    Pack(Pack),
     #[command(subcommand)]
     Repo(Repo),
     #[command(subcommand)]
     Info(info::Info),
     #[command(subcommand)]
     Migrate(migrate::Migrate),
+    #[command(subcommand)]
+    Bundle(bundle::Bundle),
+    #[command(subcommand)]
+    Settings(settings::Settings),
     #[cfg(feature = "experimental-vcc")]
     #[command(subcommand)]
     Vcc(vcc::Vcc),
@@ -376,12 +649,19 @@ multi_command!(Command is
     Remove,
     Update,
     Outdated,
+    Why,
+    CheckUnity,
     Upgrade,
+    Hold,
+    Unhold,
     Downgrade,
     Search,
+    Pack,
     Repo,
     Info,
     Migrate,
+    Bundle,
+    Settings,
     Vcc,
     Completion,
 );
@@ -414,6 +694,13 @@ pub struct Install {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan_args: PlanArgs,
+
+    /// Which version of dependencies to use.
+    /// `lowest` is useful to check the lower bounds of `vpmDependencies`
+    #[arg(long, value_enum, default_value_t)]
+    resolution: Resolution,
 
     /// skip confirm
     #[arg(short, long)]
@@ -427,6 +714,9 @@ impl Install {
             return Resolve {
                 project: self.project,
                 env_args: self.env_args,
+                plan_args: self.plan_args,
+                frozen: false,
+                resolution: self.resolution,
             }
             .run()
             .await;
@@ -467,16 +757,17 @@ impl Install {
         };
 
         let changes = unity
-            .add_package_request(
+            .add_package_request_with_strategy(
                 &env,
                 &packages,
                 AddPackageOperation::InstallToDependencies,
                 self.prerelease,
+                self.resolution.into(),
             )
             .await
             .exit_context("collecting packages to be installed");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan_args);
 
         if require_prompt_for_install(&changes, name.as_str(), None) {
             prompt_install(self.yes);
@@ -504,6 +795,18 @@ pub struct Resolve {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan_args: PlanArgs,
+
+    /// Install exactly the locked versions without changing vpm-manifest.json.
+    /// Fails if locked versions are not found or inconsistent with dependencies.
+    #[arg(long, alias = "locked")]
+    frozen: bool,
+
+    /// Which version of dependencies not locked yet to use.
+    /// `lowest` is useful to check the lower bounds of `vpmDependencies`
+    #[arg(long, value_enum, default_value_t, conflicts_with = "frozen")]
+    resolution: Resolution,
 }
 
 impl Resolve {
@@ -511,19 +814,28 @@ impl Resolve {
         let env = load_env(&self.env_args).await;
         let mut unity = load_unity(self.project).await;
 
-        let changes = unity
-            .resolve_request(&env)
-            .await
-            .exit_context("collecting packages to be installed");
+        let changes = if self.frozen {
+            unity
+                .resolve_request_frozen(&env)
+                .await
+                .exit_context("collecting locked packages to be installed")
+        } else {
+            unity
+                .resolve_request_with_strategy(&env, self.resolution.into())
+                .await
+                .exit_context("collecting packages to be installed")
+        };
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan_args);
 
         unity
             .apply_pending_changes(&env, changes)
             .await
             .exit_context("installing packages");
 
-        unity.save().await.exit_context("saving manifest file");
+        if !self.frozen {
+            unity.save().await.exit_context("saving manifest file");
+        }
     }
 }
 
@@ -540,6 +852,8 @@ pub struct Remove {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan_args: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -556,7 +870,7 @@ impl Remove {
             .await
             .exit_context("collecting packages to be removed");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan_args);
 
         let confirm =
             changes.package_changes().len() >= self.names.len() || !changes.conflicts().is_empty();
@@ -615,6 +929,9 @@ impl Outdated {
         let selector = VersionSelector::latest_for(unity.unity_version(), self.prerelease);
 
         for locked in unity.locked_packages() {
+            if unity.is_held(locked.name()) {
+                continue;
+            }
             match env.find_package_by_name(locked.name(), selector) {
                 None => log::error!("latest version for package {} not found.", locked.name()),
                 // if found version is newer: add to outdated
@@ -627,6 +944,7 @@ impl Outdated {
 
         for locked in unity.all_packages() {
             for (name, range) in locked.dependencies() {
+                let range = unity.dependency_range(locked.name(), name, range);
                 if let Some((outdated, _)) = outdated_packages.get(name.as_ref()) {
                     if !range.matches(outdated.version()) {
                         outdated_packages.remove(name.as_ref());
@@ -639,10 +957,11 @@ impl Outdated {
             0 => {
                 for (name, (found, installed)) in &outdated_packages {
                     println!(
-                        "{}: installed: {}, found: {}",
+                        "{}: installed: {}, found: {} ({})",
                         name,
                         installed,
-                        &found.version()
+                        &found.version(),
+                        UpgradeLevel::between(installed, found.version()),
                     );
                 }
             }
@@ -652,6 +971,8 @@ impl Outdated {
                     package_name: &'a str,
                     installed_version: &'a Version,
                     newer_version: &'a Version,
+                    // since 1.8.0
+                    upgrade_level: UpgradeLevel,
                 }
                 let info = outdated_packages
                     .into_iter()
@@ -659,6 +980,7 @@ impl Outdated {
                         package_name,
                         installed_version: installed,
                         newer_version: found.version(),
+                        upgrade_level: UpgradeLevel::between(installed, found.version()),
                     })
                     .collect::<Vec<_>>();
                 println!("{}", serde_json::to_string(&info).unwrap());
@@ -668,6 +990,122 @@ impl Outdated {
     }
 }
 
+/// Show why the package is installed to the project
+///
+/// This prints all paths from the dependencies of the project to the package.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Why {
+    /// Name of the package
+    #[arg()]
+    name: String,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+
+    /// With this option, output is printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
+}
+
+/// Returns all paths from the dependencies of the project to the `target` package.
+fn why_paths<'a>(unity: &'a UnityProject, target: &str) -> Vec<Vec<RequirementEdgeInfo<'a>>> {
+    fn find_paths<'a>(
+        unity: &'a UnityProject,
+        current: LockedDependencyInfo<'a>,
+        target: &str,
+        path: &mut Vec<RequirementEdgeInfo<'a>>,
+        paths: &mut Vec<Vec<RequirementEdgeInfo<'a>>>,
+    ) {
+        for (dependency, range) in current.dependencies() {
+            if path.iter().any(|x| x.dependency == dependency.as_ref()) {
+                // circular dependency
+                continue;
+            }
+            path.push(RequirementEdgeInfo {
+                dependant_name: Some(current.name()),
+                dependant_version: Some(current.version()),
+                dependency,
+                range: unity
+                    .dependency_range(current.name(), dependency, range)
+                    .to_string(),
+            });
+            if dependency.as_ref() == target {
+                paths.push(path.clone());
+            } else if let Some(locked) = unity.get_locked(dependency) {
+                find_paths(unity, locked, target, path, paths);
+            }
+            path.pop();
+        }
+    }
+
+    let mut paths = Vec::new();
+    for (dependency, range) in unity.dependencies_with_range() {
+        let mut path = vec![RequirementEdgeInfo {
+            dependant_name: None,
+            dependant_version: None,
+            dependency,
+            range: range.to_string(),
+        }];
+        if dependency == target {
+            paths.push(path);
+        } else if let Some(locked) = unity.get_locked(dependency) {
+            find_paths(unity, locked, target, &mut path, &mut paths);
+        }
+    }
+    paths
+}
+
+impl Why {
+    pub async fn run(self) {
+        let unity = load_unity(self.project).await;
+
+        let Some(target) = unity.get_locked(&self.name) else {
+            exit_with!("package {} is not installed", self.name);
+        };
+
+        let paths = why_paths(&unity, target.name());
+
+        match self.json_format.map(|x| x.get()).unwrap_or(0) {
+            0 => {
+                if paths.is_empty() {
+                    println!(
+                        "{} version {} is not required by any dependencies of the project",
+                        target.name(),
+                        target.version()
+                    );
+                    return;
+                }
+
+                println!(
+                    "{} version {} is required by the following paths:",
+                    target.name(),
+                    target.version()
+                );
+                for path in &paths {
+                    println!("- {}", format_requirement_chain(path));
+                }
+            }
+            1 => {
+                #[derive(Serialize)]
+                struct WhyInfo<'a> {
+                    package_name: &'a str,
+                    installed_version: &'a Version,
+                    paths: &'a [Vec<RequirementEdgeInfo<'a>>],
+                }
+                let info = WhyInfo {
+                    package_name: target.name(),
+                    installed_version: target.version(),
+                    paths: &paths,
+                };
+                println!("{}", serde_json::to_string(&info).unwrap());
+            }
+            v => exit_with!("unsupported json version: {v}"),
+        }
+    }
+}
+
 /// Upgrade specified package or all packages to latest or specified version.
 ///
 /// With install command, you'll add to dependencies. With upgrade command,
@@ -690,10 +1128,24 @@ pub struct Upgrade {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan_args: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
     yes: bool,
+
+    /// Allow upgrading held packages
+    #[arg(long)]
+    allow_held: bool,
+
+    /// Upgrade only within the same major.minor version of the locked version
+    #[arg(long, conflicts_with_all = ["minor", "VERSION"])]
+    patch: bool,
+
+    /// Upgrade only within the same major version of the locked version
+    #[arg(long, conflicts_with = "VERSION")]
+    minor: bool,
 }
 
 impl Upgrade {
@@ -701,35 +1153,116 @@ impl Upgrade {
         let env = load_env(&self.env_args).await;
         let mut unity = load_unity(self.project).await;
 
-        let updates = if let Some(name) = &self.name {
-            let version_selector = match self.version {
-                None => VersionSelector::latest_for(unity.unity_version(), self.prerelease),
-                Some(ref version) => VersionSelector::specific_version(version),
+        let max_level = if self.patch {
+            Some(UpgradeLevel::Patch)
+        } else if self.minor {
+            Some(UpgradeLevel::Minor)
+        } else {
+            None
+        };
+
+        let version_selector = VersionSelector::latest_for(unity.unity_version(), self.prerelease);
+
+        let changes = if let Some(name) = &self.name {
+            let package = match (&self.version, max_level) {
+                (Some(version), _) => {
+                    get_package(&env, name, VersionSelector::specific_version(version))
+                }
+                (None, Some(max_level)) => {
+                    let Some(locked) = unity.get_locked(name) else {
+                        exit_with!("package {name} is not locked");
+                    };
+                    get_upgrade_package(&env, name, locked.version(), version_selector, max_level)
+                        .unwrap_or_else(|| exit_with!("no matching package not found"))
+                }
+                (None, None) => get_package(&env, name, version_selector),
             };
-            let package = get_package(&env, name, version_selector);
 
-            vec![package]
-        } else {
-            let version_selector =
-                VersionSelector::latest_for(unity.unity_version(), self.prerelease);
+            let changes = upgrade_request(&env, &unity, &[package], self.prerelease).await;
 
-            unity
+            if let Some(max_level) = max_level {
+                let beyond = upgrades_beyond(&unity, &changes, max_level);
+                if !beyond.is_empty() {
+                    exit_with!(
+                        "upgrading {name} requires upgrades beyond {max_level}: {}",
+                        beyond.join(", ")
+                    );
+                }
+            }
+
+            changes
+        } else if let Some(max_level) = max_level {
+            // upgrades which keep all packages within max_level
+            let mut updates = Vec::new();
+            for locked in unity.locked_packages() {
+                if unity.is_held(locked.name()) {
+                    continue;
+                }
+                let Some(package) = get_upgrade_package(
+                    &env,
+                    locked.name(),
+                    locked.version(),
+                    version_selector,
+                    max_level,
+                ) else {
+                    warn!("skipping {}: no matching version found", locked.name());
+                    continue;
+                };
+                if package.version() == locked.version() {
+                    continue;
+                }
+                let changes = upgrade_request(&env, &unity, &[package], self.prerelease).await;
+                let beyond = upgrades_beyond(&unity, &changes, max_level);
+                if beyond.is_empty() {
+                    updates.push(package);
+                } else {
+                    warn!(
+                        "skipping {} {}: it requires upgrades beyond {max_level}: {}",
+                        package.name(),
+                        package.version(),
+                        beyond.join(", ")
+                    );
+                }
+            }
+
+            let changes = upgrade_request(&env, &unity, &updates, self.prerelease).await;
+            let beyond = upgrades_beyond(&unity, &changes, max_level);
+            if !beyond.is_empty() {
+                exit_with!(
+                    "upgrading packages together requires upgrades beyond {max_level}: {}",
+                    beyond.join(", ")
+                );
+            }
+            changes
+        } else {
+            let updates = unity
                 .locked_packages()
+                .filter(|locked| !unity.is_held(locked.name()))
                 .map(|locked| get_package(&env, locked.name(), version_selector))
-                .collect()
+                .collect::<Vec<_>>();
+
+            upgrade_request(&env, &unity, &updates, self.prerelease).await
         };
 
-        let changes = unity
-            .add_package_request(
-                &env,
-                &updates,
-                AddPackageOperation::UpgradeLocked,
-                self.prerelease,
-            )
-            .await
-            .exit_context("collecting packages to be upgraded");
+        if !self.allow_held {
+            let held = (changes.package_changes().iter())
+                .filter(|(_, change)| {
+                    (change.as_install()).is_some_and(|x| x.install_package().is_some())
+                })
+                .map(|(name, _)| name.as_ref())
+                .filter(|name| unity.is_held(name))
+                .sorted()
+                .collect::<Vec<_>>();
+
+            if !held.is_empty() {
+                exit_with!(
+                    "the following packages are held: {}\nuse --allow-held to upgrade held packages",
+                    held.join(", ")
+                );
+            }
+        }
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan_args);
 
         let require_prompt = if let Some(name) = &self.name {
             require_prompt_for_install(&changes, name.as_str(), None)
@@ -752,8 +1285,10 @@ impl Upgrade {
             .await
             .exit_context("upgrading packages");
 
-        for (name, version) in updates {
-            println!("upgraded {} to {}", name, version);
+        if self.plan_args.json_format.is_none() {
+            for (name, version) in updates {
+                println!("upgraded {} to {}", name, version);
+            }
         }
 
         save_unity(&mut unity).await;
@@ -761,22 +1296,17 @@ impl Upgrade {
     }
 }
 
-/// Downgrade the specified package specified version.
+/// Check whether the project is compatible with the unity version
 ///
-/// With install command, you'll add to dependencies. With upgrade command,
-/// you'll upgrade dependencies or locked dependencies but not add to dependencies.
+/// This checks each locked package, finds the newest compatible versions of incompatible packages,
+/// and simulates resolving the project with them to find conflicts.
+/// This exits with non-zero status if the project is not compatible.
 #[derive(Parser)]
 #[command(author, version)]
-pub struct Downgrade {
-    /// Name of Package
-    #[arg()]
-    name: String,
-    /// Version of package.
-    #[arg(id = "VERSION")]
-    version: Version,
-    /// Include prerelease
-    #[arg(long = "prerelease")]
-    prerelease: bool,
+pub struct CheckUnity {
+    /// Unity version to check. e.g. 2022.3.6f1
+    #[arg(value_parser = parse_unity_version)]
+    unity_version: UnityVersion,
 
     /// Path to project dir. by default CWD or parents of CWD will be used
     #[arg(short = 'p', long = "project")]
@@ -784,67 +1314,320 @@ pub struct Downgrade {
     #[command(flatten)]
     env_args: EnvArgs,
 
-    /// skip confirm
-    #[arg(short, long)]
-    yes: bool,
+    /// With this option, output is printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
 }
 
-impl Downgrade {
+fn parse_unity_version(input: &str) -> Result<UnityVersion, String> {
+    UnityVersion::parse(input).ok_or_else(|| format!("invalid unity version: {input}"))
+}
+
+impl CheckUnity {
     pub async fn run(self) {
         let env = load_env(&self.env_args).await;
-        let mut unity = load_unity(self.project).await;
-
-        let updates = [get_package(
-            &env,
-            &self.name,
-            VersionSelector::specific_version(&self.version),
-        )];
+        let unity = load_unity(self.project).await;
 
-        let changes = unity
-            .add_package_request(
-                &env,
-                &updates,
-                AddPackageOperation::Downgrade,
-                self.prerelease,
-            )
+        let report = unity
+            .check_unity_compatibility(&env, self.unity_version)
             .await
-            .exit_context("collecting packages to be upgraded");
-
-        print_prompt_install(&changes);
-
-        if require_prompt_for_install(&changes, self.name.as_str(), None) {
-            prompt_install(self.yes)
-        }
+            .exit_context("checking compatibility");
+        let changes = report.simulated_changes();
 
-        let downgrades = (changes.package_changes().iter())
-            .filter_map(|(_, x)| x.as_install())
-            .filter_map(|x| x.install_package())
-            .map(|x| (x.name().to_owned(), x.version().clone()))
+        let installs = (changes.package_changes().values())
+            .filter_map(|change| change.as_install())
+            .filter_map(|change| change.install_package())
             .collect::<Vec<_>>();
 
-        unity
-            .apply_pending_changes(&env, changes)
-            .await
-            .exit_context("upgrading packages");
+        match self.json_format.map(|x| x.get()).unwrap_or(0) {
+            0 => {
+                println!(
+                    "Checking compatibility with unity {}",
+                    report.unity_version()
+                );
+                for package in report.packages() {
+                    let status = match (package.compatible(), package.newest_compatible()) {
+                        (Some(true), _) => "compatible".to_string(),
+                        (Some(false), Some(newest)) => format!(
+                            "incompatible, newest compatible version: {}",
+                            newest.version()
+                        ),
+                        (Some(false), None) => {
+                            "incompatible, no compatible version found".to_string()
+                        }
+                        (None, _) => "unknown, not found in repositories".to_string(),
+                    };
+                    println!(
+                        "- {} version {}: {status}",
+                        package.name(),
+                        package.locked_version()
+                    );
+                }
 
-        for (name, version) in downgrades {
-            println!("downgraded {} to {}", name, version);
-        }
+                if !installs.is_empty() {
+                    println!("Upgrading to the newest compatible versions installs:");
+                    for package in &installs {
+                        println!("- {} version {}", package.name(), package.version());
+                    }
+                }
 
-        save_unity(&mut unity).await;
-        update_project_last_modified(env, unity.project_dir()).await;
-    }
-}
+                if !changes.conflicts().is_empty() {
+                    println!("**The following packages conflict**");
+                    for (package, conflict) in changes.conflicts() {
+                        if conflict.conflicts_with_unity() {
+                            println!("- {package} is not compatible with the unity version");
+                        }
+                        if !conflict.conflicting_packages().is_empty() {
+                            println!(
+                                "- {package} conflicts with {}",
+                                conflict.conflicting_packages().iter().join(", ")
+                            );
+                        }
+                    }
+                }
 
-/// Search package by the query
-///
-/// Search for packages that includes query in either name, displayName, or description.
-#[derive(Parser)]
-#[command(author, version)]
-pub struct Search {
-    /// Name of Package
-    #[arg(required = true, name = "QUERY")]
-    queries: Vec<String>,
+                if report.is_compatible() {
+                    println!(
+                        "The project is compatible with unity {}",
+                        report.unity_version()
+                    );
+                } else {
+                    println!(
+                        "The project is not compatible with unity {}",
+                        report.unity_version()
+                    );
+                }
+            }
+            1 => {
+                #[derive(Serialize)]
+                struct Report<'a> {
+                    unity_version: UnityVersion,
+                    compatible: bool,
+                    packages: Vec<PackageCompatibilityInfo<'a>>,
+                    installs: Vec<InstallInfo<'a>>,
+                    conflicts: Vec<ConflictInfo<'a>>,
+                }
+
+                #[derive(Serialize)]
+                struct PackageCompatibilityInfo<'a> {
+                    name: &'a str,
+                    locked_version: &'a Version,
+                    compatible: Option<bool>,
+                    newest_compatible_version: Option<&'a Version>,
+                }
+
+                #[derive(Serialize)]
+                struct InstallInfo<'a> {
+                    name: &'a str,
+                    version: &'a Version,
+                }
+
+                #[derive(Serialize)]
+                struct ConflictInfo<'a> {
+                    package: &'a str,
+                    conflicting_packages: &'a [Box<str>],
+                    conflicts_with_unity: bool,
+                }
+
+                let info = Report {
+                    unity_version: report.unity_version(),
+                    compatible: report.is_compatible(),
+                    packages: (report.packages().iter())
+                        .map(|package| PackageCompatibilityInfo {
+                            name: package.name(),
+                            locked_version: package.locked_version(),
+                            compatible: package.compatible(),
+                            newest_compatible_version: package
+                                .newest_compatible()
+                                .map(|x| x.version()),
+                        })
+                        .collect(),
+                    installs: (installs.iter())
+                        .map(|x| InstallInfo {
+                            name: x.name(),
+                            version: x.version(),
+                        })
+                        .collect(),
+                    conflicts: (changes.conflicts().iter())
+                        .map(|(package, conflict)| ConflictInfo {
+                            package,
+                            conflicting_packages: conflict.conflicting_packages(),
+                            conflicts_with_unity: conflict.conflicts_with_unity(),
+                        })
+                        .collect(),
+                };
+                println!("{}", serde_json::to_string(&info).unwrap());
+            }
+            v => exit_with!("unsupported json version: {v}"),
+        }
+
+        if !report.is_compatible() {
+            exit(1);
+        }
+    }
+}
+
+/// Hold packages to the current version
+///
+/// Held packages are not upgraded by `vrc-get upgrade` without package name and
+/// not listed by `vrc-get outdated`.
+/// Without package names, this command lists held packages.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Hold {
+    /// Name of Packages to hold
+    #[arg()]
+    names: Vec<String>,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+}
+
+impl Hold {
+    pub async fn run(self) {
+        let mut unity = load_unity(self.project).await;
+
+        if self.names.is_empty() {
+            for name in unity.held_packages() {
+                println!("{name}");
+            }
+            return;
+        }
+
+        for name in &self.names {
+            if !unity.is_locked(name) {
+                exit_with!("package {name} is not installed");
+            }
+        }
+
+        for name in &self.names {
+            unity.set_held(name, true);
+        }
+
+        save_unity(&mut unity).await;
+    }
+}
+
+/// Remove holds from packages
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Unhold {
+    /// Name of Packages to remove holds
+    #[arg(required = true)]
+    names: Vec<String>,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+}
+
+impl Unhold {
+    pub async fn run(self) {
+        let mut unity = load_unity(self.project).await;
+
+        for name in &self.names {
+            if !unity.is_held(name) {
+                exit_with!("package {name} is not held");
+            }
+        }
+
+        for name in &self.names {
+            unity.set_held(name, false);
+        }
+
+        save_unity(&mut unity).await;
+    }
+}
+
+/// Downgrade the specified package specified version.
+///
+/// With install command, you'll add to dependencies. With upgrade command,
+/// you'll upgrade dependencies or locked dependencies but not add to dependencies.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Downgrade {
+    /// Name of Package
+    #[arg()]
+    name: String,
+    /// Version of package.
+    #[arg(id = "VERSION")]
+    version: Version,
+    /// Include prerelease
+    #[arg(long = "prerelease")]
+    prerelease: bool,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+    #[command(flatten)]
+    env_args: EnvArgs,
+    #[command(flatten)]
+    plan_args: PlanArgs,
+
+    /// skip confirm
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Downgrade {
+    pub async fn run(self) {
+        let env = load_env(&self.env_args).await;
+        let mut unity = load_unity(self.project).await;
+
+        let updates = [get_package(
+            &env,
+            &self.name,
+            VersionSelector::specific_version(&self.version),
+        )];
+
+        let changes = unity
+            .add_package_request(
+                &env,
+                &updates,
+                AddPackageOperation::Downgrade,
+                self.prerelease,
+            )
+            .await
+            .exit_context("collecting packages to be upgraded");
+
+        print_prompt_install(&changes, &self.plan_args);
+
+        if require_prompt_for_install(&changes, self.name.as_str(), None) {
+            prompt_install(self.yes)
+        }
+
+        let downgrades = (changes.package_changes().iter())
+            .filter_map(|(_, x)| x.as_install())
+            .filter_map(|x| x.install_package())
+            .map(|x| (x.name().to_owned(), x.version().clone()))
+            .collect::<Vec<_>>();
+
+        unity
+            .apply_pending_changes(&env, changes)
+            .await
+            .exit_context("upgrading packages");
+
+        if self.plan_args.json_format.is_none() {
+            for (name, version) in downgrades {
+                println!("downgraded {} to {}", name, version);
+            }
+        }
+
+        save_unity(&mut unity).await;
+        update_project_last_modified(env, unity.project_dir()).await;
+    }
+}
+
+/// Search package by the query
+///
+/// Search for packages that includes query in either name, displayName, or description.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Search {
+    /// Name of Package
+    #[arg(required = true, name = "QUERY")]
+    queries: Vec<String>,
 
     #[command(flatten)]
     env_args: EnvArgs,
@@ -898,18 +1681,128 @@ impl Search {
     }
 }
 
+/// Pack the package folder to the zip to distribute
+///
+/// This checks package.json and .meta files, and creates the zip with package.json at the root.
+/// Files matching patterns in `.vpmignore` in the package folder are not included.
+/// The zip is deterministic so packing the same files always produces the same zip.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Pack {
+    /// Path to the package folder. by default CWD will be used
+    #[arg()]
+    package: Option<PathBuf>,
+
+    /// Path to the zip to write. by default `<name>-<version>.zip` next to the package folder
+    ///
+    /// The zip cannot be written inside the package folder.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+impl Pack {
+    pub async fn run(self) {
+        let package = self.package.unwrap_or_else(|| PathBuf::from("."));
+        let package_io = DefaultProjectIo::new(package.clone().into_boxed_path());
+
+        let problems = check_package(&package_io)
+            .await
+            .exit_context("checking package");
+        if !problems.is_empty() {
+            for problem in &problems {
+                eprintln!("{problem}");
+            }
+            exit_with!("{} problems found in {}", problems.len(), package.display());
+        }
+
+        let package_json = tokio::fs::read(package.join("package.json"))
+            .await
+            .exit_context("reading package.json");
+        // check_package accepts package.json with BOM
+        let no_bom = (package_json.strip_prefix(b"\xEF\xBB\xBF")).unwrap_or(&package_json);
+        let package_json =
+            serde_json::from_slice::<PackageJson>(no_bom).exit_context("parsing package.json");
+
+        let package_dir = tokio::fs::canonicalize(&package)
+            .await
+            .exit_context("resolving package folder");
+
+        let output = self.output.unwrap_or_else(|| {
+            let Some(parent) = package_dir.parent() else {
+                exit_with!("no folder to write zip next to {}", package_dir.display());
+            };
+            parent.join(format!(
+                "{}-{}.zip",
+                package_json.name(),
+                package_json.version()
+            ))
+        });
+        let (Some(output_dir), Some(file_name)) = (output.parent(), output.file_name()) else {
+            exit_with!("invalid output path: {}", output.display());
+        };
+        let output_dir = Some(output_dir)
+            .filter(|x| !x.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        tokio::fs::create_dir_all(output_dir)
+            .await
+            .exit_context("creating output folder");
+
+        // the zip in the package folder would be packed into the next zip
+        let output_dir_canonical = tokio::fs::canonicalize(output_dir)
+            .await
+            .exit_context("resolving output folder");
+        if output_dir_canonical.starts_with(&package_dir) {
+            exit_with!(
+                "the zip cannot be written inside the package folder: {}",
+                output.display()
+            );
+        }
+
+        let output_io = DefaultProjectIo::new(output_dir.into());
+
+        let file = output_io
+            .create(file_name.as_ref())
+            .await
+            .exit_context("creating zip");
+        create_package_zip(&package_io, file)
+            .await
+            .exit_context("packing package");
+
+        let file = output_io
+            .open(file_name.as_ref())
+            .await
+            .exit_context("opening zip");
+        let zip = PackageZip::read(file).await.exit_context("reading zip");
+
+        println!(
+            "packed {} version {} to {}",
+            package_json.name(),
+            package_json.version(),
+            output.display()
+        );
+        println!("zipSHA256: {}", zip.sha256());
+    }
+}
+
 /// Commands around repositories
 #[derive(Subcommand)]
 #[command(author, version)]
 pub enum Repo {
     List(RepoList),
     Add(RepoAdd),
+    AddLink(RepoAddLink),
     Remove(RepoRemove),
     Cleanup(RepoCleanup),
     Packages(RepoPackages),
+    Priority(RepoPriority),
+    Build(RepoBuild),
+    Serve(repo_serve::RepoServe),
+    News(RepoNews),
+    Export(RepoExport),
+    Import(RepoImport),
 }
 
-multi_command!(Repo is List, Add, Remove, Cleanup, Packages);
+multi_command!(Repo is List, Add, AddLink, Remove, Cleanup, Packages, Priority, Build, Serve, News, Export, Import);
 
 /// List all repositories
 #[derive(Parser)]
@@ -924,14 +1817,25 @@ impl RepoList {
         let env = load_env(&self.env_args).await;
 
         for (local_path, repo) in env.get_repos() {
+            let priority = env
+                .get_user_repos()
+                .iter()
+                .find(|x| x.local_path() == local_path.as_ref())
+                .map(|x| env.get_repo_priority(x))
+                .unwrap_or(0);
             println!(
-                "{}: {} (from {} at {})",
+                "{}: {} (from {} at {}){}",
                 repo.id()
                     .or(repo.url().map(Url::as_str))
                     .unwrap_or("(no id)"),
                 repo.name().unwrap_or("(unnamed)"),
                 repo.url().map(Url::as_str).unwrap_or("(no remote)"),
                 local_path.display(),
+                if priority != 0 {
+                    format!(" priority {priority}")
+                } else {
+                    String::new()
+                },
             );
         }
     }
@@ -949,9 +1853,38 @@ pub struct RepoAdd {
     name: Option<String>,
 
     /// Headers
+    ///
+    /// With `--resolve-header-references`, header values can refer to `${env:NAME}` for
+    /// environment variables or `${cmd:COMMAND}` for the output of credential helper commands.
     #[arg(short='H', long, value_parser = HeaderPair::from_str)]
     header: Vec<HeaderPair>,
 
+    /// Resolve `${env:NAME}` and `${cmd:COMMAND}` references in header values
+    ///
+    /// The references are resolved when requests are made and the resolved values are not saved.
+    #[arg(long)]
+    resolve_header_references: bool,
+
+    /// Minisign public key to verify the signature of the repository with.
+    ///
+    /// The signature is downloaded from `<url>.minisig` every time the repository is fetched.
+    #[arg(long)]
+    public_key: Option<String>,
+
+    /// Mirror URL of the repository. Mirrors are tried in order if fetching from the URL fails.
+    #[arg(long = "mirror")]
+    mirrors: Vec<Url>,
+
+    /// Package name pattern the repository may provide. `*` matches any characters.
+    ///
+    /// If specified, packages not matching any of the patterns are ignored.
+    #[arg(long = "allow-package")]
+    allowed_packages: Vec<String>,
+
+    /// Package name pattern the repository must not provide. `*` matches any characters.
+    #[arg(long = "deny-package")]
+    denied_packages: Vec<String>,
+
     #[command(flatten)]
     env_args: EnvArgs,
 }
@@ -1011,14 +1944,28 @@ impl RepoAdd {
     pub async fn run(self) {
         let mut env = load_env(&self.env_args).await;
 
+        let allowed = (self.allowed_packages.into_iter().map(Into::into)).collect::<Vec<_>>();
+        let denied = (self.denied_packages.into_iter().map(Into::into)).collect::<Vec<_>>();
+        let has_filter = !allowed.is_empty() || !denied.is_empty();
+
         if let Ok(url) = Url::parse(&self.path_or_url) {
             let mut headers = IndexMap::<Box<str>, Box<str>>::new();
             for HeaderPair(name, value) in self.header {
                 headers.insert(name.as_str().into(), value.to_str().unwrap().into());
             }
-            env.add_remote_repo(url, self.name.as_deref(), headers)
-                .await
-                .exit_context("adding repository")
+            env.add_remote_repo(
+                url.clone(),
+                self.name.as_deref(),
+                headers,
+                self.mirrors,
+                self.public_key.as_deref(),
+                self.resolve_header_references,
+            )
+            .await
+            .exit_context("adding repository");
+            if has_filter {
+                env.set_repo_package_filter(|x| x.url() == Some(&url), allowed, denied);
+            }
         } else {
             let cwd = env::current_dir().exit_context("getting current directory");
             let joined = cwd.join(&self.path_or_url);
@@ -1026,10 +1973,69 @@ impl RepoAdd {
             if !normalized.exists() {
                 exit_with!("path not found: {}", normalized.display());
             }
+            if self.public_key.is_some() {
+                exit_with!("public key is only supported for remote repositories");
+            }
+            if !self.mirrors.is_empty() {
+                exit_with!("mirrors are only supported for remote repositories");
+            }
             env.add_local_repo(normalized.as_ref(), self.name.as_deref())
-                .exit_context("adding repository")
+                .exit_context("adding repository");
+            if has_filter {
+                env.set_repo_package_filter(
+                    |x| x.local_path() == normalized.as_path(),
+                    allowed,
+                    denied,
+                );
+            }
+        }
+
+        save_env(&mut env).await;
+    }
+}
+
+/// Add repository from `vcc://vpm/addRepo` link
+///
+/// Repository landing pages have `Add to VCC` buttons with the links.
+/// You can copy the link and pass to this command to add the repository.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoAddLink {
+    /// `vcc://vpm/addRepo?url=...` link
+    #[arg(value_parser = AddRepositoryLink::from_str)]
+    link: AddRepositoryLink,
+
+    /// skip confirm
+    #[arg(short, long)]
+    yes: bool,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoAddLink {
+    pub async fn run(self) {
+        let mut env = load_env(&self.env_args).await;
+
+        println!("adding repository {}", self.link.url());
+        if !self.link.headers().is_empty() {
+            println!("with headers:");
+            for (name, value) in self.link.headers() {
+                println!("  {name}: {value}");
+            }
+        }
+
+        if self.yes {
+            eprintln!("--yes is set. skipping confirm");
+        } else if !confirm_prompt("Do you want to add the repository?") {
+            exit(1);
         }
 
+        let (url, headers) = self.link.into_parts();
+        env.add_remote_repo(url, None, headers, Vec::new(), None, false)
+            .await
+            .exit_context("adding repository");
+
         save_env(&mut env).await;
     }
 }
@@ -1070,16 +2076,16 @@ pub struct RepoRemove {
 #[derive(Args)]
 #[group(multiple = false)]
 struct RepoSearcherArgs {
-    /// Find repository to remove by id
+    /// Find repository by id
     #[arg(long)]
     id: bool,
-    /// Find repository to remove by url
+    /// Find repository by url
     #[arg(long)]
     url: bool,
-    /// Find repository to remove by name
+    /// Find repository by name
     #[arg(long)]
     name: bool,
-    /// Find repository to remove by local path
+    /// Find repository by local path
     #[arg(long)]
     path: bool,
 }
@@ -1142,6 +2148,391 @@ impl RepoRemove {
     }
 }
 
+/// Set priority of repository with specified url, path or name
+///
+/// If multiple repositories have the same version of a package,
+/// the package from the repository with the highest priority is used.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoPriority {
+    /// id, url, name, or path of repository
+    #[arg()]
+    finder: String,
+
+    /// The priority of the repository. The default priority is 0.
+    #[arg(allow_negative_numbers = true)]
+    priority: i32,
+
+    #[clap(flatten)]
+    searcher: RepoSearcherArgs,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoPriority {
+    pub async fn run(self) {
+        let mut env = load_env(&self.env_args).await;
+
+        // we're using OsStr for paths.
+        let finder = OsStr::new(self.finder.as_str());
+        let searcher = self.searcher.as_searcher();
+
+        let count = env.set_repo_priority(|x| searcher.get(x) == Some(finder), self.priority);
+
+        if count == 0 {
+            exit_with!("no repository found with {} {}", searcher, self.finder);
+        }
+
+        println!(
+            "set priority of {} repositories with {} to {}",
+            count, searcher, self.priority
+        );
+
+        save_env(&mut env).await;
+    }
+}
+
+/// Build index.json of a VPM repository from packages in the folder
+///
+/// The folder can contain package zips and package folders with package.json.
+/// Package folders are packed to `<name>-<version>.zip` in the folder of the output index.
+/// If the output index already exists, packages are added to it so previously published versions are kept.
+/// Package folders are checked like `vrc-get pack` before packing.
+/// Published versions are not replaced with different zips unless `--replace` is specified
+/// since clients with the old zip cached would fail to verify it.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoBuild {
+    /// Path to the folder with package zips and package folders
+    #[arg()]
+    input: PathBuf,
+
+    /// Path to the index.json to write. by default index.json in the input folder
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// The template of the url of package zips.
+    /// `{name}`, `{version}`, and `{file}` are replaced with the package name, the version, and the zip file name.
+    #[arg(long)]
+    url_template: String,
+
+    /// The name of the repository
+    #[arg(long)]
+    name: Option<String>,
+
+    /// The id of the repository
+    #[arg(long)]
+    id: Option<String>,
+
+    /// The url of the repository
+    #[arg(long)]
+    url: Option<Url>,
+
+    /// The author of the repository
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Replace versions already in the index with different zips or urls,
+    /// and overwrite existing zips of package folders with different content
+    #[arg(long)]
+    replace: bool,
+}
+
+impl RepoBuild {
+    pub async fn run(self) {
+        let output = self.output.unwrap_or_else(|| self.input.join("index.json"));
+        let output_dir = output
+            .parent()
+            .filter(|x| !x.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let mut builder = match tokio::fs::read(&output).await {
+            Ok(json) => RepositoryBuilder::parse(&json).exit_context("parsing existing index"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => RepositoryBuilder::new(),
+            Err(e) => exit_with!("reading existing index: {e}"),
+        };
+
+        let properties = [
+            ("name", self.name),
+            ("id", self.id),
+            ("url", self.url.map(|x| x.to_string())),
+            ("author", self.author),
+        ];
+        for (key, value) in properties {
+            if let Some(value) = value {
+                builder.set_property(key, &value);
+            }
+        }
+
+        tokio::fs::create_dir_all(output_dir)
+            .await
+            .exit_context("creating output folder");
+
+        let input_io = DefaultProjectIo::new(self.input.clone().into_boxed_path());
+
+        let mut entries = std::fs::read_dir(&self.input)
+            .exit_context("reading input folder")
+            .map(|x| x.exit_context("reading input folder").path())
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        for entry in entries {
+            let Some(file_name) = entry.file_name().and_then(OsStr::to_str) else {
+                continue;
+            };
+
+            let (zip, file_name) = if entry.is_dir() {
+                if !entry.join("package.json").is_file() {
+                    continue;
+                }
+                let package_io = DefaultProjectIo::new(entry.clone().into_boxed_path());
+
+                let problems = check_package(&package_io)
+                    .await
+                    .exit_context(&format!("checking {}", entry.display()));
+                if !problems.is_empty() {
+                    for problem in &problems {
+                        eprintln!("{problem}");
+                    }
+                    exit_with!("{} problems found in {}", problems.len(), entry.display());
+                }
+
+                // packed in memory first so existing zips are not overwritten with different content
+                let packed = create_package_zip(&package_io, Vec::new())
+                    .await
+                    .exit_context(&format!("packing {}", entry.display()));
+                let zip = PackageZip::read(futures::io::Cursor::new(&packed))
+                    .await
+                    .exit_context(&format!("reading package in {}", entry.display()));
+                let package_json = zip.package_json();
+
+                let zip_name = format!("{}-{}.zip", package_json.name(), package_json.version());
+                let zip_path = output_dir.join(&zip_name);
+                match tokio::fs::read(&zip_path).await {
+                    Ok(existing) if existing == packed => {}
+                    Ok(_) if !self.replace => exit_with!(
+                        "{} already exists with different content. use --replace to overwrite it",
+                        zip_path.display()
+                    ),
+                    Ok(_) => write_zip(&zip_path, &packed).await,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        write_zip(&zip_path, &packed).await
+                    }
+                    Err(e) => exit_with!("reading {}: {e}", zip_path.display()),
+                }
+
+                (Ok(zip), zip_name)
+            } else if file_name.ends_with(".zip") {
+                let file = input_io
+                    .open(file_name.as_ref())
+                    .await
+                    .exit_context(&format!("opening {file_name}"));
+                (PackageZip::read(file).await, file_name.to_owned())
+            } else {
+                continue;
+            };
+
+            let zip = zip.exit_context(&format!("reading package in {}", entry.display()));
+            let package_json = zip.package_json();
+
+            let url = self
+                .url_template
+                .replace("{name}", package_json.name())
+                .replace("{version}", &package_json.version().to_string())
+                .replace("{file}", &file_name);
+            let url = Url::parse(&url).exit_context(&format!("parsing url {url}"));
+
+            if builder.check_package(&zip, &url) == AddPackageResult::Replaced && !self.replace {
+                exit_with!(
+                    "{} version {} is already in the index with a different zip or url. use --replace to replace it",
+                    package_json.name(),
+                    package_json.version()
+                );
+            }
+
+            match builder.add_package(&zip, &url) {
+                AddPackageResult::Added => println!(
+                    "added {} version {}",
+                    package_json.name(),
+                    package_json.version()
+                ),
+                AddPackageResult::Unchanged => println!(
+                    "{} version {} is already in the index",
+                    package_json.name(),
+                    package_json.version()
+                ),
+                AddPackageResult::Replaced => warn!(
+                    "{} version {} is replaced with a different zip",
+                    package_json.name(),
+                    package_json.version()
+                ),
+                _ => {}
+            }
+        }
+
+        let json = builder.to_json().exit_context("serializing index");
+        tokio::fs::write(&output, json)
+            .await
+            .exit_context("writing index");
+    }
+}
+
+async fn write_zip(path: &Path, zip: &[u8]) {
+    tokio::fs::write(path, zip)
+        .await
+        .exit_context(&format!("writing {}", path.display()));
+}
+
+/// Show changes of repositories found when repositories were refreshed
+///
+/// New packages, new versions, yanked versions, and removed versions are shown, the newest first.
+/// Only recent changes are kept.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoNews {
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoNews {
+    pub async fn run(self) {
+        let env = load_env(&self.env_args).await;
+
+        let news = env
+            .get_repository_news()
+            .await
+            .exit_context("loading repository news");
+
+        if news.is_empty() {
+            println!("No changes found yet. Changes are recorded when repositories are refreshed.");
+            return;
+        }
+
+        for news in news.iter().rev() {
+            println!(
+                "{} ({}) at {}",
+                news.repository_name().unwrap_or(news.repository_id()),
+                news.repository_id(),
+                news.fetched_at().format("%Y-%m-%d %H:%M:%S UTC"),
+            );
+            for change in news.changes() {
+                let kind = match change.kind() {
+                    PackageNewsKind::NewPackage => "new package",
+                    PackageNewsKind::NewVersion => "new version",
+                    PackageNewsKind::Yanked => "yanked",
+                    PackageNewsKind::Removed => "removed",
+                    _ => "changed",
+                };
+                println!("  {kind}: {} version {}", change.name(), change.version());
+            }
+        }
+    }
+}
+
+/// Export user repositories to the file to share with others
+///
+/// The file contains url, name, id, headers, and other options of remote repositories.
+/// Local repositories are not exported.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoExport {
+    /// Path to the file to write. by default the list is printed to stdout
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Replace header values with `<redacted>` to not share secrets like tokens.
+    /// Headers whose value is exactly one `${env:NAME}` reference are kept. `${cmd:COMMAND}` values are redacted too.
+    #[arg(long)]
+    redact_headers: bool,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoExport {
+    pub async fn run(self) {
+        let env = load_env(&self.env_args).await;
+
+        let list = env.export_repositories(self.redact_headers);
+        let json = list.to_json().exit_context("serializing repositories");
+
+        match self.output {
+            Some(output) => {
+                tokio::fs::write(&output, json)
+                    .await
+                    .exit_context("writing repositories");
+                println!(
+                    "exported {} repositories to {}",
+                    list.repositories().len(),
+                    output.display()
+                );
+            }
+            None => print!("{}", String::from_utf8(json).unwrap()),
+        }
+    }
+}
+
+/// Import user repositories from the file exported with `vrc-get repo export`
+///
+/// Repositories with the same url or id as already added ones are skipped.
+/// Header values exactly one `${env:NAME}` reference are resolved with environment variables of this machine.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoImport {
+    /// Path to the file exported with `vrc-get repo export`
+    #[arg()]
+    input: PathBuf,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoImport {
+    pub async fn run(self) {
+        let mut env = load_env(&self.env_args).await;
+
+        let json = tokio::fs::read(&self.input)
+            .await
+            .exit_context("reading repositories");
+        let list = RepositoryList::parse(&json).exit_context("parsing repositories");
+
+        let mut failed = false;
+
+        for repo in list.repositories() {
+            let name = repo.name().unwrap_or(repo.url().as_str());
+            match env.import_repository(repo).await {
+                Ok(()) => {
+                    println!("added {name}");
+                    let redacted = repo.redacted_headers().collect::<Vec<_>>();
+                    if !redacted.is_empty() {
+                        warn!(
+                            "headers of {name} are redacted and not added: {}",
+                            redacted.join(", ")
+                        );
+                    }
+                }
+                Err(AddRepositoryErr::AlreadyAdded) => println!("skipped {name}: already added"),
+                Err(e @ AddRepositoryErr::HeaderReference { .. }) => {
+                    failed = true;
+                    eprintln!("failed to add {name}: {e}");
+                    eprintln!("if you trust the list, add it with `vrc-get repo add --resolve-header-references`");
+                }
+                Err(e) => {
+                    failed = true;
+                    eprintln!("failed to add {name}: {e}");
+                }
+            }
+        }
+
+        save_env(&mut env).await;
+
+        if failed {
+            exit(1);
+        }
+    }
+}
+
 /// Cleanup repositories in Repos directory
 ///
 /// The official VPM CLI will add &lt;uuid&gt;.json in the Repos directory even if error occurs.
@@ -1202,13 +2593,15 @@ impl RepoPackages {
         }
 
         if let Ok(url) = Url::parse(&self.name_or_url) {
-            if self.env_args.offline {
+            if self.env_args.offline && url.scheme() != "file" {
                 exit_with!("remote repository specified but offline mode.");
             }
-            let client = crate::create_client(self.env_args.offline).unwrap();
-            let (repo, _) = RemoteRepository::download(&client, &url, &IndexMap::new())
-                .await
-                .exit_context("downloading repository");
+            let client = crate::create_client(self.env_args.offline);
+            let io = DefaultEnvironmentIo::new_default();
+            let (repo, _) =
+                RemoteRepository::download(client.as_ref(), &io, &url, &IndexMap::new(), None)
+                    .await
+                    .exit_context("downloading repository");
 
             print_repo(&repo);
         } else {
@@ -1276,3 +2669,129 @@ impl FakeVcc {
         exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn why_paths() {
+        let root = std::env::temp_dir().join(format!("vrc-get-why-{}", std::process::id()));
+        tokio::fs::create_dir_all(root.join("Packages"))
+            .await
+            .unwrap();
+        tokio::fs::create_dir_all(root.join("ProjectSettings"))
+            .await
+            .unwrap();
+        tokio::fs::write(
+            root.join("ProjectSettings/ProjectVersion.txt"),
+            "m_EditorVersion: 2022.3.6f1\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            root.join("Packages/manifest.json"),
+            r#"{"dependencies":{}}"#,
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            root.join("Packages/vpm-manifest.json"),
+            r#"{
+                "dependencies": {
+                    "com.vrchat.avatars": { "version": "3.5.0" },
+                    "com.anatawa12.tool": { "version": "1.0.0" }
+                },
+                "locked": {
+                    "com.vrchat.avatars": {
+                        "version": "3.5.0",
+                        "dependencies": { "com.vrchat.base": "3.5.0" }
+                    },
+                    "com.vrchat.base": {
+                        "version": "3.5.0",
+                        "dependencies": { "com.anatawa12.tool": "^1.0.0" }
+                    },
+                    "com.anatawa12.tool": {
+                        "version": "1.0.0",
+                        "dependencies": { "com.vrchat.avatars": ">=3.0.0" }
+                    },
+                    "com.anatawa12.unused": {
+                        "version": "1.0.0",
+                        "dependencies": {}
+                    }
+                }
+            }"#,
+        )
+        .await
+        .unwrap();
+
+        let unity = UnityProject::load(DefaultProjectIo::new(root.clone().into_boxed_path()))
+            .await
+            .unwrap();
+
+        let describe = |target: &str| {
+            super::why_paths(&unity, target)
+                .into_iter()
+                .map(|path| {
+                    (path.iter())
+                        .map(|edge| match edge.dependant_name {
+                            None => format!("project -> {} {}", edge.dependency, edge.range),
+                            Some(name) => {
+                                format!("{name} -> {} {}", edge.dependency, edge.range)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // direct dependency, and transitive through a circular dependency
+        let mut paths = describe("com.vrchat.base");
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                vec![
+                    "project -> com.anatawa12.tool 1.0.0".to_owned(),
+                    "com.anatawa12.tool -> com.vrchat.avatars >=3.0.0".to_owned(),
+                    "com.vrchat.avatars -> com.vrchat.base 3.5.0".to_owned(),
+                ],
+                vec![
+                    "project -> com.vrchat.avatars 3.5.0".to_owned(),
+                    "com.vrchat.avatars -> com.vrchat.base 3.5.0".to_owned(),
+                ],
+            ]
+        );
+
+        // the cycle back to the target is not followed again
+        let mut paths = describe("com.vrchat.avatars");
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                vec![
+                    "project -> com.anatawa12.tool 1.0.0".to_owned(),
+                    "com.anatawa12.tool -> com.vrchat.avatars >=3.0.0".to_owned(),
+                ],
+                vec!["project -> com.vrchat.avatars 3.5.0".to_owned()],
+            ]
+        );
+
+        assert!(describe("com.anatawa12.unused").is_empty());
+
+        // the same formatting as the requirement chains of conflicts
+        let mut formatted = (super::why_paths(&unity, "com.vrchat.avatars").iter())
+            .map(|path| super::format_requirement_chain(path))
+            .collect::<Vec<_>>();
+        formatted.sort();
+        assert_eq!(
+            formatted,
+            vec![
+                "project requires com.anatawa12.tool 1.0.0 -> com.anatawa12.tool 1.0.0 requires com.vrchat.avatars >=3.0.0".to_owned(),
+                "project requires com.vrchat.avatars 3.5.0".to_owned(),
+            ]
+        );
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
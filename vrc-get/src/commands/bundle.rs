@@ -0,0 +1,150 @@
+use crate::commands::{
+    load_env, load_unity, print_prompt_install, save_unity, EnvArgs, Environment, PlanArgs,
+    ResultExt,
+};
+use clap::{Parser, Subcommand};
+use log::warn;
+use std::path::{Path, PathBuf};
+use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo, IoTrait};
+use vrc_get_vpm::unity_project::ProjectBundle;
+
+/// Offline bundles of the packages locked in the project
+///
+/// The bundle is a single archive with the zips of the packages locked in the project,
+/// so you can resolve the project on machines without network access.
+#[derive(Subcommand)]
+#[command(author, version)]
+pub enum Bundle {
+    Export(Export),
+    Import(Import),
+}
+
+multi_command!(Bundle is Export, Import);
+
+/// Write the bundle of the packages locked in the project
+///
+/// The package zips are taken from the cache in the Repos folder, or downloaded if not cached.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Export {
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+
+    /// Path to the bundle to write. Defaults to `<project name>-bundle.zip`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl Export {
+    pub async fn run(self) {
+        let env = load_env(&self.env_args).await;
+        let unity = load_unity(self.project).await;
+
+        let output = self.output.unwrap_or_else(|| {
+            let project_name = unity.project_dir().file_name().unwrap_or_default();
+            format!("{}-bundle.zip", project_name.to_string_lossy()).into()
+        });
+        let (Some(output_dir), Some(file_name)) = (output.parent(), output.file_name()) else {
+            exit_with!("invalid output path: {}", output.display());
+        };
+        let output_dir = Some(output_dir)
+            .filter(|x| !x.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        tokio::fs::create_dir_all(output_dir)
+            .await
+            .exit_context("creating output folder");
+        let output_io = DefaultProjectIo::new(output_dir.into());
+
+        let file = output_io
+            .create(file_name.as_ref())
+            .await
+            .exit_context("creating bundle");
+        unity
+            .create_bundle(&env, file)
+            .await
+            .exit_context("creating bundle");
+
+        println!(
+            "bundled {} packages to {}",
+            unity.locked_packages().count(),
+            output.display()
+        );
+    }
+}
+
+/// Resolve the project with the packages in the bundle without network access
+///
+/// If the project doesn't have `vpm-manifest.json`, the one in the bundle is used.
+/// The locked versions are installed as is, like `vrc-get resolve --frozen`.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Import {
+    /// Path to the bundle created with `vrc-get bundle export`
+    #[arg()]
+    bundle: PathBuf,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+
+    #[command(flatten)]
+    plan_args: PlanArgs,
+}
+
+impl Import {
+    pub async fn run(self) {
+        let bundle = tokio::fs::read(&self.bundle)
+            .await
+            .exit_context("reading bundle");
+        let bundle = ProjectBundle::parse(bundle)
+            .await
+            .exit_context("parsing bundle");
+
+        let mut unity = load_unity(self.project).await;
+
+        let manifest_path = unity.project_dir().join("Packages/vpm-manifest.json");
+        match tokio::fs::read(&manifest_path).await {
+            Ok(manifest) => {
+                if manifest != bundle.vpm_manifest() {
+                    warn!("vpm-manifest.json of the project is different from the bundle. using one in the project");
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // written with unity.save() after installing packages
+                unity
+                    .use_bundle_manifest(&bundle)
+                    .exit_context("reading vpm-manifest.json in the bundle");
+            }
+            Err(e) => exit_with!("reading vpm-manifest.json: {e}"),
+        }
+
+        let changes = unity
+            .resolve_request_frozen(bundle.repository())
+            .await
+            .exit_context("collecting locked packages to be installed");
+
+        print_prompt_install(&changes, &self.plan_args);
+
+        // we never access network so repositories are not loaded
+        let env = Environment::load(None, DefaultEnvironmentIo::new_default())
+            .await
+            .exit_context("loading global config");
+
+        for (package, zip) in bundle.packages() {
+            env.add_package_to_cache(package, zip)
+                .await
+                .exit_context(&format!("adding {} to cache", package.name()));
+        }
+
+        unity
+            .apply_pending_changes(&env, changes)
+            .await
+            .exit_context("installing packages");
+
+        save_unity(&mut unity).await;
+    }
+}
@@ -3,7 +3,7 @@ use crate::commands::{load_env, load_unity};
 use clap::{Parser, Subcommand};
 use itertools::Itertools;
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU32;
 use std::path::Path;
 use vrc_get_vpm::version::{UnityVersion, Version, VersionRange};
@@ -60,19 +60,24 @@ impl Project {
         eprintln!();
         eprintln!("Locked Packages:");
         for locked in unity.locked_packages() {
+            let held = if unity.is_held(locked.name()) {
+                " (held)"
+            } else {
+                ""
+            };
             if let Some(installed) = unity
                 .get_installed_package(locked.name())
                 .map(|x| x.version())
             {
                 eprintln!(
-                    "{package} version {version} with installed version {installed}",
+                    "{package} version {version} with installed version {installed}{held}",
                     package = locked.name(),
                     version = locked.version(),
                     installed = installed,
                 );
             } else {
                 eprintln!(
-                    "{package} version {version} not installed",
+                    "{package} version {version} not installed{held}",
                     package = locked.name(),
                     version = locked.version(),
                 );
@@ -90,6 +95,21 @@ impl Project {
                 );
             }
         }
+
+        let overrides = overrides_in_effect(unity);
+        if !overrides.is_empty() {
+            eprintln!();
+            eprintln!("Dependency Overrides:");
+            for x in overrides {
+                eprintln!(
+                    "{dependant} requires {dependency} {range} (declared: {declared})",
+                    dependant = x.dependant,
+                    dependency = x.dependency,
+                    range = x.range,
+                    declared = x.declared,
+                );
+            }
+        }
     }
 
     pub async fn version1(unity: &UnityProject) {
@@ -97,6 +117,8 @@ impl Project {
         struct Project<'a> {
             unity_version: Option<UnityVersion>,
             packages: &'a [PackageInfo<'a>],
+            // since 1.8.0
+            overrides: &'a [OverrideInfo<'a>],
         }
 
         #[derive(Serialize)]
@@ -155,15 +177,44 @@ impl Project {
             });
         }
 
+        let overrides = overrides_in_effect(unity);
+
         let project = Project {
             unity_version: unity.unity_version(),
             packages: packages.as_slice(),
+            overrides: overrides.as_slice(),
         };
 
         println!("{}", serde_json::to_string(&project).unwrap());
     }
 }
 
+#[derive(Serialize)]
+struct OverrideInfo<'a> {
+    dependant: &'a str,
+    dependency: &'a str,
+    declared: &'a VersionRange,
+    range: &'a VersionRange,
+}
+
+/// Collects dependency overrides which replace a range declared by an installed package.
+fn overrides_in_effect(unity: &UnityProject) -> Vec<OverrideInfo<'_>> {
+    let packages: HashMap<_, _> = unity.all_packages().map(|x| (x.name(), x)).collect();
+
+    unity
+        .dependency_overrides()
+        .filter_map(|(dependant, dependency, range)| {
+            let declared = packages.get(dependant)?.dependencies().get(dependency)?;
+            Some(OverrideInfo {
+                dependant,
+                dependency,
+                declared,
+                range,
+            })
+        })
+        .collect()
+}
+
 /// Show project information
 #[derive(Parser)]
 #[command(author, version)]
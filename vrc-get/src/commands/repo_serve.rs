@@ -0,0 +1,441 @@
+use crate::commands::ResultExt;
+use clap::Parser;
+use hyper::header::{HeaderValue, CONTENT_TYPE, ETAG, HOST, IF_NONE_MATCH};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{info, warn};
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use vrc_get_vpm::io::{DefaultProjectIo, IoTrait};
+use vrc_get_vpm::repository::{PackageZip, RepositoryBuilder};
+
+/// Serve package zips in the folder as a VPM repository over HTTP
+///
+/// `index.json` is generated from package zips in the folder for each request,
+/// so zips added to the folder are served without restarting.
+/// If the folder has `index.json`, packages are added to it so you can set name or id of the repository.
+/// Responses have ETag so vrc-get can use the cache if nothing is changed.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoServe {
+    /// Path to the folder with package zips
+    #[arg()]
+    dir: PathBuf,
+
+    /// The address to listen on. Use 0.0.0.0:<port> to share in the LAN
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    address: SocketAddr,
+
+    /// The name of the repository
+    #[arg(long)]
+    name: Option<String>,
+
+    /// The id of the repository
+    #[arg(long)]
+    id: Option<String>,
+}
+
+impl RepoServe {
+    pub async fn run(self) {
+        if !self.dir.is_dir() {
+            exit_with!("{} is not a folder", self.dir.display());
+        }
+
+        let server = Arc::new(RepositoryServer {
+            io: DefaultProjectIo::new(self.dir.clone().into_boxed_path()),
+            dir: self.dir.clone(),
+            address: self.address,
+            name: self.name,
+            id: self.id,
+            zips: Mutex::new(HashMap::new()),
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let server = server.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle(request).await) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&self.address)
+            .exit_context("binding address")
+            .serve(make_service);
+
+        info!(
+            "serving {} at http://{}/index.json",
+            self.dir.display(),
+            self.address
+        );
+
+        server.await.exit_context("serving repository");
+    }
+}
+
+struct CachedZip {
+    modified: SystemTime,
+    len: u64,
+    zip: Arc<PackageZip>,
+}
+
+struct RepositoryServer {
+    dir: PathBuf,
+    io: DefaultProjectIo,
+    address: SocketAddr,
+    name: Option<String>,
+    id: Option<String>,
+    // reading zips for each request is slow so we keep them until the file is changed
+    zips: Mutex<HashMap<String, CachedZip>>,
+}
+
+impl RepositoryServer {
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        // urls in index.json are percent-encoded so names with spaces or non-ASCII characters are encoded
+        let path = percent_decode_str(request.uri().path()).decode_utf8_lossy();
+        let path = path.trim_start_matches('/');
+
+        let response = if request.method() != Method::GET && request.method() != Method::HEAD {
+            status_response(StatusCode::METHOD_NOT_ALLOWED)
+        } else {
+            let base_url = request
+                .headers()
+                .get(HOST)
+                .and_then(|x| x.to_str().ok())
+                .map(|host| format!("http://{host}/"))
+                .unwrap_or_else(|| format!("http://{}/", self.address));
+
+            let content = match Url::parse(&base_url) {
+                Err(_) => Err(io::Error::new(io::ErrorKind::InvalidInput, "bad host")),
+                Ok(base_url) if path.is_empty() || path == "index.json" => {
+                    self.index_json(&base_url).await.map(Some)
+                }
+                Ok(_) => self.read_file(path).await,
+            };
+
+            match content {
+                Ok(Some(content)) => {
+                    content_response(&request, path, content, request.method() == Method::HEAD)
+                }
+                Ok(None) => status_response(StatusCode::NOT_FOUND),
+                Err(e) if e.kind() == io::ErrorKind::InvalidInput => {
+                    status_response(StatusCode::BAD_REQUEST)
+                }
+                Err(e) => {
+                    warn!("error serving {path}: {e}");
+                    status_response(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
+        };
+
+        info!(
+            "{} /{path}: {}",
+            request.method(),
+            response.status().as_u16()
+        );
+        response
+    }
+
+    async fn read_file(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        if !is_servable_name(name) {
+            return Ok(None);
+        }
+
+        match tokio::fs::read(self.dir.join(name)).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn index_json(&self, base_url: &Url) -> io::Result<Vec<u8>> {
+        let mut builder = match self.read_file("index.json").await? {
+            Some(json) => RepositoryBuilder::parse(&json)?,
+            None => RepositoryBuilder::new(),
+        };
+
+        builder.set_property("url", base_url.join("index.json").unwrap().as_str());
+        if let Some(name) = &self.name {
+            builder.set_property("name", name);
+        }
+        if let Some(id) = &self.id {
+            builder.set_property("id", id);
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        let mut zips = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(file_name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if file_name.ends_with(".zip") && entry.file_type().await?.is_file() {
+                zips.push((file_name, entry.metadata().await?));
+            }
+        }
+        zips.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (file_name, metadata) in zips {
+            let zip = match self.read_zip(&file_name, &metadata).await {
+                Ok(zip) => zip,
+                Err(e) => {
+                    warn!("skipping {file_name}: {e}");
+                    continue;
+                }
+            };
+            let Ok(url) = base_url.join(&file_name) else {
+                continue;
+            };
+            builder.add_package(&zip, &url);
+        }
+
+        builder.to_json()
+    }
+
+    async fn read_zip(
+        &self,
+        file_name: &str,
+        metadata: &std::fs::Metadata,
+    ) -> io::Result<Arc<PackageZip>> {
+        let modified = metadata.modified()?;
+        let len = metadata.len();
+
+        if let Some(cached) = self.zips.lock().unwrap().get(file_name) {
+            if cached.modified == modified && cached.len == len {
+                return Ok(cached.zip.clone());
+            }
+        }
+
+        let file = self.io.open(file_name.as_ref()).await?;
+        let zip = Arc::new(PackageZip::read(file).await?);
+
+        self.zips.lock().unwrap().insert(
+            file_name.to_owned(),
+            CachedZip {
+                modified,
+                len,
+                zip: zip.clone(),
+            },
+        );
+
+        Ok(zip)
+    }
+}
+
+/// Returns true if the file can be served. We only serve files directly in the folder.
+///
+/// `:` is rejected since `C:foo` is a path relative to the current folder of the drive on Windows.
+fn is_servable_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\', ':']) && !name.starts_with('.')
+}
+
+/// Returns true if `If-None-Match` header matches `etag`.
+///
+/// Weak comparison is used as specified for `If-None-Match`.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|x| {
+        let x = x.strip_prefix("W/").unwrap_or(x);
+        x == etag || x == "*"
+    })
+}
+
+fn content_response(
+    request: &Request<Body>,
+    path: &str,
+    content: Vec<u8>,
+    head: bool,
+) -> Response<Body> {
+    let etag = format!("\"{:x}\"", Sha256::digest(&content));
+
+    let matched = request
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| etag_matches(x, &etag));
+
+    let content_type = if path.is_empty() || path.ends_with(".json") {
+        "application/json"
+    } else if path.ends_with(".zip") {
+        "application/zip"
+    } else {
+        "application/octet-stream"
+    };
+
+    let mut response = if matched {
+        status_response(StatusCode::NOT_MODIFIED)
+    } else if head {
+        Response::new(Body::empty())
+    } else {
+        Response::new(Body::from(content))
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+    if !matched {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    response
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrc_get_vpm::package_zip::create_package_zip;
+
+    #[test]
+    fn servable_names() {
+        assert!(is_servable_name("index.json"));
+        assert!(is_servable_name("com.anatawa12.package-1.0.0.zip"));
+
+        assert!(!is_servable_name(""));
+        assert!(!is_servable_name("../secret"));
+        assert!(!is_servable_name("folder/file.zip"));
+        assert!(!is_servable_name("folder\\file.zip"));
+        assert!(!is_servable_name("..\\secret"));
+        assert!(!is_servable_name(".hidden"));
+        assert!(!is_servable_name("C:secret"));
+        assert!(!is_servable_name("C:\\secret"));
+    }
+
+    fn request(if_none_match: Option<&str>, method: Method) -> Request<Body> {
+        let mut request = Request::builder().method(method).uri("/index.json");
+        if let Some(if_none_match) = if_none_match {
+            request = request.header(IF_NONE_MATCH, if_none_match);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn etag() {
+        let content = b"{}".to_vec();
+        let etag = format!("\"{:x}\"", Sha256::digest(&content));
+
+        let response = content_response(
+            &request(None, Method::GET),
+            "index.json",
+            content.clone(),
+            false,
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+
+        for if_none_match in [
+            etag.clone(),
+            format!("W/{etag}"),
+            format!("\"other\", {etag}"),
+            "*".to_owned(),
+        ] {
+            let request = request(Some(&if_none_match), Method::GET);
+            let response = content_response(&request, "index.json", content.clone(), false);
+            assert_eq!(
+                response.status(),
+                StatusCode::NOT_MODIFIED,
+                "{if_none_match}"
+            );
+            assert_eq!(response.headers()[ETAG], etag.as_str());
+        }
+
+        let request = request(Some("\"other\""), Method::GET);
+        let response = content_response(&request, "index.json", content.clone(), false);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn refuse_paths_outside_folder() {
+        let root = std::env::temp_dir().join(format!("vrc-get-repo-serve-{}", std::process::id()));
+        let dir = root.join("repo");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(root.join("secret"), b"secret")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("file.txt"), b"content")
+            .await
+            .unwrap();
+
+        let server = RepositoryServer {
+            io: DefaultProjectIo::new(dir.clone().into_boxed_path()),
+            dir,
+            address: "127.0.0.1:8080".parse().unwrap(),
+            name: None,
+            id: None,
+            zips: Mutex::new(HashMap::new()),
+        };
+
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        assert_eq!(
+            server.handle(get("/file.txt")).await.status(),
+            StatusCode::OK
+        );
+        for path in [
+            "/../secret",
+            "/%2E%2E/secret",
+            "/..%2Fsecret",
+            "/..%5Csecret",
+            "/C:secret",
+        ] {
+            let status = server.handle(get(path)).await.status();
+            assert_eq!(status, StatusCode::NOT_FOUND, "{path}");
+        }
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn serve_zip_with_space() {
+        let root =
+            std::env::temp_dir().join(format!("vrc-get-repo-serve-space-{}", std::process::id()));
+        let dir = root.join("repo");
+        let package = root.join("package");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::create_dir_all(&package).await.unwrap();
+        tokio::fs::write(
+            package.join("package.json"),
+            br#"{"name":"com.anatawa12.package","version":"1.0.0"}"#,
+        )
+        .await
+        .unwrap();
+
+        let server = RepositoryServer {
+            io: DefaultProjectIo::new(dir.clone().into_boxed_path()),
+            dir,
+            address: "127.0.0.1:8080".parse().unwrap(),
+            name: None,
+            id: None,
+            zips: Mutex::new(HashMap::new()),
+        };
+        let file = (server.io.create("package 1.0.0.zip".as_ref()).await).unwrap();
+        let package_io = DefaultProjectIo::new(package.into_boxed_path());
+        create_package_zip(&package_io, file).await.unwrap();
+
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        let index = server.handle(get("/index.json")).await.into_body();
+        let index = hyper::body::to_bytes(index).await.unwrap();
+        let index = serde_json::from_slice::<serde_json::Value>(&index).unwrap();
+        let url = &index["packages"]["com.anatawa12.package"]["versions"]["1.0.0"]["url"];
+        let url = Url::parse(url.as_str().unwrap()).unwrap();
+        assert_eq!(url.path(), "/package%201.0.0.zip");
+
+        let response = server.handle(get(url.path())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/zip");
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use crate::commands::{save_env, Environment, ResultExt};
+use clap::{ArgAction, Parser, Subcommand};
+use reqwest::Url;
+use std::num::NonZeroU64;
+use std::time::Duration;
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+
+/// Show or change vrc-get specific settings
+///
+/// Those settings are saved in `vrc-get/settings.json` in the VCC settings folder and not used by VCC.
+#[derive(Subcommand)]
+#[command(author, version)]
+pub enum Settings {
+    RequireZipSha256(RequireZipSha256),
+    RepositoryTimeout(RepositoryTimeout),
+    UrlRewrite(UrlRewrite),
+}
+
+multi_command!(Settings is RequireZipSha256, RepositoryTimeout, UrlRewrite);
+
+async fn load_settings_env() -> Environment {
+    // remote repositories are not needed to change settings
+    let io = DefaultEnvironmentIo::new_default();
+    Environment::load(None, io)
+        .await
+        .exit_context("loading global config")
+}
+
+/// Show or change whether remote packages without `zipSHA256` in the repository are refused
+///
+/// Without value, the current value is shown.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RequireZipSha256 {
+    /// `true` to refuse packages without `zipSHA256`, `false` to install them without verification
+    #[arg(action = ArgAction::Set)]
+    value: Option<bool>,
+}
+
+impl RequireZipSha256 {
+    pub async fn run(self) {
+        let mut env = load_settings_env().await;
+
+        match self.value {
+            None => println!("{}", env.require_zip_sha256()),
+            Some(value) => {
+                env.set_require_zip_sha256(value);
+                save_env(&mut env).await;
+            }
+        }
+    }
+}
+
+/// Show or change the timeout for fetching each remote repository in seconds
+///
+/// Without value, the current value is shown.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepositoryTimeout {
+    /// The timeout in seconds
+    #[arg()]
+    seconds: Option<NonZeroU64>,
+
+    /// Reset the timeout to the default
+    #[arg(long, conflicts_with = "seconds")]
+    reset: bool,
+}
+
+impl RepositoryTimeout {
+    pub async fn run(self) {
+        let mut env = load_settings_env().await;
+
+        if self.reset {
+            env.set_repository_timeout(None);
+            save_env(&mut env).await;
+        } else if let Some(seconds) = self.seconds {
+            env.set_repository_timeout(Some(Duration::from_secs(seconds.get())));
+            save_env(&mut env).await;
+        } else {
+            println!("{}", env.repository_timeout().as_secs());
+        }
+    }
+}
+
+/// Show or change the prefix-based rewrites of package zip urls
+///
+/// Package zips are downloaded from the rewritten url first, then the original url.
+/// Without prefix, all rewrites are shown.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct UrlRewrite {
+    /// The prefix of package zip urls to rewrite, like `https://github.com/`
+    #[arg()]
+    prefix: Option<String>,
+
+    /// The url to replace the prefix with
+    #[arg()]
+    replacement: Option<String>,
+
+    /// Remove the rewrite of the prefix
+    #[arg(long, requires = "prefix", conflicts_with = "replacement")]
+    remove: bool,
+}
+
+impl UrlRewrite {
+    pub async fn run(self) {
+        let mut env = load_settings_env().await;
+
+        let Some(prefix) = self.prefix else {
+            for (prefix, replacement) in env.url_rewrites() {
+                println!("{prefix} -> {replacement}");
+            }
+            return;
+        };
+
+        if self.remove {
+            env.set_url_rewrite(&prefix, None);
+            save_env(&mut env).await;
+        } else if let Some(replacement) = self.replacement {
+            for url in [&prefix, &replacement] {
+                if let Err(e) = Url::parse(url) {
+                    exit_with!("invalid url '{url}': {e}");
+                }
+            }
+            env.set_url_rewrite(&prefix, Some(&replacement));
+            save_env(&mut env).await;
+        } else if let Some(replacement) = env.url_rewrites().get(prefix.as_str()) {
+            println!("{replacement}");
+        }
+    }
+}